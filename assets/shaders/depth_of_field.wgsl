@@ -0,0 +1,56 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+#ifdef MULTISAMPLED
+@group(0) @binding(2) var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(2) var depth_texture: texture_depth_2d;
+#endif
+
+struct DepthOfField {
+    focus_distance: f32,
+    focus_range: f32,
+    max_blur: f32,
+    near: f32,
+}
+@group(0) @binding(3) var<uniform> settings: DepthOfField;
+
+const TAPS: u32 = 32u;
+const GOLDEN_ANGLE: f32 = 2.3999632;
+
+// How out of focus the scene is at `uv`, from 0 sharp to 1 fully blurred.
+fn blur_at(uv: vec2<f32>) -> f32 {
+    let size = vec2<f32>(textureDimensions(depth_texture));
+    let coords = vec2<i32>(clamp(uv * size, vec2<f32>(0.0), size - 1.0));
+    // The first sample is enough to go on with MSAA.
+    let depth = textureLoad(depth_texture, coords, 0);
+    // Depth is reversed and the far plane infinite: it's near / distance,
+    // and nothing drawn (the sky) is at zero.
+    let distance = settings.near / max(depth, 1e-6);
+    return clamp(abs(distance - settings.focus_distance) / settings.focus_range, 0.0, 1.0);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let centre = textureSampleLevel(screen_texture, texture_sampler, in.uv, 0.0);
+    let radius = blur_at(in.uv) * settings.max_blur;
+    let size = vec2<f32>(textureDimensions(screen_texture));
+    // Keeps the disc round on a wide screen.
+    let aspect = vec2<f32>(size.y / size.x, 1.0);
+
+    var total = centre.rgb;
+    var weight = 1.0;
+    for (var i = 1u; i < TAPS; i += 1u) {
+        // Spread evenly over the disc in a golden-angle spiral.
+        let angle = f32(i) * GOLDEN_ANGLE;
+        let offset = vec2<f32>(cos(angle), sin(angle)) * sqrt(f32(i) / f32(TAPS)) * radius;
+        let uv = in.uv + offset * aspect;
+        // Taps that are themselves in focus don't bleed into the blur
+        // around them.
+        let tap_weight = blur_at(uv);
+        total += textureSampleLevel(screen_texture, texture_sampler, uv, 0.0).rgb * tap_weight;
+        weight += tap_weight;
+    }
+    return vec4<f32>(total / weight, centre.a);
+}
@@ -0,0 +1,30 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+
+struct PostProcessSettings {
+    vignette: f32,
+    vignette_radius: f32,
+    aberration: f32,
+    saturation: f32,
+}
+@group(0) @binding(2) var<uniform> settings: PostProcessSettings;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Split the channels outwards from the centre of the screen.
+    let offset = (in.uv - vec2<f32>(0.5)) * settings.aberration;
+    let centre = textureSample(screen_texture, texture_sampler, in.uv);
+    let red = textureSample(screen_texture, texture_sampler, in.uv + offset).r;
+    let blue = textureSample(screen_texture, texture_sampler, in.uv - offset).b;
+    var color = vec3<f32>(red, centre.g, blue);
+
+    let luma = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    color = mix(vec3<f32>(luma), color, settings.saturation);
+
+    let distance = length(in.uv - vec2<f32>(0.5)) * 1.414;
+    color *= 1.0 - settings.vignette * smoothstep(settings.vignette_radius, 1.0, distance);
+
+    return vec4<f32>(color, centre.a);
+}
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, reflect::TypePath};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    character::{AnimationState, CharacterState, Health, Opponent},
+    input::ControlInput,
+    moves::{MoveId, MovePhase},
+    rng::GameRng,
+    throws::THROW_RANGE,
+    SIMULATION_HZ,
+};
+
+// Per-personality multipliers on the utility of each action. Only the ratios
+// matter; the situational part of each score lives in `choose_action`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct AiProfile {
+    pub name: String,
+    pub approach: f32,
+    pub retreat: f32,
+    pub block: f32,
+    pub attack: f32,
+    pub wait: f32,
+    pub think_interval: f32,
+    pub attack_range: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AiAction {
+    #[default]
+    Wait,
+    Approach,
+    Retreat,
+    Block,
+    Punch,
+    Kick,
+    ShoulderCharge,
+    Throw,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    // How many simulation ticks old the AI's view of the fight is.
+    pub fn reaction_ticks(&self) -> usize {
+        match self {
+            Difficulty::Easy => 24,
+            Difficulty::Normal => 14,
+            Difficulty::Hard => 8,
+        }
+    }
+
+    // Random utility added to every action, so weaker AIs pick sloppily.
+    pub fn noise(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 0.35,
+            Difficulty::Hard => 0.1,
+        }
+    }
+
+    // Chance of breaking a throw once the AI has seen it. Easy AIs react too
+    // slowly to get inside the window at all.
+    pub fn tech_chance(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.0,
+            Difficulty::Normal => 0.4,
+            Difficulty::Hard => 0.75,
+        }
+    }
+}
+
+// What the AI believes the fight looks like. Filled from live state and only
+// read back `reaction_ticks` later.
+#[derive(Clone, Copy, Debug)]
+pub struct Perception {
+    pub offset: f32,
+    pub opponent_state: AnimationState,
+    pub opponent_phase: Option<MovePhase>,
+    pub health: f32,
+}
+
+#[derive(Component, Clone)]
+pub struct AiController {
+    pub profile: Handle<AiProfile>,
+    pub difficulty: Difficulty,
+    pub think: Timer,
+    pub action: AiAction,
+    pub perceptions: VecDeque<Perception>,
+}
+
+impl AiController {
+    pub fn new(profile: Handle<AiProfile>, difficulty: Difficulty) -> Self {
+        AiController {
+            profile,
+            difficulty,
+            think: Timer::from_seconds(0.0, TimerMode::Once),
+            action: AiAction::Wait,
+            perceptions: VecDeque::new(),
+        }
+    }
+
+    fn perceive(&mut self, perception: Perception) -> Option<Perception> {
+        self.perceptions.push_back(perception);
+        if self.perceptions.len() <= self.difficulty.reaction_ticks() {
+            return None;
+        }
+        self.perceptions.pop_front()
+    }
+}
+
+// Scores every action for the (delayed) situation and takes the best. The
+// profile weights scale each score so personalities lean different ways.
+fn choose_action(
+    profile: &AiProfile,
+    perception: &Perception,
+    noise: f32,
+    rng: &mut impl Rng,
+) -> AiAction {
+    let distance = perception.offset.abs();
+    let range = profile.attack_range;
+    let punch_range = range * 0.8;
+    // Only attacks that can still hit are worth blocking, and guard breaks
+    // can't be blocked at all.
+    let opponent_attacking = matches!(
+        perception.opponent_phase,
+        Some(MovePhase::Startup | MovePhase::Active)
+    ) && MoveId::from_state(perception.opponent_state)
+        .is_some_and(|move_id| !move_id.data().guard_break);
+    // A whiffed move leaves the opponent open until it recovers.
+    let punish = if perception.opponent_phase == Some(MovePhase::Recovery) {
+        1.5
+    } else {
+        1.0
+    };
+
+    let scores = [
+        (AiAction::Wait, profile.wait * 0.3),
+        (
+            AiAction::Approach,
+            profile.approach * ((distance - punch_range) / 3.0).clamp(0.0, 1.0),
+        ),
+        (
+            AiAction::Retreat,
+            profile.retreat
+                * if distance < range * 0.6 { 0.8 } else { 0.1 }
+                * (1.5 - perception.health),
+        ),
+        (
+            AiAction::Block,
+            profile.block
+                * if opponent_attacking && distance < range + 0.5 {
+                    1.0
+                } else {
+                    0.05
+                },
+        ),
+        (
+            AiAction::Punch,
+            profile.attack * punish * if distance <= punch_range { 0.9 } else { 0.0 },
+        ),
+        (
+            AiAction::Kick,
+            profile.attack
+                * punish
+                * if distance <= range && distance > punch_range * 0.6 {
+                    0.8
+                } else {
+                    0.0
+                },
+        ),
+        // Throws and the shoulder charge are how a guard gets opened.
+        (
+            AiAction::Throw,
+            profile.attack
+                * if perception.opponent_state == AnimationState::Blocking
+                    && distance <= THROW_RANGE
+                {
+                    1.1
+                } else {
+                    0.0
+                },
+        ),
+        // Only worth the slow startup against someone sitting in their guard.
+        (
+            AiAction::ShoulderCharge,
+            profile.attack
+                * if perception.opponent_state == AnimationState::Blocking
+                    && distance <= punch_range
+                {
+                    1.0
+                } else {
+                    0.0
+                },
+        ),
+    ];
+
+    scores
+        .into_iter()
+        .map(|(action, score)| (action, score + rng.gen::<f32>() * noise))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(AiAction::Wait, |(action, _)| action)
+}
+
+pub fn run_ai(
+    time: Res<Time>,
+    profiles: Res<Assets<AiProfile>>,
+    mut rng: ResMut<GameRng>,
+    mut ais: Query<(
+        Entity,
+        &mut AiController,
+        &mut ControlInput,
+        &Transform,
+        &Opponent,
+        &Health,
+    )>,
+    fighters: Query<(&Transform, &CharacterState)>,
+) {
+    for (entity, mut ai, mut input, transform, opponent, health) in ais.iter_mut() {
+        let Some(profile) = profiles.get(&ai.profile) else {
+            continue;
+        };
+        let Ok((opponent, opponent_state)) = fighters.get(opponent.0) else {
+            continue;
+        };
+
+        let perceived = ai.perceive(Perception {
+            offset: opponent.translation.x - transform.translation.x,
+            opponent_state: opponent_state.player_state,
+            opponent_phase: opponent_state.move_phase,
+            health: health.current / health.max,
+        });
+        // A grab is reacted to on the tick it's seen, like anything else, and
+        // there's nothing else to do while held.
+        if let Some(grab) = fighters
+            .get(entity)
+            .ok()
+            .and_then(|(_, state)| state.grabbed.as_ref())
+        {
+            let seen_for = (grab.window.elapsed_secs() * SIMULATION_HZ as f32).round() as usize;
+            if seen_for == ai.difficulty.reaction_ticks()
+                && rng.simulation().gen_bool(ai.difficulty.tech_chance())
+            {
+                input.throw = true;
+            }
+            continue;
+        }
+        if !ai.think.tick(time.delta()).finished() {
+            continue;
+        }
+        let Some(perceived) = perceived else {
+            continue;
+        };
+        ai.think = Timer::from_seconds(profile.think_interval, TimerMode::Once);
+        ai.action = choose_action(profile, &perceived, ai.difficulty.noise(), rng.simulation());
+
+        let towards_right = perceived.offset > 0.0;
+        *input = ControlInput::default();
+        match ai.action {
+            AiAction::Wait => {}
+            AiAction::Approach => {
+                input.right = towards_right;
+                input.left = !towards_right;
+            }
+            AiAction::Retreat => {
+                input.right = !towards_right;
+                input.left = towards_right;
+            }
+            AiAction::Block => input.block = true,
+            AiAction::Punch => input.punch = true,
+            AiAction::Kick => input.kick = true,
+            AiAction::ShoulderCharge => {
+                input.punch = true;
+                input.kick = true;
+            }
+            AiAction::Throw => input.throw = true,
+        }
+    }
+}
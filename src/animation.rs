@@ -0,0 +1,683 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{
+    animation::{EntityPath, Keyframes, VariableCurve},
+    math::Affine3A,
+    prelude::*,
+    scene::SceneInstanceReady,
+};
+use bevy_rapier3d::prelude::KinematicCharacterController;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    asset_cache::{character_asset, AssetCache},
+    character::{AnimationState, Character, CharacterState, Opponent, WallHit},
+    combat::{HitLanded, HurtRegion, HEAVY_HIT_DAMAGE},
+    moves::{AttackLevel, MoveId},
+    rng::GameRng,
+    roster::CharacterDef,
+    sfx::{PlaySfx, Whoosh, Whooshes},
+    throws::{ThrowLanded, ThrowTeched},
+};
+
+pub const HEAD_SNAP_DURATION: f32 = 0.35;
+pub const HEAD_SNAP_ANGLE: f32 = 0.6;
+// Attack whooshes play at a slightly different pitch each time so repeats
+// don't sound canned.
+const WHOOSH_PITCH_VARIATION: f32 = 0.08;
+// Volume of the sound a graph state starts with.
+const STATE_SFX_VOLUME: f32 = 0.4;
+const WHOOSH_VOLUME: f32 = 0.3;
+// Impacts play the attacker's sound for the move, louder the more damage it
+// did. Blocked ones are quieter and higher, like a slap on a guard.
+const IMPACT_VOLUME: f32 = 0.05;
+const MAX_IMPACT_VOLUME: f32 = 0.8;
+const BLOCKED_IMPACT_VOLUME: f32 = 0.25;
+const BLOCKED_IMPACT_SPEED: f32 = 1.3;
+const PAIN_VOLUME: f32 = 0.5;
+// A fighter hitting the wall or thrown to the floor is their own kick sound
+// slowed down to a thud.
+const WALL_VOLUME: f32 = 0.6;
+const WALL_SPEED: f32 = 0.6;
+// Fighters turn their heads to follow each other, within what a neck can do.
+// The spine takes a share of the turn so it doesn't all happen at the neck.
+const LOOK_MAX_YAW: f32 = 0.7;
+const LOOK_MAX_PITCH: f32 = 0.4;
+const LOOK_SPINE_SHARE: f32 = 0.3;
+// How quickly the gaze catches up, per second.
+const LOOK_SPEED: f32 = 8.0;
+
+// How a character animates: the clip each state plays, at what speed and
+// whether it loops, and how long the blend into it takes. Transitions listed
+// here override the default blend.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationGraph {
+    pub blend: f32,
+    pub states: HashMap<AnimationState, AnimationNode>,
+    #[serde(default)]
+    pub transitions: Vec<AnimationTransition>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationNode {
+    // One of the character's animations, by its name in `animations`.
+    pub clip: String,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub looping: bool,
+    // One of the character's sounds, played as the state starts.
+    #[serde(default)]
+    pub sfx: Option<String>,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationTransition {
+    pub from: AnimationState,
+    pub to: AnimationState,
+    pub blend: f32,
+}
+
+impl AnimationGraph {
+    // Seconds the blend from one state into another takes.
+    pub fn blend(&self, from: AnimationState, to: AnimationState) -> f32 {
+        self.transitions
+            .iter()
+            .find(|transition| transition.from == from && transition.to == to)
+            .map_or(self.blend, |transition| transition.blend)
+    }
+
+    // Seconds the state's clip takes to play through once at its speed, once
+    // the clip has loaded.
+    pub fn clip_duration(
+        &self,
+        state: AnimationState,
+        animations: &FighterAnimations,
+        clips: &Assets<AnimationClip>,
+    ) -> Option<f32> {
+        let node = self.states.get(&state)?;
+        let clip = clips.get(animations.clip(&node.clip)?)?;
+        (node.speed > 0.0).then(|| clip.duration() / node.speed)
+    }
+}
+
+#[derive(Component)]
+pub struct FighterAnimations {
+    pub idle: Handle<AnimationClip>,
+    pub run_forwards: Handle<AnimationClip>,
+    pub walk_backwards: Handle<AnimationClip>,
+    pub punch: Handle<AnimationClip>,
+    pub kick: Handle<AnimationClip>,
+    pub block: Handle<AnimationClip>,
+    pub crouch: Handle<AnimationClip>,
+    pub jump: Handle<AnimationClip>,
+}
+
+impl FighterAnimations {
+    // The clip a character's animation graph calls `name`.
+    pub fn clip(&self, name: &str) -> Option<&Handle<AnimationClip>> {
+        match name {
+            "idle" => Some(&self.idle),
+            "run_forwards" => Some(&self.run_forwards),
+            "walk_backwards" => Some(&self.walk_backwards),
+            "punch" => Some(&self.punch),
+            "kick" => Some(&self.kick),
+            "block" => Some(&self.block),
+            "crouch" => Some(&self.crouch),
+            "jump" => Some(&self.jump),
+            _ => None,
+        }
+    }
+
+    pub fn cached(cache: &AssetCache, id: &str) -> Self {
+        let clip = |name: &str| cache.get(&character_asset(id, &format!("anim.{name}")));
+        FighterAnimations {
+            idle: clip("idle"),
+            run_forwards: clip("run_forwards"),
+            walk_backwards: clip("walk_backwards"),
+            punch: clip("punch"),
+            kick: clip("kick"),
+            block: clip("block"),
+            crouch: clip("crouch"),
+            jump: clip("jump"),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct FighterSfx {
+    pub punch: Handle<AudioSource>,
+    pub kick: Handle<AudioSource>,
+    pub pain: Handle<AudioSource>,
+}
+
+impl FighterSfx {
+    // The sound a character's animation graph calls `name`.
+    pub fn sound(&self, name: &str) -> Option<&Handle<AudioSource>> {
+        match name {
+            "punch" => Some(&self.punch),
+            "kick" => Some(&self.kick),
+            "pain" => Some(&self.pain),
+            _ => None,
+        }
+    }
+
+    pub fn cached(cache: &AssetCache, id: &str) -> Self {
+        let sound = |name: &str| cache.get(&character_asset(id, &format!("sfx.{name}")));
+        FighterSfx {
+            punch: sound("punch"),
+            kick: sound("kick"),
+            pain: sound("pain"),
+        }
+    }
+}
+
+pub fn setup_scene_once_loaded(
+    fighters: Query<&FighterAnimations>,
+    parents: Query<&Parent>,
+    mut animation_players: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+) {
+    for (entity, mut animation_player) in animation_players.iter_mut() {
+        let Some(animations) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| fighters.get(ancestor).ok())
+        else {
+            continue;
+        };
+        animation_player.play(animations.idle.clone_weak()).repeat();
+    }
+}
+
+fn whoosh_speed(rng: &mut GameRng) -> f32 {
+    1.0 + rng
+        .cosmetic()
+        .gen_range(-WHOOSH_PITCH_VARIATION..WHOOSH_PITCH_VARIATION)
+}
+
+// Starting an attack plays its whoosh, whether or not it goes on to land;
+// the impact only sounds once it does.
+#[allow(clippy::too_many_arguments)]
+pub fn process_animation(
+    mut rng: ResMut<GameRng>,
+    whooshes: Res<Whooshes>,
+    mut sounds: EventWriter<PlaySfx>,
+    mut whoosh_sounds: EventWriter<PlaySfx<Whoosh>>,
+    mut playing: Local<HashMap<Entity, AnimationState>>,
+    characters: Res<Assets<CharacterDef>>,
+    mut animation_players: Query<(Entity, &mut AnimationPlayer)>,
+    parent_query: Query<&Parent>,
+    character_state: Query<(&CharacterState, &Character, &FighterAnimations, &FighterSfx)>,
+) {
+    for (entity, mut animation_player) in animation_players.iter_mut() {
+        let Some(fighter) = parent_query
+            .iter_ancestors(entity)
+            .find(|ancestor| character_state.contains(*ancestor))
+        else {
+            continue;
+        };
+        let Ok((character_state, character, animations, sfx)) = character_state.get(fighter) else {
+            continue;
+        };
+        let Some(graph) = characters.get(&character.0).map(|def| &def.animation_graph) else {
+            continue;
+        };
+        let current = playing.entry(fighter).or_default();
+        if character_state.player_state == *current {
+            continue;
+        }
+        let from = std::mem::replace(current, character_state.player_state);
+        let to = character_state.player_state;
+        if let Some(move_id) = MoveId::from_state(to) {
+            let whoosh = match move_id.data().level {
+                AttackLevel::Heavy => &whooshes.heavy,
+                AttackLevel::Light | AttackLevel::Medium => &whooshes.light,
+            };
+            whoosh_sounds.send(
+                PlaySfx::new(whoosh.clone(), WHOOSH_VOLUME).with_speed(whoosh_speed(&mut rng)),
+            );
+        }
+
+        let Some(node) = graph.states.get(&to) else {
+            continue;
+        };
+        let Some(clip) = animations.clip(&node.clip) else {
+            warn!("no animation named {}, {to:?} won't play", node.clip);
+            continue;
+        };
+        let blend = Duration::from_secs_f32(graph.blend(from, to));
+        animation_player
+            .play_with_transition(clip.clone(), blend)
+            .set_speed(node.speed);
+        if node.looping {
+            animation_player.repeat();
+        }
+        if let Some(sound) = node.sfx.as_deref().and_then(|sound| sfx.sound(sound)) {
+            sounds.send(
+                PlaySfx::new(sound.clone(), STATE_SFX_VOLUME).with_speed(whoosh_speed(&mut rng)),
+            );
+        }
+    }
+}
+
+pub fn play_impact_sounds(
+    mut hits: EventReader<HitLanded>,
+    mut sounds: EventWriter<PlaySfx>,
+    fighters: Query<&FighterSfx>,
+) {
+    for hit in hits.iter() {
+        let Ok(attacker) = fighters.get(hit.attacker) else {
+            continue;
+        };
+        let impact = match hit.move_id {
+            MoveId::Punch | MoveId::Elbow => &attacker.punch,
+            MoveId::Kick | MoveId::ShoulderCharge => &attacker.kick,
+        };
+        let volume = (hit.damage * IMPACT_VOLUME).min(MAX_IMPACT_VOLUME);
+        sounds.send(if hit.blocked {
+            PlaySfx::new(impact.clone(), BLOCKED_IMPACT_VOLUME).with_speed(BLOCKED_IMPACT_SPEED)
+        } else {
+            PlaySfx::new(impact.clone(), volume)
+        });
+        if !hit.blocked && hit.damage >= HEAVY_HIT_DAMAGE {
+            if let Ok(defender) = fighters.get(hit.defender) {
+                sounds.send(PlaySfx::new(defender.pain.clone(), PAIN_VOLUME));
+            }
+        }
+    }
+}
+
+// A splat against the wall hurts more than a bounce off it.
+pub fn play_wall_sounds(
+    mut wall_hits: EventReader<WallHit>,
+    mut sounds: EventWriter<PlaySfx>,
+    fighters: Query<&FighterSfx>,
+) {
+    for wall_hit in wall_hits.iter() {
+        let Ok(fighter) = fighters.get(wall_hit.fighter) else {
+            continue;
+        };
+        sounds.send(PlaySfx::new(fighter.kick.clone(), WALL_VOLUME).with_speed(WALL_SPEED));
+        if wall_hit.splat {
+            sounds.send(PlaySfx::new(fighter.pain.clone(), PAIN_VOLUME));
+        }
+    }
+}
+
+// A thrown fighter lands with the same thud as on a wall. Breaking out of a
+// throw sounds like both fighters slapping each other's guard away.
+pub fn play_throw_sounds(
+    mut landed: EventReader<ThrowLanded>,
+    mut teched: EventReader<ThrowTeched>,
+    mut sounds: EventWriter<PlaySfx>,
+    fighters: Query<&FighterSfx>,
+) {
+    for throw in landed.iter() {
+        let Ok(victim) = fighters.get(throw.victim) else {
+            continue;
+        };
+        sounds.send(PlaySfx::new(victim.kick.clone(), WALL_VOLUME).with_speed(WALL_SPEED));
+        sounds.send(PlaySfx::new(victim.pain.clone(), PAIN_VOLUME));
+    }
+    for tech in teched.iter() {
+        for fighter in fighters.iter_many(tech.fighters) {
+            sounds.send(
+                PlaySfx::new(fighter.punch.clone(), BLOCKED_IMPACT_VOLUME)
+                    .with_speed(BLOCKED_IMPACT_SPEED),
+            );
+        }
+    }
+}
+
+pub fn start_head_snaps(mut commands: Commands, mut hits: EventReader<HitLanded>) {
+    for hit in hits.iter() {
+        if hit.region == HurtRegion::Head && !hit.blocked {
+            commands.entity(hit.defender).insert(HeadSnap {
+                timer: Timer::from_seconds(HEAD_SNAP_DURATION, TimerMode::Once),
+            });
+        }
+    }
+}
+
+// The bone a fighter's attack clips carry, as it sat in the bind pose, and how
+// far along the fight axis the current attack had taken it last frame.
+#[derive(Component)]
+pub struct RootMotion {
+    bone: Entity,
+    rest: Vec3,
+    travelled: Option<f32>,
+}
+
+pub fn find_root_bones(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    characters: Res<Assets<CharacterDef>>,
+    fighters: Query<&Character>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    transforms: Query<&Transform>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        let Some(root_bone) = fighters
+            .get(*fighter)
+            .ok()
+            .and_then(|character| characters.get(&character.0))
+            .and_then(|def| def.root_bone.as_deref())
+        else {
+            continue;
+        };
+        let bone = children
+            .iter_descendants(*fighter)
+            .find(|bone| names.get(*bone).is_ok_and(|name| name.as_str() == root_bone));
+        let Some((bone, rest)) = bone.and_then(|bone| Some((bone, transforms.get(bone).ok()?)))
+        else {
+            warn!("no root bone named {root_bone}, attacks will play in place");
+            continue;
+        };
+        commands.entity(*fighter).insert(RootMotion {
+            bone,
+            rest: rest.translation,
+            travelled: None,
+        });
+    }
+}
+
+// Takes whatever distance along the fight axis of `space` the root bone has
+// moved from where it rests back off it, and returns it.
+fn take_root_travel(space: Affine3A, rest: Vec3, bone: &mut Transform) -> f32 {
+    let travel = space.transform_vector3(bone.translation - rest).x;
+    bone.translation -= space.inverse().transform_vector3(Vec3::X * travel);
+    travel
+}
+
+// Runs between the clips being sampled and the pose being propagated. Whatever
+// distance an attack has carried the root bone along the fight axis is taken
+// back off the bone and given to the fighter's controller, so the step-in is
+// real movement that walls and the other fighter can stop on the next tick.
+pub fn extract_root_motion(
+    mut fighters: Query<(&CharacterState, &mut RootMotion, &mut KinematicCharacterController)>,
+    parents: Query<&Parent>,
+    globals: Query<&GlobalTransform>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (state, mut root, mut controller) in fighters.iter_mut() {
+        if MoveId::from_state(state.player_state).is_none() {
+            root.travelled = None;
+            continue;
+        }
+        let Some(space) = parents
+            .get(root.bone)
+            .ok()
+            .and_then(|parent| globals.get(parent.get()).ok())
+        else {
+            continue;
+        };
+        let Ok(mut bone) = transforms.get_mut(root.bone) else {
+            continue;
+        };
+        let travel = take_root_travel(space.affine(), root.rest, &mut bone);
+        let moved = travel - root.travelled.unwrap_or(travel);
+        root.travelled = Some(travel);
+        controller.translation = Some(controller.translation.unwrap_or_default() + Vec3::X * moved);
+    }
+}
+
+// Where a curve has its bone `seconds` in, between the keyframes either side.
+fn sample_curve(curve: &VariableCurve, seconds: f32, transform: &mut Transform) {
+    let stamps = &curve.keyframe_timestamps;
+    let Some(last) = stamps.len().checked_sub(1) else {
+        return;
+    };
+    let next = stamps.partition_point(|stamp| *stamp <= seconds).min(last);
+    let previous = next.saturating_sub(1);
+    let span = stamps[next] - stamps[previous];
+    let lerp = if span > 0.0 {
+        ((seconds - stamps[previous]) / span).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    match &curve.keyframes {
+        Keyframes::Rotation(keys) => transform.rotation = keys[previous].slerp(keys[next], lerp),
+        Keyframes::Translation(keys) => {
+            transform.translation = keys[previous].lerp(keys[next], lerp)
+        }
+        Keyframes::Scale(keys) => transform.scale = keys[previous].lerp(keys[next], lerp),
+        // Morph weights don't move any limbs.
+        _ => {}
+    }
+}
+
+// Poses `bone` and everything under it from the clip, `path` being the names
+// down to it from the animation player, the way the clip addresses bones.
+fn pose_bones(
+    clip: &AnimationClip,
+    seconds: f32,
+    bone: Entity,
+    path: &mut EntityPath,
+    (children, names): (&Query<&Children>, &Query<&Name>),
+    bones: &mut Query<&mut Transform>,
+) {
+    if let (Some(curves), Ok(mut transform)) = (clip.get_curves_by_path(path), bones.get_mut(bone))
+    {
+        for curve in curves {
+            sample_curve(curve, seconds, &mut transform);
+        }
+    }
+    let Ok(under) = children.get(bone) else {
+        return;
+    };
+    for &child in under.iter() {
+        let Ok(name) = names.get(child) else {
+            continue;
+        };
+        path.parts.push(name.clone());
+        pose_bones(clip, seconds, child, path, (children, names), bones);
+        path.parts.pop();
+    }
+}
+
+// Runs on the tick before physics, the way headless runs pose their proxy
+// limbs: each fighter's bones go where its state's clip has them this tick,
+// rather than wherever the last rendered frame left them, so hits land on the
+// same ticks at any frame rate and after any seek. Timed states are as far in
+// as their timer, looping ones at their start and the rest held at their end.
+// Blends, the leg layer and the head turns are left to the screen, which the
+// animation player poses again once the tick is done.
+#[allow(clippy::too_many_arguments)]
+pub fn pose_skeletons_on_tick(
+    characters: Res<Assets<CharacterDef>>,
+    clips: Res<Assets<AnimationClip>>,
+    fighters: Query<(
+        &CharacterState,
+        &Character,
+        &FighterAnimations,
+        Option<&RootMotion>,
+    )>,
+    players: Query<(Entity, &Name), With<AnimationPlayer>>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    globals: Query<&GlobalTransform>,
+    mut bones: Query<&mut Transform>,
+) {
+    for (player, name) in players.iter() {
+        let Some((state, character, animations, root)) = parents
+            .iter_ancestors(player)
+            .find_map(|ancestor| fighters.get(ancestor).ok())
+        else {
+            continue;
+        };
+        let Some(node) = characters
+            .get(&character.0)
+            .and_then(|def| def.animation_graph.states.get(&state.player_state))
+        else {
+            continue;
+        };
+        let Some(clip) = animations.clip(&node.clip).and_then(|clip| clips.get(clip)) else {
+            continue;
+        };
+        let duration = clip.duration();
+        let seconds = match &state.current_animation_timer {
+            Some(timer) => timer.elapsed_secs() * node.speed,
+            None if node.looping => 0.0,
+            None => duration,
+        };
+        let seconds = if node.looping && duration > 0.0 {
+            seconds % duration
+        } else {
+            seconds.min(duration)
+        };
+        let mut path = EntityPath {
+            parts: vec![name.clone()],
+        };
+        pose_bones(
+            clip,
+            seconds,
+            player,
+            &mut path,
+            (&children, &names),
+            &mut bones,
+        );
+
+        // Attacks step in by moving the fighter, not the root bone.
+        let Some(root) = root.filter(|_| MoveId::from_state(state.player_state).is_some()) else {
+            continue;
+        };
+        let space = parents
+            .get(root.bone)
+            .ok()
+            .and_then(|parent| globals.get(parent.get()).ok());
+        if let (Some(space), Ok(mut bone)) = (space, bones.get_mut(root.bone)) {
+            take_root_travel(space.affine(), root.rest, &mut bone);
+        }
+    }
+}
+
+pub fn apply_head_snaps(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fighters: Query<(Entity, &mut HeadSnap)>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (fighter, mut snap) in fighters.iter_mut() {
+        if snap.timer.tick(time.delta()).finished() {
+            commands.entity(fighter).remove::<HeadSnap>();
+            continue;
+        }
+        // Whip back fast, then ease into the clip again.
+        let t = snap.timer.percent();
+        let weight = (t * std::f32::consts::PI).sin() * (1.0 - t);
+        for bone in children.iter_descendants(fighter) {
+            if names.get(bone).map_or(false, |name| name.as_str() == "head") {
+                if let Ok(mut transform) = transforms.get_mut(bone) {
+                    transform.rotate_local_x(-HEAD_SNAP_ANGLE * weight);
+                }
+            }
+        }
+    }
+}
+
+// The bones that turn to follow the opponent, and how far they're turned
+// now: yaw, then pitch, relative to the way the fighter faces.
+#[derive(Component)]
+pub struct LookAt {
+    head: Entity,
+    spine: Option<Entity>,
+    aim: Vec2,
+}
+
+pub fn find_look_bones(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    characters: Res<Assets<CharacterDef>>,
+    fighters: Query<&Character>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        let Some(def) = fighters
+            .get(*fighter)
+            .ok()
+            .and_then(|character| characters.get(&character.0))
+        else {
+            continue;
+        };
+        let Some(head_bone) = def.head_bone.as_deref() else {
+            continue;
+        };
+        let find = |bone: &str| {
+            children
+                .iter_descendants(*fighter)
+                .find(|entity| names.get(*entity).is_ok_and(|name| name.as_str() == bone))
+        };
+        let Some(head) = find(head_bone) else {
+            warn!("no head bone named {head_bone}, fighter won't track the opponent");
+            continue;
+        };
+        commands.entity(*fighter).insert(LookAt {
+            head,
+            spine: def.spine_bone.as_deref().and_then(find),
+            aim: Vec2::ZERO,
+        });
+    }
+}
+
+// Layered over whatever clip is playing: the spine and head are turned
+// towards the opponent's head on top of the pose the clip set this frame.
+pub fn apply_look_at(
+    time: Res<Time>,
+    mut fighters: Query<(&mut LookAt, &GlobalTransform, &Opponent)>,
+    looks: Query<&LookAt>,
+    parents: Query<&Parent>,
+    globals: Query<&GlobalTransform>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let catch_up = 1.0 - (-LOOK_SPEED * time.delta_seconds()).exp();
+    for (mut look, fighter, opponent) in fighters.iter_mut() {
+        let target = looks
+            .get(opponent.0)
+            .ok()
+            .and_then(|other| globals.get(other.head).ok())
+            .or_else(|| globals.get(opponent.0).ok());
+        let (Some(target), Ok(head)) = (target, globals.get(look.head)) else {
+            continue;
+        };
+        let facing = fighter.to_scale_rotation_translation().1;
+        let direction = facing.inverse() * (target.translation() - head.translation());
+        let Some(direction) = direction.try_normalize() else {
+            continue;
+        };
+        let wanted = Vec2::new(
+            direction.x.atan2(direction.z).clamp(-LOOK_MAX_YAW, LOOK_MAX_YAW),
+            (-direction.y.asin()).clamp(-LOOK_MAX_PITCH, LOOK_MAX_PITCH),
+        );
+        look.aim += (wanted - look.aim) * catch_up;
+
+        let spine_share = if look.spine.is_some() { LOOK_SPINE_SHARE } else { 0.0 };
+        for (bone, share) in [(look.spine, spine_share), (Some(look.head), 1.0 - spine_share)] {
+            let Some(bone) = bone else {
+                continue;
+            };
+            let Some(parent) = parents
+                .get(bone)
+                .ok()
+                .and_then(|parent| globals.get(parent.get()).ok())
+            else {
+                continue;
+            };
+            let turn = Quat::from_rotation_y(look.aim.x * share)
+                * Quat::from_rotation_x(look.aim.y * share);
+            let turn = facing * turn * facing.inverse();
+            let parent = parent.to_scale_rotation_translation().1;
+            if let Ok(mut transform) = transforms.get_mut(bone) {
+                transform.rotation = parent.inverse() * turn * parent * transform.rotation;
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    ai::AiProfile,
+    roster::{CharacterDef, Roster},
+    sfx::synthesize_call,
+    voice::voice_asset,
+};
+
+// The announcer's "fight!" line.
+pub const BEGIN_SFX: &str = "sfx.begin";
+// The rest of the announcer's calls. Placeholders, synthesised at startup by
+// `cache_announcer_calls` until there are recordings.
+pub const KNOCK_OUT_SFX: &str = "sfx.knock_out";
+pub const CHIP_KNOCK_OUT_SFX: &str = "sfx.chip_knock_out";
+pub const TIME_OVER_SFX: &str = "sfx.time_over";
+pub const RING_OUT_SFX: &str = "sfx.ring_out";
+pub const PERFECT_SFX: &str = "sfx.perfect";
+// Rounds past this get the call without a number.
+pub const NUMBERED_ROUND_CALLS: u32 = 9;
+
+// Strong handles to everything a fight plays, loaded while the game is on the
+// loading screen and looked up by logical name ("sfx.begin",
+// "char.ninja.scene"), so nothing is read from disk once a fight is running.
+#[derive(Resource, Default)]
+pub struct AssetCache {
+    handles: HashMap<String, UntypedHandle>,
+}
+
+impl AssetCache {
+    pub fn load<A: Asset>(&mut self, asset_server: &AssetServer, name: String, path: &str) {
+        let handle: Handle<A> = asset_server.load(path.to_owned());
+        self.handles.insert(name, handle.untyped());
+    }
+
+    // Caches an asset made in code rather than loaded.
+    pub fn insert<A: Asset>(&mut self, name: String, handle: Handle<A>) {
+        self.handles.insert(name, handle.untyped());
+    }
+
+    // A name that was never cached is a bug in the caller; it gets an empty
+    // handle, which plays or shows nothing, rather than a mid-fight load.
+    pub fn get<A: Asset>(&self, name: &str) -> Handle<A> {
+        match self.handles.get(name) {
+            Some(handle) => handle.clone().typed::<A>(),
+            None => {
+                warn!("no asset cached as {name}");
+                Handle::default()
+            }
+        }
+    }
+}
+
+// The announcer calling round `number`, or just "round" past the numbered
+// calls.
+pub fn round_sfx(number: u32) -> String {
+    if (1..=NUMBERED_ROUND_CALLS).contains(&number) {
+        format!("sfx.round.{number}")
+    } else {
+        "sfx.round".to_owned()
+    }
+}
+
+// Name of one of a character's assets, keyed by the id the roster lists it
+// under: `character_asset("ninja", "sfx.punch")` is "char.ninja.sfx.punch".
+pub fn character_asset(id: &str, asset: &str) -> String {
+    format!("char.{id}.{asset}")
+}
+
+// The AI profiles are all the simulation needs, so headless runs cache only
+// these.
+pub fn cache_ai_profiles(
+    asset_server: Res<AssetServer>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    mut cache: ResMut<AssetCache>,
+) {
+    for (id, def) in roster.defs(&characters) {
+        cache.load::<AiProfile>(&asset_server, character_asset(id, "ai"), &def.ai_profile);
+    }
+}
+
+pub fn cache_fighter_assets(
+    asset_server: Res<AssetServer>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    mut cache: ResMut<AssetCache>,
+) {
+    for (id, def) in roster.defs(&characters) {
+        let idle = &def.animations.idle;
+        let clips = [
+            ("idle", idle),
+            ("run_forwards", &def.animations.run_forwards),
+            ("walk_backwards", &def.animations.walk_backwards),
+            ("punch", &def.animations.punch),
+            ("kick", &def.animations.kick),
+            // Characters without these moves stand idle instead.
+            ("block", def.animations.block.as_ref().unwrap_or(idle)),
+            ("crouch", def.animations.crouch.as_ref().unwrap_or(idle)),
+            ("jump", def.animations.jump.as_ref().unwrap_or(idle)),
+        ];
+        for (clip, path) in clips {
+            let name = character_asset(id, &format!("anim.{clip}"));
+            cache.load::<AnimationClip>(&asset_server, name, path);
+        }
+        for (sound, path) in [
+            ("punch", &def.sfx.punch),
+            ("kick", &def.sfx.kick),
+            ("pain", &def.sfx.pain),
+        ] {
+            let name = character_asset(id, &format!("sfx.{sound}"));
+            cache.load::<AudioSource>(&asset_server, name, path);
+        }
+        for path in def.voice.recordings() {
+            cache.load::<AudioSource>(&asset_server, voice_asset(id, path), path);
+        }
+        cache.load::<Scene>(&asset_server, character_asset(id, "scene"), &def.scene);
+    }
+    cache.load::<AudioSource>(&asset_server, BEGIN_SFX.to_owned(), "begin.ogg");
+}
+
+// Calls that start something rise and calls that end something fall. A
+// round's number is counted out in pips after it.
+pub fn cache_announcer_calls(
+    mut cache: ResMut<AssetCache>,
+    mut sources: ResMut<Assets<AudioSource>>,
+) {
+    const ROUND: [(f32, f32); 2] = [(440.0, 0.12), (660.0, 0.2)];
+    const PIP: [(f32, f32); 2] = [(0.0, 0.06), (880.0, 0.08)];
+    let mut calls = vec![
+        (
+            KNOCK_OUT_SFX.to_owned(),
+            vec![(660.0, 0.15), (440.0, 0.15), (220.0, 0.45)],
+        ),
+        // The KO call, ground out a chip at a time.
+        (
+            CHIP_KNOCK_OUT_SFX.to_owned(),
+            vec![
+                (660.0, 0.06),
+                (0.0, 0.04),
+                (660.0, 0.06),
+                (0.0, 0.04),
+                (440.0, 0.15),
+                (220.0, 0.45),
+            ],
+        ),
+        (
+            TIME_OVER_SFX.to_owned(),
+            vec![(440.0, 0.2), (440.0, 0.2), (330.0, 0.45)],
+        ),
+        (
+            RING_OUT_SFX.to_owned(),
+            vec![(550.0, 0.1), (370.0, 0.1), (247.0, 0.1), (165.0, 0.4)],
+        ),
+        (
+            PERFECT_SFX.to_owned(),
+            vec![(523.0, 0.1), (659.0, 0.1), (784.0, 0.1), (1047.0, 0.4)],
+        ),
+        (round_sfx(0), ROUND.to_vec()),
+    ];
+    for number in 1..=NUMBERED_ROUND_CALLS {
+        let pips = (0..number).flat_map(|_| PIP);
+        calls.push((round_sfx(number), ROUND.into_iter().chain(pips).collect()));
+    }
+    for (name, notes) in calls {
+        let handle = sources.add(synthesize_call(&notes));
+        cache.insert(name, handle);
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    character::Character,
+    hud::Hud,
+    locale::Strings,
+    rng::GameRng,
+    roster::{EnemyControl, MatchSetup, Roster},
+    transition::{ScreenTransition, TransitionConfig, TransitionCovered},
+    AppState, GameMode,
+};
+
+// Real seconds the menu has to sit untouched before the demo starts.
+const IDLE_SECONDS: f32 = 30.0;
+
+// Counts idle time on the menu, and remembers what the demo replaced so the
+// menu comes back exactly as it was left.
+#[derive(Resource, Default)]
+pub struct Attract {
+    idle: f32,
+    saved: Option<(GameMode, MatchSetup)>,
+    leaving: bool,
+}
+
+#[derive(Component)]
+pub struct DemoOverlay;
+
+fn any_input(
+    keys: &Input<KeyCode>,
+    mouse: &Input<MouseButton>,
+    buttons: &Input<GamepadButton>,
+) -> bool {
+    keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || buttons.get_just_pressed().next().is_some()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_attract_demo(
+    time: Res<Time<Real>>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    buttons: Res<Input<GamepadButton>>,
+    roster: Res<Roster>,
+    mut rng: ResMut<GameRng>,
+    mut attract: ResMut<Attract>,
+    mut mode: ResMut<GameMode>,
+    mut setup: ResMut<MatchSetup>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if any_input(&keys, &mouse, &buttons) {
+        attract.idle = 0.0;
+        return;
+    }
+    attract.idle += time.delta_seconds();
+    if attract.idle < IDLE_SECONDS || roster.characters.is_empty() {
+        return;
+    }
+    attract.idle = 0.0;
+    attract.saved = Some((*mode, setup.clone()));
+    let rng = rng.cosmetic();
+    let count = roster.characters.len();
+    *setup = MatchSetup {
+        player: rng.gen_range(0..count),
+        enemy: rng.gen_range(0..count),
+        enemy_control: EnemyControl::CharacterProfile,
+        ..default()
+    };
+    *mode = GameMode::Demo;
+    next_state.set(AppState::Fight);
+}
+
+pub fn setup_demo_overlay(mut commands: Commands, strings: Strings) {
+    commands.spawn((
+        TextBundle::from_section(
+            strings.get("attract.demo"),
+            TextStyle {
+                font_size: 48.0,
+                color: Color::rgba(1.0, 1.0, 1.0, 0.8),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(12.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_text_alignment(TextAlignment::Center),
+        DemoOverlay,
+    ));
+}
+
+// Any input, or the demo fight finishing, fades to black and tears the fight
+// down behind the cover. The menu then opens over it as it does at startup.
+#[allow(clippy::too_many_arguments)]
+pub fn leave_attract_demo(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    buttons: Res<Input<GamepadButton>>,
+    state: Res<State<AppState>>,
+    mut attract: ResMut<Attract>,
+    mut mode: ResMut<GameMode>,
+    mut setup: ResMut<MatchSetup>,
+    mut transition: ResMut<ScreenTransition>,
+    mut covered: EventReader<TransitionCovered>,
+    mut next_state: ResMut<NextState<AppState>>,
+    leftovers: Query<Entity, Or<(With<Character>, With<Hud>, With<DemoOverlay>)>>,
+) {
+    let finished = *state.get() == AppState::Finished;
+    if !attract.leaving && (finished || any_input(&keys, &mouse, &buttons)) {
+        attract.leaving = true;
+        transition.cover(TransitionConfig::default());
+    }
+    if !attract.leaving || covered.iter().next().is_none() {
+        return;
+    }
+    for entity in leftovers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Some((saved_mode, saved_setup)) = attract.saved.take() {
+        *mode = saved_mode;
+        *setup = saved_setup;
+    }
+    attract.leaving = false;
+    next_state.set(AppState::CharacterSelect);
+}
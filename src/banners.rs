@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset_cache::{
+        round_sfx, AssetCache, BEGIN_SFX, CHIP_KNOCK_OUT_SFX, KNOCK_OUT_SFX, PERFECT_SFX,
+        RING_OUT_SFX, TIME_OVER_SFX,
+    },
+    locale::Strings,
+    round::{FightStarted, RoundEndReason, RoundEnded, RoundStarted},
+    sfx::PlaySfx,
+    transition::Easing,
+    versus::Rivalry,
+    GameMode,
+};
+
+// Share of a banner's life spent animating in, and then out.
+const BANNER_IN: f32 = 0.2;
+const BANNER_OUT: f32 = 0.2;
+const BANNER_SCALE_FROM: f32 = 3.0;
+const BANNER_SLIDE_FROM: f32 = -100.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BannerKind {
+    Round(u32),
+    Fight,
+    KnockOut,
+    ChipKnockOut,
+    TimeOver,
+    RingOut,
+    Perfect,
+    // Opens a versus match between the same two characters yet again.
+    Rivals(u32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BannerTween {
+    // Slams down from large.
+    Scale,
+    // Slides in from the left and out to the right.
+    Slide,
+    Fade,
+}
+
+impl BannerKind {
+    pub fn text(&self, strings: &Strings) -> String {
+        match self {
+            BannerKind::Round(number) => strings.fill("banner.round", &[("number", number)]),
+            BannerKind::Fight => strings.get("banner.fight"),
+            BannerKind::KnockOut => strings.get("banner.knock_out"),
+            BannerKind::ChipKnockOut => strings.get("banner.chip_knock_out"),
+            BannerKind::TimeOver => strings.get("banner.time_over"),
+            BannerKind::RingOut => strings.get("banner.ring_out"),
+            BannerKind::Perfect => strings.get("banner.perfect"),
+            BannerKind::Rivals(rematches) => {
+                strings.fill("banner.rivals", &[("rematches", rematches)])
+            }
+        }
+    }
+
+    fn tween(&self) -> BannerTween {
+        match self {
+            BannerKind::Round(_) | BannerKind::TimeOver => BannerTween::Slide,
+            BannerKind::Fight
+            | BannerKind::KnockOut
+            | BannerKind::ChipKnockOut
+            | BannerKind::RingOut
+            | BannerKind::Rivals(_) => BannerTween::Scale,
+            BannerKind::Perfect => BannerTween::Fade,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            BannerKind::KnockOut | BannerKind::ChipKnockOut | BannerKind::RingOut => {
+                Color::rgb(1.0, 0.25, 0.2)
+            }
+            BannerKind::Perfect => Color::rgb(1.0, 0.85, 0.3),
+            BannerKind::Rivals(_) => Color::rgb(1.0, 0.45, 0.9),
+            _ => Color::WHITE,
+        }
+    }
+
+    // Seconds on screen.
+    fn duration(&self) -> f32 {
+        match self {
+            BannerKind::Round(_) => 1.6,
+            BannerKind::Fight => 1.0,
+            BannerKind::KnockOut
+            | BannerKind::ChipKnockOut
+            | BannerKind::TimeOver
+            | BannerKind::RingOut => 2.0,
+            BannerKind::Perfect => 1.5,
+            BannerKind::Rivals(_) => 1.0,
+        }
+    }
+
+    // The announcer's call, by its name in the AssetCache.
+    fn announcer(&self) -> Option<String> {
+        match self {
+            BannerKind::Round(number) => Some(round_sfx(*number)),
+            BannerKind::Fight => Some(BEGIN_SFX.to_owned()),
+            BannerKind::KnockOut => Some(KNOCK_OUT_SFX.to_owned()),
+            BannerKind::ChipKnockOut => Some(CHIP_KNOCK_OUT_SFX.to_owned()),
+            BannerKind::TimeOver => Some(TIME_OVER_SFX.to_owned()),
+            BannerKind::RingOut => Some(RING_OUT_SFX.to_owned()),
+            BannerKind::Perfect => Some(PERFECT_SFX.to_owned()),
+            BannerKind::Rivals(_) => None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Banner {
+    pub kind: BannerKind,
+    // Waits out `delay` before it shows, so calls can queue behind each other.
+    pub delay: Timer,
+    pub timer: Timer,
+    announced: bool,
+}
+
+fn spawn_banner(commands: &mut Commands, strings: &Strings, kind: BannerKind, delay: f32) {
+    commands.spawn((
+        TextBundle::from_section(
+            kind.text(strings),
+            TextStyle {
+                font_size: 96.0,
+                color: Color::NONE,
+                ..default()
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        }),
+        Banner {
+            kind,
+            delay: Timer::from_seconds(delay, TimerMode::Once),
+            timer: Timer::from_seconds(kind.duration(), TimerMode::Once),
+            announced: false,
+        },
+    ));
+}
+
+pub fn queue_banners(
+    mut commands: Commands,
+    strings: Strings,
+    mode: Res<GameMode>,
+    rivalry: Res<Rivalry>,
+    mut round_started: EventReader<RoundStarted>,
+    mut fight_started: EventReader<FightStarted>,
+    mut round_ended: EventReader<RoundEnded>,
+) {
+    for round in round_started.iter() {
+        // Rivals get their own call, squeezed in ahead of the first round's
+        // so both are done by the time the fight is called.
+        let mut delay = 0.5;
+        if round.number == 1 && *mode == GameMode::Versus && rivalry.is_rivalry() {
+            let rivals = BannerKind::Rivals(rivalry.rematches);
+            spawn_banner(&mut commands, &strings, rivals, 0.0);
+            delay = rivals.duration();
+        }
+        spawn_banner(&mut commands, &strings, BannerKind::Round(round.number), delay);
+    }
+    for _ in fight_started.iter() {
+        spawn_banner(&mut commands, &strings, BannerKind::Fight, 0.0);
+    }
+    for round in round_ended.iter() {
+        let kind = match round.reason {
+            RoundEndReason::KnockOut if round.chip => BannerKind::ChipKnockOut,
+            RoundEndReason::KnockOut => BannerKind::KnockOut,
+            RoundEndReason::TimeOver => BannerKind::TimeOver,
+            RoundEndReason::RingOut => BannerKind::RingOut,
+        };
+        spawn_banner(&mut commands, &strings, kind, 0.0);
+        if round.perfect {
+            spawn_banner(&mut commands, &strings, BannerKind::Perfect, kind.duration() * 0.6);
+        }
+    }
+}
+
+// Real time, so the KO slow motion doesn't hold the banners up.
+pub fn animate_banners(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    cache: Res<AssetCache>,
+    mut sounds: EventWriter<PlaySfx>,
+    mut banners: Query<(Entity, &mut Banner, &mut Style, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut banner, mut style, mut transform, mut text) in banners.iter_mut() {
+        if !banner.delay.tick(time.delta()).finished() {
+            continue;
+        }
+        if !banner.announced {
+            banner.announced = true;
+            if let Some(line) = banner.kind.announcer() {
+                sounds.send(PlaySfx::new(cache.get(&line), 0.3));
+            }
+        }
+        if banner.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let t = banner.timer.percent();
+        let entering = 1.0 - Easing::EaseOut.apply(t / BANNER_IN);
+        let leaving = Easing::EaseInOut.apply((t - (1.0 - BANNER_OUT)) / BANNER_OUT);
+        let mut alpha = 1.0 - leaving;
+        let mut scale = 1.0;
+        let mut left = 0.0;
+        match banner.kind.tween() {
+            BannerTween::Scale => scale = 1.0 + (BANNER_SCALE_FROM - 1.0) * entering,
+            BannerTween::Slide => {
+                left = BANNER_SLIDE_FROM * entering - BANNER_SLIDE_FROM * leaving;
+            }
+            BannerTween::Fade => alpha *= 1.0 - entering,
+        }
+        style.left = Val::Percent(left);
+        transform.scale = Vec3::splat(scale);
+        let color = banner.kind.color();
+        for section in text.sections.iter_mut() {
+            section.style.color = color.with_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::{
+    animation::{EntityPath, Keyframes, VariableCurve},
+    prelude::*,
+    scene::SceneInstanceReady,
+};
+
+use crate::{
+    animation::FighterAnimations,
+    character::{Character, CharacterState},
+    roster::CharacterDef,
+};
+
+// Seconds the legs take to pick up or let go of the walk, matching the
+// transitions the AnimationPlayer makes between whole-body clips.
+const LEG_LAYER_BLEND: f32 = 0.2;
+
+// The bones under the fighter's lower-body mask, each with its path from the
+// AnimationPlayer so it can be found in any clip. While an upper-body move
+// plays, the walk is sampled onto these on top of the move's pose.
+#[derive(Component)]
+pub struct LegLayer {
+    bones: Vec<(Entity, EntityPath)>,
+    clip: Option<Handle<AnimationClip>>,
+    elapsed: f32,
+    weight: f32,
+}
+
+pub fn find_lower_body_bones(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    characters: Res<Assets<CharacterDef>>,
+    fighters: Query<&Character>,
+    players: Query<(), With<AnimationPlayer>>,
+    children: Query<&Children>,
+    parents: Query<&Parent>,
+    names: Query<&Name>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        let Some(def) = fighters
+            .get(*fighter)
+            .ok()
+            .and_then(|character| characters.get(&character.0))
+        else {
+            continue;
+        };
+        if def.lower_body.is_empty() {
+            continue;
+        }
+        let Some(player) = children
+            .iter_descendants(*fighter)
+            .find(|entity| players.contains(*entity))
+        else {
+            continue;
+        };
+        // Clips address bones by the names from the player down.
+        let path = |bone: Entity| {
+            let mut parts = vec![names.get(bone).ok()?.clone()];
+            for ancestor in parents.iter_ancestors(bone) {
+                parts.push(names.get(ancestor).ok()?.clone());
+                if ancestor == player {
+                    parts.reverse();
+                    return Some(EntityPath { parts });
+                }
+            }
+            None
+        };
+        let mut bones = Vec::new();
+        for root in &def.lower_body {
+            let Some(root) = children.iter_descendants(player).find(|entity| {
+                names
+                    .get(*entity)
+                    .is_ok_and(|name| name.as_str() == root.as_str())
+            }) else {
+                warn!(
+                    "{} has no bone named {root}, its legs won't walk under punches",
+                    def.name
+                );
+                continue;
+            };
+            bones.extend(
+                std::iter::once(root)
+                    .chain(children.iter_descendants(root))
+                    .filter_map(|bone| Some((bone, path(bone)?))),
+            );
+        }
+        if !bones.is_empty() {
+            commands.entity(*fighter).insert(LegLayer {
+                bones,
+                clip: None,
+                elapsed: 0.0,
+                weight: 0.0,
+            });
+        }
+    }
+}
+
+// The keyframes either side of `time`, and how far between them it falls.
+fn keyframes_at(curve: &VariableCurve, time: f32) -> (usize, usize, f32) {
+    let timestamps = &curve.keyframe_timestamps;
+    let next = timestamps.partition_point(|timestamp| *timestamp <= time);
+    if next == 0 {
+        (0, 0, 0.0)
+    } else if next >= timestamps.len() {
+        (timestamps.len() - 1, timestamps.len() - 1, 0.0)
+    } else {
+        let previous = next - 1;
+        let span = timestamps[next] - timestamps[previous];
+        (previous, next, (time - timestamps[previous]) / span)
+    }
+}
+
+fn blend_curve(curve: &VariableCurve, time: f32, weight: f32, transform: &mut Transform) {
+    if curve.keyframe_timestamps.is_empty() {
+        return;
+    }
+    let (from, to, t) = keyframes_at(curve, time);
+    match &curve.keyframes {
+        Keyframes::Rotation(keys) => {
+            let pose = keys[from].slerp(keys[to], t);
+            transform.rotation = transform.rotation.slerp(pose, weight);
+        }
+        Keyframes::Translation(keys) => {
+            let pose = keys[from].lerp(keys[to], t);
+            transform.translation = transform.translation.lerp(pose, weight);
+        }
+        Keyframes::Scale(keys) => {
+            let pose = keys[from].lerp(keys[to], t);
+            transform.scale = transform.scale.lerp(pose, weight);
+        }
+        Keyframes::Weights(_) => {}
+    }
+}
+
+// Runs after the AnimationPlayer has posed the whole body. When an upper-body
+// move is playing over a walk, the walk clip is laid over the masked legs so
+// the punch keeps its arms and the fighter keeps its stride.
+pub fn apply_leg_layer(
+    time: Res<Time>,
+    clips: Res<Assets<AnimationClip>>,
+    characters: Res<Assets<CharacterDef>>,
+    mut fighters: Query<(
+        &CharacterState,
+        &Character,
+        &FighterAnimations,
+        &mut LegLayer,
+    )>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let dt = time.delta_seconds();
+    for (state, character, animations, mut layer) in fighters.iter_mut() {
+        let legs = state.legs_state();
+        let walk = characters
+            .get(&character.0)
+            .filter(|_| legs != state.player_state)
+            .and_then(|def| def.animation_graph.states.get(&legs))
+            .and_then(|node| animations.clip(&node.clip));
+        if let Some(walk) = walk {
+            if layer.clip.as_ref() != Some(walk) {
+                layer.clip = Some(walk.clone_weak());
+                layer.elapsed = 0.0;
+            }
+            layer.weight = (layer.weight + dt / LEG_LAYER_BLEND).min(1.0);
+        } else {
+            layer.weight = (layer.weight - dt / LEG_LAYER_BLEND).max(0.0);
+        }
+        layer.elapsed += dt;
+        if layer.weight <= 0.0 {
+            continue;
+        }
+        let Some(clip) = layer.clip.as_ref().and_then(|clip| clips.get(clip)) else {
+            continue;
+        };
+        let time = if clip.duration() > 0.0 {
+            layer.elapsed % clip.duration()
+        } else {
+            0.0
+        };
+        for (bone, path) in &layer.bones {
+            let Some(curves) = clip.paths().get(path).and_then(|id| clip.curves().get(*id)) else {
+                continue;
+            };
+            let Ok(mut transform) = transforms.get_mut(*bone) else {
+                continue;
+            };
+            for curve in curves {
+                blend_curve(curve, time, layer.weight, &mut transform);
+            }
+        }
+    }
+}
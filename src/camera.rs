@@ -0,0 +1,213 @@
+use bevy::{
+    core_pipeline::bloom::BloomSettings,
+    pbr::{CascadeShadowConfig, CascadeShadowConfigBuilder, NotShadowCaster},
+    prelude::*,
+    render::render_resource::TextureUsages,
+};
+
+use crate::{camera_rig::CameraRig, character::Character, post_process::PostProcessSettings};
+
+const BLOB_SHADOW_RADIUS: f32 = 0.6;
+const BLOB_SHADOW_HEIGHT: f32 = 0.01;
+
+#[derive(Component)]
+pub struct Cameraman;
+
+// Point lights in order of importance; lower quality presets drop the later
+// ones. `intensity` is the resting brightness the fight effects modulate.
+#[derive(Component)]
+pub struct StageLight {
+    pub priority: usize,
+    pub intensity: f32,
+}
+
+// The key light. Angles are in radians; elevation is above the horizon and
+// azimuth is measured round from the camera's side of the stage.
+#[derive(Resource, Clone, Debug)]
+pub struct SunConfig {
+    pub color: Color,
+    pub illuminance: f32,
+    pub elevation: f32,
+    pub azimuth: f32,
+    pub cascades: usize,
+    pub shadow_distance: f32,
+}
+
+impl Default for SunConfig {
+    fn default() -> Self {
+        SunConfig {
+            color: Color::WHITE,
+            illuminance: 6000.0,
+            elevation: 0.9,
+            azimuth: 0.6,
+            cascades: 3,
+            shadow_distance: 30.0,
+        }
+    }
+}
+
+impl SunConfig {
+    fn transform(&self) -> Transform {
+        Transform::from_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            self.azimuth,
+            -self.elevation,
+            0.0,
+        ))
+    }
+
+    fn cascade_config(&self) -> CascadeShadowConfig {
+        CascadeShadowConfigBuilder {
+            num_cascades: self.cascades,
+            maximum_distance: self.shadow_distance,
+            first_cascade_far_bound: self.shadow_distance / 6.0,
+            ..default()
+        }
+        .into()
+    }
+}
+
+#[derive(Component)]
+pub struct Sun;
+
+// A soft dark disc on the ground under a fighter. It isn't parented so it
+// stays on the floor while they jump.
+#[derive(Component)]
+pub struct BlobShadow {
+    pub owner: Entity,
+}
+
+pub fn setup_camera(mut commands: Commands, sun: Res<SunConfig>) {
+    commands.insert_resource(ClearColor(Color::rgb(0.3, 0.3, 0.6)));
+
+    let camera = Camera3dBundle {
+        camera: Camera {
+            hdr: true,
+            ..default()
+        },
+        camera_3d: Camera3d {
+            // Read by the depth of field pass in photo mode.
+            depth_texture_usages: (TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING)
+                .into(),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 3.0, 12.0).looking_at(Vec3::new(0.0, 3.0, 0.0), Vec3::Y),
+        ..default()
+    };
+
+    commands
+        .spawn(camera)
+        .insert(BloomSettings::NATURAL)
+        .insert(PostProcessSettings::default())
+        .insert(CameraRig::default())
+        .insert(Cameraman);
+
+    commands
+        .spawn(DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: sun.color,
+                illuminance: sun.illuminance,
+                shadows_enabled: true,
+                ..default()
+            },
+            transform: sun.transform(),
+            cascade_shadow_config: sun.cascade_config(),
+            ..default()
+        })
+        .insert(Sun);
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 2500.0,
+            shadows_enabled: true,
+            shadow_depth_bias : 0.001,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 5.0, 4.0),
+        ..default()
+    }).insert(StageLight {
+        priority: 0,
+        intensity: 2500.0,
+    });
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 2500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(-4.0, 5.0, -2.0),
+        ..default()
+    }).insert(StageLight {
+        priority: 1,
+        intensity: 2500.0,
+    });
+}
+
+pub fn apply_sun_config(
+    sun: Res<SunConfig>,
+    mut lights: Query<
+        (&mut DirectionalLight, &mut Transform, &mut CascadeShadowConfig),
+        With<Sun>,
+    >,
+) {
+    if !sun.is_changed() {
+        return;
+    }
+    for (mut light, mut transform, mut cascades) in lights.iter_mut() {
+        light.color = sun.color;
+        light.illuminance = sun.illuminance;
+        *transform = sun.transform();
+        *cascades = sun.cascade_config();
+    }
+}
+
+pub fn spawn_blob_shadows(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    fighters: Query<Entity, Added<Character>>,
+) {
+    for owner in &fighters {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Circle::new(BLOB_SHADOW_RADIUS))),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                ..default()
+            },
+            NotShadowCaster,
+            BlobShadow { owner },
+        ));
+    }
+}
+
+// Shrinks and fades with height so jumps and knockdowns read against the
+// floor.
+pub fn update_blob_shadows(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    fighters: Query<&Transform, (With<Character>, Without<BlobShadow>)>,
+    mut shadows: Query<(Entity, &BlobShadow, &Handle<StandardMaterial>, &mut Transform)>,
+) {
+    for (entity, shadow, material, mut transform) in shadows.iter_mut() {
+        let Ok(owner) = fighters.get(shadow.owner) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let height = owner.translation.y.max(0.0);
+        let falloff = 1.0 / (1.0 + height);
+        transform.translation =
+            Vec3::new(owner.translation.x, BLOB_SHADOW_HEIGHT, owner.translation.z);
+        transform.rotation = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+        transform.scale = Vec3::splat(falloff);
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_a(0.5 * falloff);
+        }
+    }
+}
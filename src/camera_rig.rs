@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::Cameraman,
+    character::{Character, Team},
+    round::{RoundEndReason, RoundEnded, RoundStarted},
+};
+
+pub const INTRO_SWEEP_DURATION: f32 = 2.5;
+pub const KO_SHOT_DURATION: f32 = 3.0;
+pub const KO_SLOW_MOTION: f64 = 0.3;
+const KO_ORBIT_RADIUS: f32 = 4.0;
+const KO_ORBIT_HEIGHT: f32 = 1.5;
+const KO_ORBIT_SPEED: f32 = 0.8;
+const MAX_LOOK_AHEAD: f32 = 1.5;
+
+// One way of framing the fight. Each proposes an eye position and a point to
+// look at; the rig blends between them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CameraBehavior {
+    FollowMidpoint,
+    FocusOn(Entity),
+    IntroSweep { elapsed: f32 },
+    Orbit { target: Entity, elapsed: f32 },
+}
+
+struct ActiveBehavior {
+    behavior: CameraBehavior,
+    weight: f32,
+    target_weight: f32,
+    blend_speed: f32,
+}
+
+// Framing rules for the cameraman. New shots are added as behaviors and
+// blended to, rather than written as their own camera systems.
+#[derive(Component)]
+pub struct CameraRig {
+    pub height_offset: f32,
+    pub distance: f32,
+    // How far above the framed point the camera aims.
+    pub pitch_bias: f32,
+    // Roughly how long the camera takes to settle on a new framing.
+    pub smooth_time: f32,
+    // The look target ignores movement smaller than this.
+    pub dead_zone: f32,
+    pub max_pan_speed: f32,
+    // Seconds of the fight's sideways motion to lead the framing by.
+    pub look_ahead: f32,
+    eye: Option<Vec3>,
+    eye_velocity: Vec3,
+    look_at: Vec3,
+    look_velocity: Vec3,
+    framed: Option<Vec3>,
+    fight_velocity: Vec3,
+    behaviors: Vec<ActiveBehavior>,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        CameraRig {
+            height_offset: 3.0,
+            distance: 12.0,
+            pitch_bias: 0.5,
+            smooth_time: 0.3,
+            dead_zone: 0.1,
+            max_pan_speed: 12.0,
+            look_ahead: 0.25,
+            eye: None,
+            eye_velocity: Vec3::ZERO,
+            look_at: Vec3::ZERO,
+            look_velocity: Vec3::ZERO,
+            framed: None,
+            fight_velocity: Vec3::ZERO,
+            behaviors: vec![ActiveBehavior {
+                behavior: CameraBehavior::FollowMidpoint,
+                weight: 1.0,
+                target_weight: 1.0,
+                blend_speed: 0.0,
+            }],
+        }
+    }
+}
+
+impl CameraRig {
+    // Fades everything else out and `behavior` in over `seconds`.
+    pub fn blend_to(&mut self, behavior: CameraBehavior, seconds: f32) {
+        let blend_speed = 1.0 / seconds.max(f32::EPSILON);
+        for active in self.behaviors.iter_mut() {
+            active.target_weight = 0.0;
+            active.blend_speed = blend_speed;
+        }
+        self.behaviors.push(ActiveBehavior {
+            behavior,
+            weight: 0.0,
+            target_weight: 1.0,
+            blend_speed,
+        });
+    }
+
+    pub fn cut_to(&mut self, behavior: CameraBehavior) {
+        self.behaviors.clear();
+        self.behaviors.push(ActiveBehavior {
+            behavior,
+            weight: 1.0,
+            target_weight: 1.0,
+            blend_speed: 0.0,
+        });
+        self.eye = None;
+        self.framed = None;
+    }
+
+    // The behavior currently blending in, or holding the shot.
+    pub fn current(&self) -> Option<CameraBehavior> {
+        self.behaviors.last().map(|active| active.behavior)
+    }
+
+    // (eye, look at) for a behavior, or None if what it frames is gone.
+    fn frame(
+        &self,
+        behavior: CameraBehavior,
+        fighters: &Query<(Entity, &Transform), (With<Character>, Without<Cameraman>)>,
+    ) -> Option<(Vec3, Vec3)> {
+        let back = Vec3::new(0.0, self.height_offset, self.distance);
+        let aim = Vec3::Y * self.pitch_bias;
+        match behavior {
+            CameraBehavior::FollowMidpoint => {
+                let positions: Vec<Vec3> = fighters.iter().map(|(_, t)| t.translation).collect();
+                if positions.is_empty() {
+                    return None;
+                }
+                let midpoint = positions.iter().sum::<Vec3>() / positions.len() as f32;
+                Some((Vec3::new(midpoint.x, 0.0, 0.0) + back, midpoint + aim))
+            }
+            CameraBehavior::FocusOn(target) => {
+                let (_, target) = fighters.get(target).ok()?;
+                Some((target.translation + back * 0.5, target.translation + Vec3::Y))
+            }
+            CameraBehavior::IntroSweep { elapsed } => {
+                let progress = (elapsed / INTRO_SWEEP_DURATION).clamp(0.0, 1.0);
+                let eased = progress * progress * (3.0 - 2.0 * progress);
+                let angle = (1.0 - eased) * 1.2;
+                let eye = Quat::from_rotation_y(angle) * (back * (1.0 + (1.0 - eased)));
+                Some((eye, aim))
+            }
+            CameraBehavior::Orbit { target, elapsed } => {
+                let (_, target) = fighters.get(target).ok()?;
+                let angle = elapsed * KO_ORBIT_SPEED;
+                let eye = target.translation
+                    + Vec3::new(angle.sin(), 0.0, angle.cos()) * KO_ORBIT_RADIUS
+                    + Vec3::Y * KO_ORBIT_HEIGHT;
+                Some((eye, target.translation + Vec3::Y))
+            }
+        }
+    }
+}
+
+// Critically damped spring towards `target`: as fast as possible without
+// overshooting. Approximates exp(-x) the usual game-programming way.
+fn smooth_damp(
+    current: Vec3,
+    target: Vec3,
+    velocity: &mut Vec3,
+    smooth_time: f32,
+    dt: f32,
+) -> Vec3 {
+    let omega = 2.0 / smooth_time.max(0.0001);
+    let x = omega * dt;
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = current - target;
+    let temp = (*velocity + change * omega) * dt;
+    *velocity = (*velocity - temp * omega) * decay;
+    target + (change + temp) * decay
+}
+
+pub fn start_intro_sweep(mut rigs: Query<&mut CameraRig>) {
+    for mut rig in rigs.iter_mut() {
+        rig.cut_to(CameraBehavior::IntroSweep { elapsed: 0.0 });
+    }
+}
+
+// Later rounds open on the usual framing; the sweep is for the first only.
+pub fn frame_next_round(mut started: EventReader<RoundStarted>, mut rigs: Query<&mut CameraRig>) {
+    if !started.iter().any(|round| round.number > 1) {
+        return;
+    }
+    for mut rig in rigs.iter_mut() {
+        rig.cut_to(CameraBehavior::FollowMidpoint);
+    }
+}
+
+// Runs on real time so slow motion doesn't drag the camera.
+pub fn update_camera_rig(
+    time: Res<Time<Real>>,
+    fighters: Query<(Entity, &Transform), (With<Character>, Without<Cameraman>)>,
+    mut cameraman: Query<(&mut CameraRig, &mut Transform), With<Cameraman>>,
+) {
+    let dt = time.delta_seconds();
+    for (mut rig, mut transform) in cameraman.iter_mut() {
+        for active in rig.behaviors.iter_mut() {
+            let step = active.blend_speed * dt;
+            active.weight += (active.target_weight - active.weight).clamp(-step, step);
+            match &mut active.behavior {
+                CameraBehavior::IntroSweep { elapsed } | CameraBehavior::Orbit { elapsed, .. } => {
+                    *elapsed += dt
+                }
+                CameraBehavior::FollowMidpoint | CameraBehavior::FocusOn(_) => {}
+            }
+        }
+        rig.behaviors
+            .retain(|active| active.weight > 0.0 || active.target_weight > 0.0);
+        if let Some(CameraBehavior::IntroSweep { elapsed }) = rig.current() {
+            if elapsed >= INTRO_SWEEP_DURATION {
+                rig.blend_to(CameraBehavior::FollowMidpoint, 0.5);
+            }
+        }
+
+        let mut total = 0.0;
+        let mut eye = Vec3::ZERO;
+        let mut look_at = Vec3::ZERO;
+        for active in rig.behaviors.iter() {
+            let Some((behavior_eye, behavior_look_at)) = rig.frame(active.behavior, &fighters)
+            else {
+                continue;
+            };
+            total += active.weight;
+            eye += behavior_eye * active.weight;
+            look_at += behavior_look_at * active.weight;
+        }
+        if total <= 0.0 {
+            continue;
+        }
+        let (eye, look_at) = (eye / total, look_at / total);
+
+        // Lead the shot in the direction the fight is drifting.
+        if let Some(framed) = rig.framed.filter(|_| dt > 0.0) {
+            let velocity = (look_at - framed) / dt;
+            rig.fight_velocity = rig.fight_velocity.lerp(velocity, (dt * 4.0).min(1.0));
+        }
+        rig.framed = Some(look_at);
+        let lead = Vec3::X
+            * (rig.fight_velocity.x * rig.look_ahead).clamp(-MAX_LOOK_AHEAD, MAX_LOOK_AHEAD);
+        let (eye, look_at) = (eye + lead, look_at + lead);
+
+        let Some(current_eye) = rig.eye else {
+            // A fresh shot starts framed rather than swinging in.
+            rig.eye = Some(eye);
+            rig.look_at = look_at;
+            rig.eye_velocity = Vec3::ZERO;
+            rig.look_velocity = Vec3::ZERO;
+            transform.translation = eye;
+            transform.look_at(look_at, Vec3::Y);
+            continue;
+        };
+        let smooth_time = rig.smooth_time;
+        let mut eye_velocity = rig.eye_velocity;
+        let target_eye = smooth_damp(current_eye, eye, &mut eye_velocity, smooth_time, dt);
+        let new_eye =
+            current_eye + (target_eye - current_eye).clamp_length_max(rig.max_pan_speed * dt);
+        rig.eye_velocity = eye_velocity;
+        rig.eye = Some(new_eye);
+
+        if look_at.distance(rig.look_at) > rig.dead_zone {
+            let mut look_velocity = rig.look_velocity;
+            rig.look_at = smooth_damp(rig.look_at, look_at, &mut look_velocity, smooth_time, dt);
+            rig.look_velocity = look_velocity;
+        } else {
+            rig.look_velocity = Vec3::ZERO;
+        }
+
+        transform.translation = new_eye;
+        transform.look_at(rig.look_at, Vec3::Y);
+    }
+}
+
+// Plays out the slow-motion orbit around the knocked out fighter, then
+// hands over to the winner.
+#[derive(Component)]
+pub struct KoShot {
+    pub timer: Timer,
+    pub winner: Option<Entity>,
+}
+
+pub fn start_ko_shot(
+    mut commands: Commands,
+    mut ended: EventReader<RoundEnded>,
+    mut time: ResMut<Time<Virtual>>,
+    fighters: Query<(Entity, &Team)>,
+    mut cameraman: Query<(Entity, &mut CameraRig), With<Cameraman>>,
+) {
+    // Only the knockout that settles the match gets the orbit. Every ring
+    // out gets a slow-motion look at the fighter going over the edge.
+    let Some(fight) = ended
+        .iter()
+        .filter(|round| {
+            round.reason == RoundEndReason::RingOut
+                || (round.decisive && round.reason == RoundEndReason::KnockOut)
+        })
+        .last()
+    else {
+        return;
+    };
+    let find = |team: Team| {
+        fighters
+            .iter()
+            .find(|(_, other)| **other == team)
+            .map(|(entity, _)| entity)
+    };
+    let winner = fight.winner.and_then(find);
+    let loser = match fight.winner {
+        Some(winner) => find(winner.opponent()),
+        None => fighters.iter().next().map(|(entity, _)| entity),
+    };
+    let (Some(loser), Ok((camera, mut rig))) = (loser, cameraman.get_single_mut()) else {
+        return;
+    };
+    rig.cut_to(match fight.reason {
+        RoundEndReason::RingOut => CameraBehavior::FocusOn(loser),
+        _ => CameraBehavior::Orbit {
+            target: loser,
+            elapsed: 0.0,
+        },
+    });
+    commands.entity(camera).insert(KoShot {
+        timer: Timer::from_seconds(KO_SHOT_DURATION, TimerMode::Once),
+        winner,
+    });
+    time.set_relative_speed_f64(KO_SLOW_MOTION);
+}
+
+pub fn finish_ko_shot(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut cameraman: Query<(Entity, &mut KoShot, &mut CameraRig)>,
+) {
+    for (camera, mut shot, mut rig) in cameraman.iter_mut() {
+        if !shot.timer.tick(real_time.delta()).finished() {
+            continue;
+        }
+        let next = shot
+            .winner
+            .map_or(CameraBehavior::FollowMidpoint, CameraBehavior::FocusOn);
+        rig.blend_to(next, 1.0);
+        time.set_relative_speed_f64(1.0);
+        commands.entity(camera).remove::<KoShot>();
+    }
+}
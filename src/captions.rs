@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::{
+    combat::{Clash, HitLanded, HEAVY_HIT_DAMAGE},
+    comeback::ComebackTriggered,
+    crowd::Excitement,
+    locale::Strings,
+    moves::MoveId,
+    round::RoundEnded,
+    settings::Settings,
+    voice::Barked,
+};
+
+// Real seconds a caption stays up.
+const CAPTION_SECONDS: f32 = 2.5;
+const MAX_CAPTIONS: usize = 3;
+// Excitement at which the cheer layer is loud enough to caption.
+const CHEER_CAPTION_LEVEL: f32 = 0.5;
+
+// Bottom-centre stack of captions, newest last.
+#[derive(Component)]
+pub struct CaptionArea;
+
+#[derive(Component)]
+pub struct Caption {
+    pub timer: Timer,
+}
+
+pub fn setup_captions(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Percent(8.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            z_index: ZIndex::Global(50),
+            ..default()
+        },
+        CaptionArea,
+    ));
+}
+
+fn move_name(strings: &Strings, move_id: MoveId) -> String {
+    strings.get(match move_id {
+        MoveId::Punch => "caption.move.punch",
+        MoveId::Kick => "caption.move.kick",
+        MoveId::Elbow => "caption.move.elbow",
+        MoveId::ShoulderCharge => "caption.move.shoulder_charge",
+    })
+}
+
+// Captions the non-speech sounds from the same events that play them, and
+// subtitles what the fighters say.
+#[allow(clippy::too_many_arguments)]
+pub fn queue_captions(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    strings: Strings,
+    excitement: Res<Excitement>,
+    mut cheering: Local<bool>,
+    mut hits: EventReader<HitLanded>,
+    mut clashes: EventReader<Clash>,
+    mut round_ended: EventReader<RoundEnded>,
+    mut comebacks: EventReader<ComebackTriggered>,
+    mut barks: EventReader<Barked>,
+    areas: Query<Entity, With<CaptionArea>>,
+    mut captions: Query<(&Text, &mut Caption)>,
+) {
+    let mut lines = Vec::new();
+    for hit in hits.iter() {
+        let name = move_name(&strings, hit.move_id);
+        lines.push(if hit.blocked {
+            strings.get("caption.blocked")
+        } else if hit.damage >= HEAVY_HIT_DAMAGE {
+            strings.fill("caption.heavy_hit", &[("move", &name)])
+        } else {
+            strings.fill("caption.hit", &[("move", &name)])
+        });
+    }
+    if clashes.iter().next().is_some() {
+        lines.push(strings.get("caption.clash"));
+    }
+    if round_ended.iter().any(|round| round.reason.beaten()) {
+        lines.push(strings.get("caption.crowd_roars"));
+    }
+    if comebacks.iter().next().is_some() {
+        lines.push(strings.get("caption.heartbeat"));
+    }
+    let loud = excitement.level >= CHEER_CAPTION_LEVEL;
+    if loud && !*cheering {
+        lines.push(strings.get("caption.crowd_cheers"));
+    }
+    *cheering = loud;
+    for bark in barks.iter() {
+        let line = strings.get(&bark.text);
+        lines.push(strings.fill("caption.voice", &[("name", &bark.speaker), ("line", &line)]));
+    }
+
+    if !settings.captions {
+        return;
+    }
+    let Ok(area) = areas.get_single() else {
+        return;
+    };
+    let mut shown: Vec<String> = Vec::new();
+    for line in lines {
+        if shown.contains(&line) {
+            continue;
+        }
+        shown.push(line.clone());
+        // A sound repeating just keeps its caption up for longer.
+        if let Some((_, mut caption)) = captions
+            .iter_mut()
+            .find(|(text, _)| text.sections[0].value == line)
+        {
+            caption.timer.reset();
+            continue;
+        }
+        let caption = commands
+            .spawn((
+                TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+                Caption {
+                    timer: Timer::from_seconds(CAPTION_SECONDS, TimerMode::Once),
+                },
+            ))
+            .id();
+        commands.entity(area).add_child(caption);
+    }
+}
+
+// Drops captions that have timed out, and the oldest ones once there are too
+// many to read.
+pub fn expire_captions(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    areas: Query<&Children, With<CaptionArea>>,
+    mut captions: Query<&mut Caption>,
+) {
+    for children in areas.iter() {
+        let overflow = children.len().saturating_sub(MAX_CAPTIONS);
+        for (index, child) in children.iter().enumerate() {
+            let Ok(mut caption) = captions.get_mut(*child) else {
+                continue;
+            };
+            let expired = caption.timer.tick(time.delta()).finished();
+            if expired || index < overflow || !settings.captions {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping, Skybox},
+    ecs::system::EntityCommands,
+    pbr::EnvironmentMapLight,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssets,
+        render_resource::{
+            BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
+            ImageDataLayout, Maintain, MapMode, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        renderer::{render_system, RenderDevice, RenderQueue},
+        view::screenshot::ScreenshotManager,
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    tasks::IoTaskPool,
+    window::PrimaryWindow,
+};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageResult, RgbaImage,
+};
+
+use crate::{camera::Cameraman, depth_of_field::DepthOfField, post_process::PostProcessSettings};
+
+pub const SCREENSHOT_KEY: KeyCode = KeyCode::F12;
+pub const CLIP_KEY: KeyCode = KeyCode::F11;
+pub const CAPTURE_DIR: &str = "captures";
+
+// During a fight the clip buffer keeps a frame every 1/CLIP_FPS seconds of
+// real time, so slow motion and pausing don't shorten what gets saved. The
+// frames are drawn CLIP_WIDTH wide by a camera of their own rather than read
+// back from the window, which keeps the copy small and leaves the window's
+// screenshots to F12. Nothing is read back from the GPU outside of fights.
+pub const CLIP_SECONDS: f32 = 5.0;
+pub const CLIP_FPS: f32 = 10.0;
+pub const CLIP_WIDTH: u32 = 480;
+// 1 is the best quality and slowest to encode, 30 the worst and fastest.
+const GIF_SPEED: i32 = 10;
+// What offscreen cameras draw into; the same as most windows, so the
+// pipelines the fight camera uses already suit it.
+const OFFSCREEN_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
+
+#[derive(Resource)]
+pub struct ClipBuffer {
+    frames: Arc<Mutex<VecDeque<RgbaImage>>>,
+    timer: Timer,
+}
+
+impl Default for ClipBuffer {
+    fn default() -> Self {
+        ClipBuffer {
+            frames: Arc::default(),
+            timer: Timer::from_seconds(1.0 / CLIP_FPS, TimerMode::Repeating),
+        }
+    }
+}
+
+fn capacity() -> usize {
+    (CLIP_SECONDS * CLIP_FPS) as usize
+}
+
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis())
+}
+
+// Timestamped path under CAPTURE_DIR, creating the directory if needed.
+pub fn capture_path(prefix: &str, extension: &str) -> Option<PathBuf> {
+    if let Err(err) = fs::create_dir_all(CAPTURE_DIR) {
+        warn!("Can't create {CAPTURE_DIR}: {err}");
+        return None;
+    }
+    let name = format!("{prefix}-{}.{extension}", timestamp());
+    Some(PathBuf::from(CAPTURE_DIR).join(name))
+}
+
+pub fn capture_screenshot(
+    keys: Res<Input<KeyCode>>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+) {
+    if !keys.just_pressed(SCREENSHOT_KEY) {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(path) = capture_path("screenshot", "png") else {
+        return;
+    };
+    if let Err(err) = screenshots.save_screenshot_to_disk(window, path) {
+        warn!("Screenshot failed: {err}");
+    }
+}
+
+// The offscreen camera drawing the clip's frames. It's only active on the
+// frames it captures.
+#[derive(Component)]
+pub struct ClipCamera {
+    image: Handle<Image>,
+}
+
+// Sized to the window's shape when the fight starts.
+pub fn spawn_clip_camera(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let height = window.physical_height() * CLIP_WIDTH / window.physical_width().max(1);
+    let image = offscreen_image(&mut images, CLIP_WIDTH, height.max(1));
+    // `record_clip_frames` switches it off until a frame is due.
+    commands.spawn((offscreen_camera(image.clone()), ClipCamera { image }));
+}
+
+// A clip never runs from one fight into the next.
+pub fn clear_clip_buffer(
+    mut commands: Commands,
+    clip: Res<ClipBuffer>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<(Entity, &ClipCamera)>,
+) {
+    clip.frames.lock().unwrap().clear();
+    for (entity, camera) in cameras.iter() {
+        images.remove(&camera.image);
+        commands.entity(entity).despawn();
+    }
+}
+
+// Turns the clip camera on for a frame each time one is due, reading back
+// what it drew, and off again the frame after.
+pub fn record_clip_frames(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut clip: ResMut<ClipBuffer>,
+    mut cameras: Query<(Entity, &mut Camera, &ClipCamera)>,
+) {
+    let due = clip.timer.tick(time.delta()).just_finished();
+    for (entity, mut camera, clip_camera) in cameras.iter_mut() {
+        if !due {
+            if camera.is_active {
+                camera.is_active = false;
+                commands.entity(entity).remove::<Readback>();
+            }
+            continue;
+        }
+        camera.is_active = true;
+        let frames = clip.frames.clone();
+        let readback = Readback::new(clip_camera.image.clone(), move |image| {
+            let mut frames = frames.lock().unwrap();
+            frames.push_back(image);
+            while frames.len() > capacity() {
+                frames.pop_front();
+            }
+        });
+        commands.entity(entity).insert(readback);
+    }
+}
+
+fn write_gif(path: &Path, frames: Vec<RgbaImage>) -> ImageResult<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(1000, CLIP_FPS as u32);
+    encoder.encode_frames(
+        frames
+            .into_iter()
+            .map(|frame| Frame::from_parts(frame, 0, 0, delay)),
+    )
+}
+
+// Encoding takes a while, so it happens off the main thread.
+pub fn export_clip(keys: Res<Input<KeyCode>>, clip: Res<ClipBuffer>) {
+    if !keys.just_pressed(CLIP_KEY) {
+        return;
+    }
+    let frames: Vec<RgbaImage> = clip.frames.lock().unwrap().iter().cloned().collect();
+    if frames.is_empty() {
+        return;
+    }
+    let Some(path) = capture_path("clip", "gif") else {
+        return;
+    };
+    IoTaskPool::get()
+        .spawn(async move {
+            let count = frames.len();
+            match write_gif(&path, frames) {
+                Ok(()) => info!("Saved {count} frame clip to {}", path.display()),
+                Err(err) => warn!("Can't write {}: {err}", path.display()),
+            }
+        })
+        .detach();
+}
+
+// An image a camera can draw into and a Readback can copy from.
+pub fn offscreen_image(images: &mut Assets<Image>, width: u32, height: u32) -> Handle<Image> {
+    let size = Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("offscreen_image"),
+            size,
+            dimension: TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    images.add(image)
+}
+
+// A second view through the fight camera, drawn into an image rather than
+// the window. Kept looking the same by `mirror_fight_camera` while active.
+#[derive(Component)]
+pub struct OffscreenCamera;
+
+pub fn offscreen_camera(image: Handle<Image>) -> impl Bundle {
+    (
+        Camera3dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(image),
+                // Drawn ahead of the window.
+                order: -1,
+                ..default()
+            },
+            ..default()
+        },
+        // The HUD is laid out for the window, so it's left out.
+        UiCameraConfig { show_ui: false },
+        OffscreenCamera,
+    )
+}
+
+fn mirror<C: Component + Clone>(entity: &mut EntityCommands, component: Option<&C>) {
+    match component {
+        Some(component) => {
+            entity.insert(component.clone());
+        }
+        None => {
+            entity.remove::<C>();
+        }
+    }
+}
+
+// Systems take at most 16 parameters, so related ones are paired up.
+#[allow(clippy::too_many_arguments)]
+pub fn mirror_fight_camera(
+    mut commands: Commands,
+    fight_cameras: Query<
+        (
+            Entity,
+            &Transform,
+            &Projection,
+            &Camera,
+            &Camera3d,
+            &Tonemapping,
+        ),
+        (With<Cameraman>, Without<OffscreenCamera>),
+    >,
+    (blooms, post_processes): (Query<&BloomSettings>, Query<&PostProcessSettings>),
+    depths_of_field: Query<&DepthOfField>,
+    (skyboxes, environment_maps): (Query<&Skybox>, Query<&EnvironmentMapLight>),
+    mut offscreen: Query<(Entity, &mut Camera), With<OffscreenCamera>>,
+) {
+    let Ok((fight, transform, projection, fight_camera, camera_3d, tonemapping)) =
+        fight_cameras.get_single()
+    else {
+        return;
+    };
+    for (entity, mut camera) in offscreen.iter_mut() {
+        if !camera.is_active {
+            continue;
+        }
+        camera.hdr = fight_camera.hdr;
+        let mut entity = commands.entity(entity);
+        entity.insert((
+            *transform,
+            projection.clone(),
+            camera_3d.clone(),
+            *tonemapping,
+        ));
+        mirror(&mut entity, blooms.get(fight).ok());
+        mirror(&mut entity, post_processes.get(fight).ok());
+        mirror(&mut entity, depths_of_field.get(fight).ok());
+        mirror(&mut entity, skyboxes.get(fight).ok());
+        mirror(&mut entity, environment_maps.get(fight).ok());
+    }
+}
+
+// Copies `image` back from the GPU once the frame it's on at the end of the
+// update has rendered, and hands the pixels to `on_read`. That happens on
+// the render thread, so anything slow belongs on a task.
+#[derive(Component, Clone)]
+pub struct Readback {
+    pub image: Handle<Image>,
+    pub on_read: Arc<dyn Fn(RgbaImage) + Send + Sync>,
+}
+
+impl Readback {
+    pub fn new(image: Handle<Image>, on_read: impl Fn(RgbaImage) + Send + Sync + 'static) -> Self {
+        Readback {
+            image,
+            on_read: Arc::new(on_read),
+        }
+    }
+}
+
+// The readbacks asked for this frame, in the render world.
+#[derive(Resource, Default)]
+struct PendingReadbacks(Vec<Readback>);
+
+fn extract_readbacks(mut pending: ResMut<PendingReadbacks>, readbacks: Extract<Query<&Readback>>) {
+    pending.0 = readbacks.iter().cloned().collect();
+}
+
+// Queued behind the frame's own rendering, so the copy sees what it drew.
+// The pixels arrive once the GPU gets there, without waiting on it here.
+fn read_back_images(
+    pending: Res<PendingReadbacks>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    for readback in pending.0.iter() {
+        let Some(image) = images.get(&readback.image) else {
+            continue;
+        };
+        let (width, height) = (image.size.x as u32, image.size.y as u32);
+        // Rows are padded out to the alignment copies need.
+        let padded_row = RenderDevice::align_copy_bytes_per_row(width as usize * 4) as u32;
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("readback_buffer"),
+            size: padded_row as u64 * height as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("readback_encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        render_queue.submit([encoder.finish()]);
+
+        let mapped = buffer.clone();
+        let on_read = readback.on_read.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            if let Err(err) = result {
+                warn!("Readback failed: {err}");
+                return;
+            }
+            let pixels = unpad(
+                &mapped.slice(..).get_mapped_range(),
+                width,
+                height,
+                padded_row,
+            );
+            mapped.unmap();
+            if let Some(pixels) = pixels {
+                on_read(pixels);
+            }
+        });
+    }
+    if !pending.0.is_empty() {
+        render_device.poll(Maintain::Poll);
+    }
+}
+
+// Drops the padding on the end of each row and puts BGRA pixels in RGBA
+// order.
+fn unpad(data: &[u8], width: u32, height: u32, padded_row: u32) -> Option<RgbaImage> {
+    let row = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row * height as usize);
+    for line in data.chunks(padded_row as usize).take(height as usize) {
+        for bgra in line[..row].chunks_exact(4) {
+            pixels.extend([bgra[2], bgra[1], bgra[0], bgra[3]]);
+        }
+    }
+    RgbaImage::from_raw(width, height, pixels)
+}
+
+pub struct ReadbackPlugin;
+
+impl Plugin for ReadbackPlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<PendingReadbacks>()
+            .add_systems(ExtractSchedule, extract_readbacks)
+            .add_systems(
+                Render,
+                read_back_images
+                    .after(render_system)
+                    .in_set(RenderSet::Render),
+            );
+    }
+}
@@ -0,0 +1,785 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    ai::AiController,
+    asset_cache::{character_asset, AssetCache},
+    animation::{FighterAnimations, FighterSfx},
+    comeback::Comeback,
+    gauntlet::{Gauntlet, Modifier, SWIFT_SPEED},
+    input::{ControlInput, SimpleControls},
+    moves::{MoveId, MovePhase},
+    roster::{CharacterDef, EnemyControl, MatchSetup, Roster},
+    round::MatchRules,
+    skins::Skin,
+    stress::StressTest,
+    tag_team::{Benched, TagTeam},
+    throws::Grab,
+    training::TrainingDummy,
+    tuning::{ComboCounter, JuggleTuning},
+    voice::Voice,
+    GameMode,
+};
+
+pub const RUN_FORWARD_SPEED: f32 = 4.0;
+pub const RUN_BACKWARDS_SPEED: f32 = -2.5;
+// Lockouts for fighters whose clips aren't loaded, as in headless runs.
+// Otherwise a move lasts as long as its clip plays.
+pub const PUNCH_DURATION: f32 = 0.6;
+pub const KICK_DURATION: f32 = 1.0;
+pub const ELBOW_DURATION: f32 = 0.45;
+pub const CHARGE_DURATION: f32 = 1.1;
+pub const MAX_HEALTH: f32 = 100.0;
+pub const MAX_METER: f32 = 100.0;
+pub const KNOCKBACK_DAMPING: f32 = 8.0;
+// Holding left or right while knocked about steers the fighter, by at most
+// this share of the speed they're flying at.
+pub const DIRECTIONAL_INFLUENCE: f32 = 0.2;
+// Knocked into a wall at this speed or more, a fighter on their feet is
+// splatted against it for `WALL_SPLAT_SECONDS`, and one in the air bounces
+// back off it at `WALL_BOUNCE` of the speed, lifted to `WALL_BOUNCE_LIFT`.
+const WALL_HIT_SPEED: f32 = 1.5;
+const WALL_SPLAT_SECONDS: f32 = 0.7;
+const WALL_BOUNCE: f32 = 0.6;
+const WALL_BOUNCE_LIFT: f32 = 4.0;
+// How close to the wall counts as against it.
+const WALL_REACH: f32 = 0.05;
+pub const JUMP_VELOCITY: f32 = 7.0;
+pub const GRAVITY: f32 = 20.0;
+// Fighters stop this far either side of the stage centre.
+pub const STAGE_HALF_WIDTH: f32 = 4.0;
+// The fighter's pushbox, standing on the fighter's origin.
+const PUSHBOX_HALF_HEIGHT: f32 = 0.6;
+const PUSHBOX_RADIUS: f32 = 0.3;
+// Lips a fighter walks straight over, and drops a grounded fighter follows
+// down rather than falling off.
+const STEP_HEIGHT: f32 = 0.25;
+const GROUND_STICK: f32 = 1.0;
+
+// Pushboxes keep fighters off each other and inside the stage. They never
+// meet limbs, so they can't raise hits.
+pub const PUSHBOX_GROUP: Group = Group::GROUP_5;
+pub const STAGE_GROUP: Group = Group::GROUP_6;
+
+#[derive(Default, PartialEq, Eq, Hash, Copy, Clone, Debug, Deserialize)]
+pub enum AnimationState {
+    #[default]
+    Idle,
+    Punching,
+    Kicking,
+    Running,
+    RunningBackwards,
+    Blocking,
+    Crouching,
+    Jumping,
+    Elbowing,
+    Charging,
+}
+
+#[derive(Component)]
+pub struct Player;
+
+#[derive(Component)]
+pub struct Enemy;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Team {
+    One,
+    Two,
+}
+
+// The fighter on the other side of this one's fight. Looked up directly so
+// systems don't have to search every fighter for the other team.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Opponent(pub Entity);
+
+impl Team {
+    pub fn opponent(&self) -> Team {
+        match self {
+            Team::One => Team::Two,
+            Team::Two => Team::One,
+        }
+    }
+}
+
+// +1 when the fighter faces +x, -1 when it faces -x. Models face local +Z.
+pub fn facing(transform: &Transform) -> f32 {
+    (transform.rotation * Vec3::Z).x.signum()
+}
+
+// The roster entry a fighter was spawned from.
+#[derive(Component, Clone, Debug)]
+pub struct Character(pub Handle<CharacterDef>);
+
+// A fighter knocked into the air and being kept there by hits. `bounce` is
+// spent on the first landing; the second one puts them on the floor.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Juggle {
+    pub hits: u32,
+    pub gravity: f32,
+    pub bounce: Option<f32>,
+    pub knockdown: f32,
+}
+
+#[derive(Component, Default, Clone)]
+pub struct CharacterState {
+    pub player_state: AnimationState,
+    pub old_player_state: AnimationState,
+    pub current_animation_timer: Option<Timer>,
+    pub airborne: bool,
+    pub vertical_velocity: f32,
+    pub juggle: Option<Juggle>,
+    // Whether they've hit the wall since they were last free to act, so a
+    // combo only splats or bounces them off it once.
+    pub walled: bool,
+    // On the floor after a juggle or a throw, until they get back up.
+    pub downed: bool,
+    // Caught in a throw that hasn't landed yet.
+    pub grabbed: Option<Grab>,
+    // Filled by clean hits and draining over time. A fighter whose gauge
+    // reaches STUN_GAUGE_MAX goes dizzy.
+    pub stun_gauge: f32,
+    pub dizzy: bool,
+    // Whether a ground bounce has hit them since they were last free to act.
+    pub ground_bounced: bool,
+    // Whether the current move's armor has already absorbed a hit.
+    pub armor_spent: bool,
+    // Whether the current move has hit or been blocked, opening its cancels.
+    pub move_connected: bool,
+    // Counts the attacks started, cancels included, so each use of a move
+    // is told apart from the last even when it's the same move again.
+    pub attack: u32,
+    // Who the current attack has connected with, and how many times.
+    pub struck: Vec<(Entity, u32)>,
+    // Phase of the attack being performed, if any.
+    pub move_phase: Option<MovePhase>,
+    // Where the legs are walking while an upper-body move plays: Running,
+    // RunningBackwards, or Idle to stand still.
+    pub stride: AnimationState,
+}
+
+impl CharacterState {
+    pub fn update_player_state(&mut self, new_state: AnimationState) {
+        self.old_player_state = self.player_state;
+        self.player_state = new_state;
+        if self.old_player_state != self.player_state {
+            //println!("Old Player state: {:?}", self.old_player_state);
+            //println!("Player state: {:?}", self.player_state);
+        }
+    }
+
+    // Can't act for `seconds`, held in `state`. Cancels any attack.
+    pub fn stun(&mut self, seconds: f32, state: AnimationState) {
+        self.update_player_state(state);
+        self.current_animation_timer = Some(Timer::from_seconds(seconds, TimerMode::Once));
+        self.refresh_move_phase();
+    }
+
+    // Works the attack phase out from the current state and its timer.
+    pub fn refresh_move_phase(&mut self) {
+        self.move_phase = MoveId::from_state(self.player_state)
+            .filter(|_| self.current_animation_timer.is_some())
+            .map(|move_id| move_id.data().phase_at(self.move_elapsed()));
+    }
+
+    // What the legs are doing. Usually the same as the whole body, but an
+    // upper-body move leaves them walking underneath it.
+    pub fn legs_state(&self) -> AnimationState {
+        let upper_body =
+            MoveId::from_state(self.player_state).is_some_and(|move_id| move_id.data().upper_body);
+        if upper_body && self.current_animation_timer.is_some() {
+            self.stride
+        } else {
+            self.player_state
+        }
+    }
+
+    // Seconds into the current move, or zero between moves.
+    pub fn move_elapsed(&self) -> f32 {
+        self.current_animation_timer
+            .as_ref()
+            .map_or(0.0, |timer| timer.elapsed_secs())
+    }
+
+    // Whether the move being performed can be cut short into `next` now.
+    // Stuns and moves that whiffed can't be, and nothing cancels in recovery.
+    pub fn can_cancel_into(&self, next: MoveId) -> bool {
+        let Some(current) = MoveId::from_state(self.player_state) else {
+            return false;
+        };
+        self.move_connected
+            && self.current_animation_timer.is_some()
+            && matches!(self.move_phase, Some(MovePhase::Startup | MovePhase::Active))
+            && current.data().cancels_into.contains(&next)
+    }
+
+    // A new attack: nobody has been struck by it yet.
+    pub fn begin_attack(&mut self) {
+        self.attack = self.attack.wrapping_add(1);
+        self.struck.clear();
+    }
+
+    // Counts a connection with `defender` if the current attack has any of
+    // its `allowed` hits left for them, and says whether it did.
+    pub fn strike(&mut self, defender: Entity, allowed: u32) -> bool {
+        let Some(index) = self
+            .struck
+            .iter()
+            .position(|(struck, _)| *struck == defender)
+        else {
+            self.struck.push((defender, 1));
+            return true;
+        };
+        let hits = &mut self.struck[index].1;
+        if *hits >= allowed {
+            return false;
+        }
+        *hits += 1;
+        true
+    }
+
+    // The current move is still in its startup and its armor is unused.
+    pub fn armored(&self) -> bool {
+        self.move_phase == Some(MovePhase::Startup)
+            && !self.armor_spent
+            && MoveId::from_state(self.player_state).is_some_and(|move_id| move_id.data().armor)
+    }
+
+    // Fighters on the floor after a juggle can only be hit by a move that
+    // bounces them back up, and only once a combo.
+    pub fn hittable(&self, ground_bounce: bool) -> bool {
+        !self.downed || (ground_bounce && !self.ground_bounced)
+    }
+
+    // A clean hit from a move with a ground bounce. A fighter on the floor
+    // is popped straight back up into a fresh juggle; anyone else takes it
+    // like any other hit, then bounces off the floor at `speed` when they
+    // next land.
+    pub fn ground_bounce_hit(&mut self, tuning: &JuggleTuning, launcher: bool, speed: f32) {
+        if self.ground_bounced {
+            self.juggle_hit(tuning, launcher);
+            return;
+        }
+        self.ground_bounced = true;
+        if self.downed {
+            self.downed = false;
+            self.juggle = Some(Juggle {
+                hits: 0,
+                gravity: GRAVITY,
+                bounce: None,
+                knockdown: tuning.knockdown,
+            });
+            self.airborne = true;
+            self.vertical_velocity = speed;
+            self.current_animation_timer = None;
+            self.update_player_state(AnimationState::Jumping);
+            self.refresh_move_phase();
+            return;
+        }
+        self.juggle_hit(tuning, launcher);
+        if let Some(juggle) = self.juggle.as_mut() {
+            juggle.bounce = Some(speed);
+        }
+    }
+
+    // Starts or extends a juggle after a clean hit. Grounded fighters only go
+    // up for launchers; anyone already in the air is kept there.
+    pub fn juggle_hit(&mut self, tuning: &JuggleTuning, launcher: bool) {
+        let juggle = match self.juggle {
+            Some(juggle) => Juggle {
+                hits: juggle.hits + 1,
+                ..juggle
+            },
+            None if launcher || self.airborne => Juggle {
+                hits: 0,
+                gravity: GRAVITY,
+                bounce: Some(tuning.bounce),
+                knockdown: tuning.knockdown,
+            },
+            None => return,
+        };
+        self.vertical_velocity = if self.airborne {
+            tuning.lift * tuning.lift_decay.powi(juggle.hits as i32)
+        } else {
+            tuning.launch
+        };
+        self.juggle = Some(Juggle {
+            gravity: GRAVITY * (1.0 + tuning.gravity_per_hit * juggle.hits as f32),
+            ..juggle
+        });
+        self.airborne = true;
+        self.current_animation_timer = None;
+        self.update_player_state(AnimationState::Jumping);
+        self.refresh_move_phase();
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health {
+            current: MAX_HEALTH,
+            max: MAX_HEALTH,
+        }
+    }
+}
+
+// Built up by landing and taking hits. Nothing spends it yet.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Meter {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Meter {
+            current: 0.0,
+            max: MAX_METER,
+        }
+    }
+}
+
+impl Meter {
+    pub fn gain(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+}
+
+// Velocity pushed onto a fighter by hits, bled off over time.
+#[derive(Component, Default, Clone, Copy, PartialEq, Debug)]
+pub struct Knockback(pub Vec3);
+
+// A juggled fighter has hit the floor for good. `hits` is how many hits
+// kept them up after the first.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct KnockedDown {
+    pub fighter: Entity,
+    pub position: Vec3,
+    pub hits: u32,
+}
+
+// A fighter knocked into the wall: splatted against it, or bounced off it
+// when `splat` is false. `position` is where they hit it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WallHit {
+    pub fighter: Entity,
+    pub position: Vec3,
+    pub splat: bool,
+}
+
+impl Team {
+    pub fn start_transform(&self) -> Transform {
+        match self {
+            Team::One => {
+                Transform::from_rotation(Quat::from_rotation_y(std::f32::consts::PI / 2.0))
+                    .with_translation(Vec3::new(-3.0, 0.0, 0.0))
+            }
+            Team::Two => {
+                Transform::from_rotation(Quat::from_rotation_y(-std::f32::consts::PI / 2.0))
+                    .with_translation(Vec3::new(3.0, 0.0, 0.0))
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_fighters(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    roster: Res<Roster>,
+    setup: Res<MatchSetup>,
+    mode: Res<GameMode>,
+    rules: Res<MatchRules>,
+    stress: Res<StressTest>,
+    cache: Res<AssetCache>,
+    characters: Res<Assets<CharacterDef>>,
+    gauntlet: Res<Gauntlet>,
+) {
+    let pairs = if *mode == GameMode::Stress { stress.pairs.max(1) } else { 1 };
+    for pair in 0..pairs {
+        // Extra stress pairs cycle through the roster so every model is drawn.
+        let pick = |index: usize| (index + pair) % roster.characters.len();
+        let mut fighters = Vec::with_capacity(2);
+        for (team, index) in [(Team::One, pick(setup.player)), (Team::Two, pick(setup.enemy))] {
+            let handle = roster.characters[index].clone();
+            let Some(def) = characters.get(&handle) else {
+                continue;
+            };
+
+            let (name, skin) = match team {
+                Team::One => (def.name.clone(), setup.player_skin),
+                Team::Two if setup.is_mirror() => (
+                    format!("{} (2P)", def.name),
+                    setup.resolved_enemy_skin(def.palettes.len()),
+                ),
+                Team::Two => (def.name.clone(), setup.enemy_skin),
+            };
+
+            let mut transform = team.start_transform();
+            transform.translation += StressTest::lane(pair);
+            let mut fighter = commands.spawn(SpatialBundle::from_transform(transform));
+            fighter
+                .insert(Name::new(name))
+                .insert(Character(handle))
+                .insert(team)
+                .insert(CharacterState::default())
+                .insert(Health {
+                    current: rules.team_health(team),
+                    max: rules.team_health(team),
+                })
+                .insert(Knockback::default())
+                .insert(ComboCounter::default())
+                .insert(Meter::default())
+                .insert(Comeback::default())
+                .insert(Skin(skin))
+                .insert(ControlInput::default())
+                .insert(RigidBody::KinematicPositionBased)
+                .insert(pushbox())
+                .insert(CollisionGroups::new(PUSHBOX_GROUP, PUSHBOX_GROUP | STAGE_GROUP))
+                .insert(KinematicCharacterController {
+                    up: Vec3::Y,
+                    snap_to_ground: Some(CharacterLength::Absolute(STEP_HEIGHT)),
+                    autostep: Some(CharacterAutostep {
+                        max_height: CharacterLength::Absolute(STEP_HEIGHT),
+                        min_width: CharacterLength::Absolute(0.1),
+                        include_dynamic_bodies: false,
+                    }),
+                    filter_groups: Some(CollisionGroups::new(
+                        PUSHBOX_GROUP,
+                        PUSHBOX_GROUP | STAGE_GROUP,
+                    )),
+                    ..default()
+                });
+            if *mode == GameMode::TagTeam {
+                let partner = match team {
+                    Team::One => setup.player_partner,
+                    Team::Two => setup.enemy_partner,
+                };
+                let character = roster.characters[pick(partner)].clone();
+                if let Some(partner_def) = characters.get(&character) {
+                    fighter.insert(TagTeam::new(Benched {
+                        name: partner_def.name.clone(),
+                        character,
+                        skin,
+                        health: Health {
+                            current: rules.team_health(team),
+                            max: rules.team_health(team),
+                        },
+                        red: 0.0,
+                    }));
+                }
+            }
+            fighters.push(fighter.id());
+            // Only the first pair is the one the HUD and the player follow.
+            let own_profile = cache.get(&character_asset(&roster.ids[index], "ai"));
+            if pair > 0 {
+                fighter.insert(AiController::new(own_profile, setup.difficulty));
+                continue;
+            }
+            match team {
+                Team::One => {
+                    fighter.insert((Player, SimpleControls::default()));
+                    // The attract demo and the stress test hand the player's
+                    // side to the AI too.
+                    if matches!(*mode, GameMode::Demo | GameMode::Stress) {
+                        fighter.insert(AiController::new(own_profile, setup.difficulty));
+                    }
+                }
+                // Tournament matches are always two people.
+                Team::Two if *mode == GameMode::Tournament => {
+                    fighter.insert((Enemy, Player, SimpleControls::default()));
+                }
+                Team::Two => {
+                    fighter.insert(Enemy);
+                    if let Some(modifier) =
+                        gauntlet.modifier.filter(|_| *mode == GameMode::Gauntlet)
+                    {
+                        fighter.insert(modifier);
+                    }
+                    // Every practice mode fights the dummy.
+                    if mode.fights_dummy() {
+                        fighter.insert(TrainingDummy::default());
+                        continue;
+                    }
+                    let profile = match &setup.enemy_control {
+                        EnemyControl::CharacterProfile => Some(own_profile),
+                        EnemyControl::Profile(path) => Some(asset_server.load(path.clone())),
+                        EnemyControl::Idle => None,
+                    };
+                    if let Some(profile) = profile {
+                        fighter.insert(AiController::new(profile, setup.difficulty));
+                    }
+                }
+            }
+        }
+        if let [one, two] = fighters[..] {
+            commands.entity(one).insert(Opponent(two));
+            commands.entity(two).insert(Opponent(one));
+        }
+    }
+}
+
+pub fn attach_fighter_models(
+    mut commands: Commands,
+    cache: Res<AssetCache>,
+    roster: Res<Roster>,
+    fighters: Query<(Entity, &Character), Added<Character>>,
+) {
+    for (entity, character) in &fighters {
+        let Some(id) = roster.id(&character.0) else {
+            continue;
+        };
+        let scene: Handle<Scene> = cache.get(&character_asset(id, "scene"));
+        commands
+            .entity(entity)
+            .insert(scene)
+            .insert(FighterAnimations::cached(&cache, id))
+            .insert(FighterSfx::cached(&cache, id))
+            .insert(Voice::default());
+    }
+}
+
+fn pushbox() -> Collider {
+    Collider::compound(vec![(
+        Vec3::Y * (PUSHBOX_HALF_HEIGHT + PUSHBOX_RADIUS),
+        Quat::IDENTITY,
+        Collider::capsule_y(PUSHBOX_HALF_HEIGHT, PUSHBOX_RADIUS),
+    )])
+}
+
+// A raised section of floor between two x positions, for stages that aren't
+// flat. Fighters start on the floor at x = ±3, so steps belong elsewhere.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct FloorStep {
+    pub from: f32,
+    pub to: f32,
+    pub height: f32,
+}
+
+#[derive(Component)]
+pub struct StageFloor;
+
+fn stage_groups() -> CollisionGroups {
+    CollisionGroups::new(STAGE_GROUP, PUSHBOX_GROUP)
+}
+
+// Replaces whatever floor there was with a plane at `ground` and a block for
+// each step on top of it.
+pub fn spawn_floor(
+    commands: &mut Commands,
+    existing: &Query<Entity, With<StageFloor>>,
+    ground: f32,
+    steps: &[FloorStep],
+) {
+    for entity in existing.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.spawn((
+        Name::new("floor"),
+        TransformBundle::from_transform(Transform::from_xyz(0.0, ground - 0.5, 0.0)),
+        Collider::cuboid(50.0, 0.5, 50.0),
+        stage_groups(),
+        StageFloor,
+    ));
+    for step in steps {
+        let half_height = (step.height - ground).max(0.0) / 2.0;
+        commands.spawn((
+            Name::new("floor_step"),
+            TransformBundle::from_transform(Transform::from_xyz(
+                (step.from + step.to) / 2.0,
+                ground + half_height,
+                0.0,
+            )),
+            Collider::cuboid((step.to - step.from).abs() / 2.0, half_height, 50.0),
+            stage_groups(),
+            StageFloor,
+        ));
+    }
+}
+
+// A flat floor at y = 0 and the walls at either end of the stage. The stage
+// model is only scenery, so these are what fighters actually stand on and stop
+// against; a stage with its own floor swaps the flat one out once it loads.
+pub fn spawn_stage_bounds(mut commands: Commands, existing: Query<Entity, With<StageFloor>>) {
+    spawn_floor(&mut commands, &existing, 0.0, &[]);
+    for side in [-1.0, 1.0] {
+        let x = side * (STAGE_HALF_WIDTH + PUSHBOX_RADIUS + 0.5);
+        commands.spawn((
+            Name::new("wall"),
+            TransformBundle::from_transform(Transform::from_xyz(x, 10.0, 0.0)),
+            Collider::cuboid(0.5, 10.0, 50.0),
+            stage_groups(),
+        ));
+    }
+}
+
+// Works out where each fighter wants to go this tick and hands the move to
+// its character controller, which stops it at the floor, the walls and the
+// other fighter when physics steps later in the tick.
+pub fn process_movement(
+    time: Res<Time>,
+    mut fighters: Query<(
+        Entity,
+        &Transform,
+        &mut KinematicCharacterController,
+        Option<&KinematicCharacterControllerOutput>,
+        &mut CharacterState,
+        &mut Knockback,
+        &ControlInput,
+        Option<&Modifier>,
+    )>,
+    mut knocked_down: EventWriter<KnockedDown>,
+    mut wall_hits: EventWriter<WallHit>,
+) {
+    let dt = time.delta_seconds();
+    for (fighter, transform, mut controller, output, mut player, mut knockback, input, modifier) in
+        fighters.iter_mut()
+    {
+        let facing = facing(transform);
+        let speed = if modifier == Some(&Modifier::Swift) {
+            SWIFT_SPEED
+        } else {
+            1.0
+        };
+        let mut step = Vec3::ZERO;
+        let legs = player.legs_state();
+        if legs == AnimationState::Running {
+            step.x += RUN_FORWARD_SPEED * speed * facing * dt;
+        } else if legs == AnimationState::RunningBackwards {
+            step.x += RUN_BACKWARDS_SPEED * speed * facing * dt;
+        }
+        // Steering only bends the arc of a hit; it can't carry a fighter
+        // anywhere once the hit has worn off, or while blocking.
+        let reeling = player.juggle.is_some()
+            || (player.current_animation_timer.is_some()
+                && player.player_state == AnimationState::Idle);
+        if reeling {
+            let held = input.right as i8 - input.left as i8;
+            let flying = knockback.0 + Vec3::Y * player.vertical_velocity.max(0.0);
+            step.x += held as f32 * flying.length() * DIRECTIONAL_INFLUENCE * dt;
+        }
+
+        // Knocked into a wall hard enough. Only once a combo, so the wall
+        // can't hold a fighter there for good.
+        let side = transform.translation.x.signum();
+        let against_wall = transform.translation.x.abs() >= STAGE_HALF_WIDTH - WALL_REACH;
+        if !reeling {
+            player.walled = false;
+            player.ground_bounced = false;
+        } else if !player.walled && against_wall && knockback.0.x * side >= WALL_HIT_SPEED {
+            player.walled = true;
+            let splat = !player.airborne;
+            if splat {
+                // Stuck there, open to whatever comes next.
+                knockback.0 = Vec3::ZERO;
+                player.stun(WALL_SPLAT_SECONDS, AnimationState::Idle);
+            } else {
+                knockback.0.x *= -WALL_BOUNCE;
+                player.vertical_velocity = player.vertical_velocity.max(WALL_BOUNCE_LIFT);
+            }
+            wall_hits.send(WallHit {
+                fighter,
+                position: Vec3::new(
+                    side * (STAGE_HALF_WIDTH + PUSHBOX_RADIUS),
+                    transform.translation.y + PUSHBOX_HALF_HEIGHT + PUSHBOX_RADIUS,
+                    transform.translation.z,
+                ),
+                splat,
+            });
+        }
+        step += knockback.0 * dt;
+        knockback.0 *= (1.0 - KNOCKBACK_DAMPING * dt).max(0.0);
+
+        // Walked off a step: fall from here rather than float.
+        if !player.airborne && output.is_some_and(|output| !output.grounded) {
+            player.airborne = true;
+            player.vertical_velocity = 0.0;
+        }
+        if player.airborne {
+            let grounded = output.is_some_and(|output| output.grounded);
+            if grounded && player.vertical_velocity <= 0.0 {
+                player.vertical_velocity = 0.0;
+                let bounce = player.juggle.as_mut().and_then(|juggle| juggle.bounce.take());
+                if let Some(bounce) = bounce {
+                    player.vertical_velocity = bounce;
+                } else {
+                    if let Some(juggle) = player.juggle.take() {
+                        // Knocked down; process_input waits out the timer.
+                        player.current_animation_timer =
+                            Some(Timer::from_seconds(juggle.knockdown, TimerMode::Once));
+                        player.downed = true;
+                        knocked_down.send(KnockedDown {
+                            fighter,
+                            position: transform.translation,
+                            hits: juggle.hits,
+                        });
+                    }
+                    player.airborne = false;
+                    player.update_player_state(AnimationState::Idle);
+                }
+            }
+        }
+        if player.airborne {
+            let gravity = player.juggle.map_or(GRAVITY, |juggle| juggle.gravity);
+            step.y += player.vertical_velocity * dt;
+            player.vertical_velocity -= gravity * dt;
+        } else {
+            // Pressed into the floor so the controller keeps reporting it.
+            step.y -= GROUND_STICK * dt;
+        }
+
+        // Added to any root motion the animation has given it since the
+        // last tick.
+        controller.translation = Some(controller.translation.unwrap_or_default() + step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_move_strikes_each_fighter_once() {
+        let mut state = CharacterState {
+            player_state: AnimationState::Kicking,
+            ..default()
+        };
+        let (first, second) = (Entity::from_raw(1), Entity::from_raw(2));
+        let hits = MoveId::Kick.data().hits;
+        assert!(state.strike(first, hits));
+        assert!(!state.strike(first, hits));
+        assert!(state.strike(second, hits));
+    }
+
+    #[test]
+    fn a_two_hit_move_connects_exactly_twice() {
+        let mut state = CharacterState::default();
+        let defender = Entity::from_raw(1);
+        state.begin_attack();
+        let connected = (0..5).filter(|_| state.strike(defender, 2)).count();
+        assert_eq!(connected, 2);
+        // The next attack gets its own hits, even if it's the same move.
+        let attack = state.attack;
+        state.begin_attack();
+        assert_ne!(state.attack, attack);
+        assert!(state.strike(defender, 2));
+    }
+
+    #[test]
+    fn downed_fighters_only_take_one_ground_bounce() {
+        let mut state = CharacterState::default();
+        assert!(state.hittable(false));
+        state.downed = true;
+        assert!(!state.hittable(false));
+        assert!(state.hittable(true));
+        state.ground_bounced = true;
+        assert!(!state.hittable(true));
+    }
+}
@@ -0,0 +1,536 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+    },
+};
+
+use crate::{
+    animation::FighterAnimations,
+    asset_cache::{character_asset, AssetCache},
+    character::Team,
+    locale::Strings,
+    menu::{menu_item, Menu, MenuAction, MenuEvent},
+    profile::{Profile, Unlock},
+    roster::{CharacterDef, CharacterStats, MatchSetup, Roster, MAX_STAT},
+    versus::Rivalry,
+    AppState, GameMode,
+};
+
+// The preview is a little sub-scene well below the stage, filmed by its own
+// camera into a texture the select screen shows.
+const PREVIEW_ORIGIN: Vec3 = Vec3::new(0.0, -100.0, 0.0);
+const PREVIEW_SIZE: u32 = 512;
+// Radians per second.
+const PREVIEW_SPIN: f32 = 0.8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SelectSlot {
+    #[default]
+    Player,
+    PlayerPartner,
+    Enemy,
+    EnemyPartner,
+}
+
+impl SelectSlot {
+    // The order fighters are picked in. Each side's partner is only picked
+    // for tag team matches, and the gauntlet picks its own opponents.
+    fn order(mode: GameMode) -> &'static [SelectSlot] {
+        match mode {
+            GameMode::TagTeam => &[
+                SelectSlot::Player,
+                SelectSlot::PlayerPartner,
+                SelectSlot::Enemy,
+                SelectSlot::EnemyPartner,
+            ],
+            GameMode::Gauntlet => &[SelectSlot::Player],
+            _ => &[SelectSlot::Player, SelectSlot::Enemy],
+        }
+    }
+
+    fn step(self, mode: GameMode, offset: isize) -> Option<SelectSlot> {
+        let order = SelectSlot::order(mode);
+        let index = order.iter().position(|slot| *slot == self)?;
+        order.get(index.checked_add_signed(offset)?).copied()
+    }
+
+    fn pick(self, setup: &mut MatchSetup) -> &mut usize {
+        match self {
+            SelectSlot::Player => &mut setup.player,
+            SelectSlot::PlayerPartner => &mut setup.player_partner,
+            SelectSlot::Enemy => &mut setup.enemy,
+            SelectSlot::EnemyPartner => &mut setup.enemy_partner,
+        }
+    }
+
+    // Partners always wear their default colours.
+    fn skin(self, setup: &mut MatchSetup) -> Option<&mut usize> {
+        match self {
+            SelectSlot::Player => Some(&mut setup.player_skin),
+            SelectSlot::Enemy => Some(&mut setup.enemy_skin),
+            SelectSlot::PlayerPartner | SelectSlot::EnemyPartner => None,
+        }
+    }
+
+    fn title_key(self) -> &'static str {
+        match self {
+            SelectSlot::Player => "select.player",
+            SelectSlot::PlayerPartner => "select.partner",
+            SelectSlot::Enemy => "select.opponent",
+            SelectSlot::EnemyPartner => "select.opponent_partner",
+        }
+    }
+}
+
+// Which fighter is being picked. The highlighted character is the focused
+// item of the `SelectMenu`.
+#[derive(Resource, Default)]
+pub struct CharacterSelect {
+    pub slot: SelectSlot,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stat {
+    Power,
+    Speed,
+    Defense,
+}
+
+impl Stat {
+    fn label_key(&self) -> &'static str {
+        match self {
+            Stat::Power => "select.power",
+            Stat::Speed => "select.speed",
+            Stat::Defense => "select.defense",
+        }
+    }
+
+    fn value(&self, stats: &CharacterStats) -> u32 {
+        match self {
+            Stat::Power => stats.power,
+            Stat::Speed => stats.speed,
+            Stat::Defense => stats.defense,
+        }
+    }
+}
+
+const STATS: [Stat; 3] = [Stat::Power, Stat::Speed, Stat::Defense];
+
+// Everything the select screen spawns, so it can all go when it closes.
+#[derive(Component)]
+pub struct SelectScreen;
+
+#[derive(Component, Default)]
+pub struct PreviewModel {
+    showing: Option<usize>,
+}
+
+// The row of character names; its focus is the highlighted character.
+#[derive(Component)]
+pub struct SelectMenu;
+
+#[derive(Component)]
+pub struct SelectTitle;
+
+#[derive(Component)]
+pub struct SelectName;
+
+// The highlighted character's palette, once alternate skins are unlocked.
+#[derive(Component)]
+pub struct SelectSkin;
+
+#[derive(Component)]
+pub struct StatBar(Stat);
+
+// Fixed text, filled in from the locale.
+#[derive(Component)]
+pub struct SelectLabel(&'static str);
+
+fn preview_image(images: &mut Assets<Image>) -> Handle<Image> {
+    let size = Extent3d {
+        width: PREVIEW_SIZE,
+        height: PREVIEW_SIZE,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("character_preview"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    images.add(image)
+}
+
+fn text(value: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn setup_character_select(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    setup: Res<MatchSetup>,
+    mode: Res<GameMode>,
+    rivalry: Res<Rivalry>,
+    strings: Strings,
+    mut select: ResMut<CharacterSelect>,
+) {
+    select.slot = SelectSlot::Player;
+    let image = preview_image(&mut images);
+
+    commands.spawn((
+        Camera3dBundle {
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::Custom(Color::rgb(0.08, 0.08, 0.12)),
+                ..default()
+            },
+            camera: Camera {
+                order: -1,
+                target: RenderTarget::Image(image.clone()),
+                ..default()
+            },
+            transform: Transform::from_translation(PREVIEW_ORIGIN + Vec3::new(0.0, 1.2, 4.0))
+                .looking_at(PREVIEW_ORIGIN + Vec3::Y, Vec3::Y),
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        SelectScreen,
+    ));
+    commands.spawn((
+        PointLightBundle {
+            point_light: PointLight {
+                intensity: 1500.0,
+                ..default()
+            },
+            transform: Transform::from_translation(PREVIEW_ORIGIN + Vec3::new(1.5, 3.0, 3.0)),
+            ..default()
+        },
+        SelectScreen,
+    ));
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_translation(PREVIEW_ORIGIN)),
+        PreviewModel::default(),
+        SelectScreen,
+    ));
+
+    // Drawn over the loading cover, which stays down until the fight starts.
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                z_index: ZIndex::Global(101),
+                ..default()
+            },
+            SelectScreen,
+        ))
+        .with_children(|screen| {
+            screen.spawn((text("", 32.0), SelectTitle));
+            // Win streaks carry over from the last versus match.
+            if *mode == GameMode::Versus && rivalry.streaks != [0, 0] {
+                let streak = strings.fill(
+                    "select.streak",
+                    &[
+                        ("one", &rivalry.streak(Team::One)),
+                        ("two", &rivalry.streak(Team::Two)),
+                    ],
+                );
+                screen.spawn(text(&streak, 22.0));
+            }
+            screen.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(320.0),
+                    height: Val::Px(320.0),
+                    ..default()
+                },
+                image: UiImage::new(image),
+                ..default()
+            });
+            screen
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    Menu {
+                        focused: setup.player,
+                        ..Menu::horizontal(roster.characters.len(), 0)
+                    },
+                    SelectMenu,
+                ))
+                .with_children(|tabs| {
+                    for (index, handle) in roster.characters.iter().enumerate() {
+                        let name = characters
+                            .get(handle)
+                            .map_or(String::new(), |def| def.name.clone());
+                        tabs.spawn(menu_item(index, text(&name, 24.0)));
+                    }
+                });
+            screen.spawn((text("", 40.0), SelectName));
+            screen.spawn((text("", 20.0), SelectSkin));
+            for stat in STATS {
+                screen
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(12.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            text("", 22.0).with_style(Style {
+                                width: Val::Px(100.0),
+                                ..default()
+                            }),
+                            SelectLabel(stat.label_key()),
+                        ));
+                        row.spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(200.0),
+                                height: Val::Px(14.0),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.2, 0.2, 0.25).into(),
+                            ..default()
+                        })
+                        .with_children(|bar| {
+                            bar.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        height: Val::Percent(100.0),
+                                        ..default()
+                                    },
+                                    background_color: Color::rgb(1.0, 0.75, 0.2).into(),
+                                    ..default()
+                                },
+                                StatBar(stat),
+                            ));
+                        });
+                    });
+            }
+            screen.spawn((text("", 20.0), SelectLabel("select.hint")));
+        });
+}
+
+pub fn close_character_select(
+    mut commands: Commands,
+    screens: Query<Entity, With<SelectScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Confirm locks in each pick in turn, then the opponent's, cancel goes back
+// to the one before. With alternate skins unlocked, Up/Down picks colours.
+#[allow(clippy::too_many_arguments)]
+pub fn character_select_keys(
+    mode: Res<GameMode>,
+    profile: Res<Profile>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    mut events: EventReader<MenuEvent>,
+    mut menus: Query<&mut Menu, With<SelectMenu>>,
+    mut select: ResMut<CharacterSelect>,
+    mut setup: ResMut<MatchSetup>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.iter() {
+        let Ok(mut menu) = menus.get_mut(event.menu) else {
+            continue;
+        };
+        let palettes = roster
+            .characters
+            .get(event.item)
+            .and_then(|handle| characters.get(handle))
+            .map_or(1, |def| def.palettes.len().max(1));
+        match event.action {
+            MenuAction::Up | MenuAction::Down if profile.is_unlocked(Unlock::AlternateSkins) => {
+                let step = match event.action {
+                    MenuAction::Up => -1,
+                    _ => 1,
+                };
+                if let Some(skin) = select.slot.skin(&mut setup) {
+                    *skin = (*skin as isize + step).rem_euclid(palettes as isize) as usize;
+                }
+            }
+            MenuAction::Confirm => {
+                *select.slot.pick(&mut setup) = event.item;
+                // Moving on to another character can leave the pick past the
+                // end of their palettes.
+                if let Some(skin) = select.slot.skin(&mut setup) {
+                    if *skin >= palettes {
+                        *skin = 0;
+                    }
+                }
+                if let Some(next) = select.slot.step(*mode, 1) {
+                    select.slot = next;
+                    menu.focused = *next.pick(&mut setup);
+                    continue;
+                }
+                // Versus stops to pick the rules; practice modes go straight in.
+                next_state.set(match *mode {
+                    GameMode::Versus | GameMode::Demo | GameMode::TagTeam => AppState::MatchOptions,
+                    GameMode::Gauntlet => AppState::Challenger,
+                    GameMode::Training
+                    | GameMode::Trials
+                    | GameMode::Tutorial
+                    | GameMode::Stress
+                    | GameMode::Tournament => AppState::Fight,
+                });
+            }
+            MenuAction::Cancel => {
+                if let Some(previous) = select.slot.step(*mode, -1) {
+                    select.slot = previous;
+                    menu.focused = *previous.pick(&mut setup);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Swaps the model when the highlight moves. The fighter animations on it get
+// the idle clip playing once the scene loads.
+pub fn update_preview_model(
+    mut commands: Commands,
+    cache: Res<AssetCache>,
+    roster: Res<Roster>,
+    menus: Query<&Menu, With<SelectMenu>>,
+    mut previews: Query<(Entity, &mut PreviewModel)>,
+) {
+    let Ok(menu) = menus.get_single() else {
+        return;
+    };
+    for (entity, mut preview) in previews.iter_mut() {
+        if preview.showing == Some(menu.focused) {
+            continue;
+        }
+        let Some(id) = roster.ids.get(menu.focused) else {
+            continue;
+        };
+        preview.showing = Some(menu.focused);
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|preview| {
+                preview.spawn((
+                    SceneBundle {
+                        scene: cache.get(&character_asset(id, "scene")),
+                        ..default()
+                    },
+                    FighterAnimations::cached(&cache, id),
+                ));
+            });
+    }
+}
+
+pub fn spin_preview_model(
+    time: Res<Time<Real>>,
+    mut previews: Query<&mut Transform, With<PreviewModel>>,
+) {
+    for mut transform in previews.iter_mut() {
+        transform.rotate_y(PREVIEW_SPIN * time.delta_seconds());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_select_panel(
+    select: Res<CharacterSelect>,
+    setup: Res<MatchSetup>,
+    profile: Res<Profile>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    strings: Strings,
+    menus: Query<Ref<Menu>, With<SelectMenu>>,
+    mut titles: Query<&mut Text, (With<SelectTitle>, Without<SelectName>)>,
+    mut names: Query<&mut Text, (With<SelectName>, Without<SelectTitle>)>,
+    mut skins: Query<&mut Text, (With<SelectSkin>, Without<SelectTitle>, Without<SelectName>)>,
+    mut labels: Query<
+        (&SelectLabel, &mut Text),
+        (
+            Without<SelectTitle>,
+            Without<SelectName>,
+            Without<SelectSkin>,
+        ),
+    >,
+    mut bars: Query<(&StatBar, &mut Style)>,
+) {
+    let Ok(menu) = menus.get_single() else {
+        return;
+    };
+    if !select.is_changed() && !setup.is_changed() && !menu.is_changed() && !strings.changed() {
+        return;
+    }
+    for (label, mut text) in labels.iter_mut() {
+        text.sections[0].value = strings.get(label.0);
+    }
+    let Some(def) = roster
+        .characters
+        .get(menu.focused)
+        .and_then(|handle| characters.get(handle))
+    else {
+        return;
+    };
+    for mut title in titles.iter_mut() {
+        title.sections[0].value = strings.get(select.slot.title_key());
+    }
+    for mut name in names.iter_mut() {
+        name.sections[0].value = def.name.clone();
+    }
+    let skin = match select.slot {
+        SelectSlot::Player => Some(setup.player_skin),
+        SelectSlot::Enemy => Some(setup.enemy_skin),
+        SelectSlot::PlayerPartner | SelectSlot::EnemyPartner => None,
+    }
+    .filter(|_| profile.is_unlocked(Unlock::AlternateSkins));
+    let palette = skin.map_or(String::new(), |skin| {
+        let name = def.palettes.get(skin).or(def.palettes.first());
+        let name = name.map_or(String::new(), |palette| palette.name.clone());
+        strings.fill("select.skin", &[("name", &name)])
+    });
+    for mut text in skins.iter_mut() {
+        text.sections[0].value = palette.clone();
+    }
+    for (bar, mut style) in bars.iter_mut() {
+        let value = bar.0.value(&def.stats).min(MAX_STAT);
+        style.width = Val::Percent(value as f32 / MAX_STAT as f32 * 100.0);
+    }
+}
@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+
+use crate::combat::Clash;
+
+const CLASH_SLOW_MOTION: f64 = 0.25;
+// Real seconds the clash holds in slow motion.
+const CLASH_HOLD: f32 = 0.25;
+
+// Brief slow motion when attacks clash. Uses real time so it ends on cue
+// however slow the game is running.
+#[derive(Resource)]
+pub struct ClashSlowMotion {
+    pub timer: Timer,
+}
+
+impl Default for ClashSlowMotion {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(CLASH_HOLD, TimerMode::Once);
+        timer.tick(timer.duration());
+        ClashSlowMotion { timer }
+    }
+}
+
+pub fn clash_slow_motion(
+    real_time: Res<Time<Real>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut slow_motion: ResMut<ClashSlowMotion>,
+    mut clashes: EventReader<Clash>,
+) {
+    if clashes.iter().next().is_some() {
+        slow_motion.timer.reset();
+        time.set_relative_speed_f64(CLASH_SLOW_MOTION);
+        return;
+    }
+    if slow_motion.timer.finished() {
+        return;
+    }
+    if slow_motion.timer.tick(real_time.delta()).just_finished() {
+        time.set_relative_speed_f64(1.0);
+    }
+}
+
+#[cfg(feature = "particles")]
+pub use sparks::*;
+
+#[cfg(feature = "particles")]
+mod sparks {
+    use bevy::prelude::*;
+    use bevy_hanabi::prelude::*;
+
+    use crate::{
+        character::WallHit,
+        combat::{Clash, HitLanded, HEAVY_HIT_DAMAGE},
+        particle_pool::{BurstKind, ParticlePool},
+        settings::Settings,
+    };
+
+    const SPARK_COUNT: f32 = 60.0;
+    const SPARK_LIFETIME: f32 = 0.4;
+    // With reduced flashing: a few dim embers that drift rather than burst.
+    const GENTLE_SPARK_COUNT: f32 = 12.0;
+    const GENTLE_SPARK_LIFETIME: f32 = 0.8;
+    // Hits that land throw a smaller spray than a clash, and heavy ones a
+    // few more.
+    const HIT_SPARK_COUNT: f32 = 16.0;
+    const HEAVY_HIT_SPARK_COUNT: f32 = 32.0;
+    const HIT_SPARK_LIFETIME: f32 = 0.25;
+    // Dust and grit knocked off the wall when a fighter slams into it.
+    const WALL_DUST_COUNT: f32 = 40.0;
+    const WALL_DUST_LIFETIME: f32 = 0.6;
+
+    #[derive(Resource)]
+    pub struct ClashSparks {
+        pub burst: BurstKind,
+        pub gentle: BurstKind,
+        pub hit: BurstKind,
+        pub heavy_hit: BurstKind,
+        pub wall: BurstKind,
+    }
+
+    fn spark_effect(
+        name: &str,
+        count: f32,
+        lifetime: f32,
+        speed: (f32, f32),
+        gradient: Gradient<Vec4>,
+    ) -> EffectAsset {
+        let writer = ExprWriter::new();
+        let init_position = SetPositionSphereModifier {
+            center: writer.lit(Vec3::ZERO).expr(),
+            radius: writer.lit(0.05).expr(),
+            dimension: ShapeDimension::Volume,
+        };
+        let speed = writer.lit(speed.0) + writer.rand(ScalarType::Float) * writer.lit(speed.1);
+        let init_velocity = SetVelocitySphereModifier {
+            center: writer.lit(Vec3::ZERO).expr(),
+            speed: speed.expr(),
+        };
+        let init_lifetime =
+            SetAttributeModifier::new(Attribute::LIFETIME, writer.lit(lifetime).expr());
+
+        // Fired by the particle pool, never on spawn.
+        EffectAsset::new(count as u32, Spawner::once(count.into(), false), writer.finish())
+            .with_name(name)
+            .init(init_position)
+            .init(init_velocity)
+            .init(init_lifetime)
+            .render(ColorOverLifetimeModifier { gradient })
+            .render(SetSizeModifier {
+                size: Vec2::splat(0.03).into(),
+                screen_space_size: false,
+            })
+    }
+
+    pub fn setup_clash_sparks(
+        mut commands: Commands,
+        mut effects: ResMut<Assets<EffectAsset>>,
+        mut pool: ResMut<ParticlePool>,
+    ) {
+        let mut gradient = Gradient::new();
+        gradient.add_key(0.0, Vec4::new(4.0, 3.5, 2.0, 1.0));
+        gradient.add_key(1.0, Vec4::new(2.0, 0.6, 0.1, 0.0));
+        let burst = spark_effect("clash_sparks", SPARK_COUNT, SPARK_LIFETIME, (3.0, 4.0), gradient);
+
+        // Kept below 1.0 so bloom never picks them up.
+        let mut gradient = Gradient::new();
+        gradient.add_key(0.0, Vec4::new(0.9, 0.7, 0.4, 0.8));
+        gradient.add_key(1.0, Vec4::new(0.6, 0.3, 0.1, 0.0));
+        let gentle = spark_effect(
+            "clash_embers",
+            GENTLE_SPARK_COUNT,
+            GENTLE_SPARK_LIFETIME,
+            (0.5, 1.0),
+            gradient,
+        );
+
+        let mut gradient = Gradient::new();
+        gradient.add_key(0.0, Vec4::new(3.0, 2.5, 1.5, 1.0));
+        gradient.add_key(1.0, Vec4::new(1.5, 0.4, 0.1, 0.0));
+        let hit = spark_effect(
+            "hit_sparks",
+            HIT_SPARK_COUNT,
+            HIT_SPARK_LIFETIME,
+            (1.5, 2.0),
+            gradient.clone(),
+        );
+        let heavy_hit = spark_effect(
+            "heavy_hit_sparks",
+            HEAVY_HIT_SPARK_COUNT,
+            HIT_SPARK_LIFETIME,
+            (2.0, 3.0),
+            gradient,
+        );
+
+        // Dull enough to leave alone with reduced flashing.
+        let mut gradient = Gradient::new();
+        gradient.add_key(0.0, Vec4::new(0.8, 0.75, 0.65, 0.9));
+        gradient.add_key(1.0, Vec4::new(0.5, 0.45, 0.4, 0.0));
+        let wall = spark_effect(
+            "wall_dust",
+            WALL_DUST_COUNT,
+            WALL_DUST_LIFETIME,
+            (1.0, 1.5),
+            gradient,
+        );
+
+        let sparks = ClashSparks {
+            burst: pool.register(
+                &mut commands,
+                effects.add(burst),
+                SPARK_COUNT as u32,
+                SPARK_LIFETIME,
+            ),
+            gentle: pool.register(
+                &mut commands,
+                effects.add(gentle),
+                GENTLE_SPARK_COUNT as u32,
+                GENTLE_SPARK_LIFETIME,
+            ),
+            hit: pool.register(
+                &mut commands,
+                effects.add(hit),
+                HIT_SPARK_COUNT as u32,
+                HIT_SPARK_LIFETIME,
+            ),
+            heavy_hit: pool.register(
+                &mut commands,
+                effects.add(heavy_hit),
+                HEAVY_HIT_SPARK_COUNT as u32,
+                HIT_SPARK_LIFETIME,
+            ),
+            wall: pool.register(
+                &mut commands,
+                effects.add(wall),
+                WALL_DUST_COUNT as u32,
+                WALL_DUST_LIFETIME,
+            ),
+        };
+        commands.insert_resource(sparks);
+    }
+
+    pub fn spawn_clash_sparks(
+        settings: Res<Settings>,
+        sparks: Res<ClashSparks>,
+        mut pool: ResMut<ParticlePool>,
+        mut clashes: EventReader<Clash>,
+    ) {
+        let kind = if settings.reduce_flashing {
+            sparks.gentle
+        } else {
+            sparks.burst
+        };
+        for clash in clashes.iter() {
+            pool.fire(kind, clash.position);
+        }
+    }
+
+    // From the point the limbs met. Blocked hits and reduced flashing don't
+    // spark at all.
+    pub fn spawn_hit_sparks(
+        settings: Res<Settings>,
+        sparks: Res<ClashSparks>,
+        mut pool: ResMut<ParticlePool>,
+        mut hits: EventReader<HitLanded>,
+    ) {
+        for hit in hits.iter().filter(|hit| !hit.blocked) {
+            if settings.reduce_flashing {
+                continue;
+            }
+            let kind = if hit.damage >= HEAVY_HIT_DAMAGE {
+                sparks.heavy_hit
+            } else {
+                sparks.hit
+            };
+            pool.fire(kind, hit.point);
+        }
+    }
+
+    pub fn spawn_wall_dust(
+        sparks: Res<ClashSparks>,
+        mut pool: ResMut<ParticlePool>,
+        mut wall_hits: EventReader<WallHit>,
+    ) {
+        for wall_hit in wall_hits.iter() {
+            pool.fire(sparks.wall, wall_hit.position);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use bevy::{pbr::NotShadowCaster, prelude::*, scene::SceneInstanceReady};
+use serde::Deserialize;
+
+use crate::{character::Character, combat::HitLanded, roster::CharacterDef};
+
+const GRAVITY: Vec3 = Vec3::new(0.0, -9.8, 0.0);
+// Share of each point's speed it keeps from one frame to the next.
+const DAMPING: f32 = 0.96;
+// Passes over the length constraints a frame. More is stiffer.
+const ITERATIONS: usize = 4;
+// Long frames are simulated as this long, so a hitch doesn't fling it.
+const MAX_STEP: f32 = 1.0 / 30.0;
+// Speed a hit throws the defender's cloth at, away from the attacker.
+const HIT_FLING: f32 = 3.0;
+const THICKNESS: f32 = 0.01;
+// Kept this far above the fighter's feet.
+const FLOOR_CLEARANCE: f32 = 0.02;
+
+// A strip of cloth hanging from a bone in the model, like a headband tail or
+// a coat tail. The models have no bones of their own for these, so the strip
+// is built and swung here and only its top end follows the animation.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClothDef {
+    pub bone: String,
+    // Where the strip hangs from, relative to the bone, in the fighter's
+    // frame: +Z is the way they face, so anything behind them is negative.
+    pub offset: (f32, f32, f32),
+    pub segments: usize,
+    pub segment_length: f32,
+    pub width: f32,
+    pub color: (f32, f32, f32),
+}
+
+// One strip, simulated in world space as a chain of points joined by fixed
+// lengths. Verlet integration: each point's velocity is how far it moved
+// last frame.
+#[derive(Component)]
+pub struct Cloth {
+    owner: Entity,
+    bone: Entity,
+    offset: Vec3,
+    segment_length: f32,
+    points: Vec<Vec3>,
+    previous: Vec<Vec3>,
+    // The mesh drawn between each point and the next.
+    parts: Vec<Entity>,
+    // Whether the points have been laid out from the anchor yet.
+    hung: bool,
+}
+
+#[derive(Component)]
+pub struct ClothPart;
+
+// Builds the strips a character's definition asks for once their model is
+// ready, replacing any from a model they had before.
+#[allow(clippy::too_many_arguments)]
+pub fn attach_cloth(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    characters: Res<Assets<CharacterDef>>,
+    fighters: Query<&Character>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    existing: Query<(Entity, &Cloth)>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        let Some(def) = fighters
+            .get(*fighter)
+            .ok()
+            .and_then(|character| characters.get(&character.0))
+        else {
+            continue;
+        };
+        for (entity, cloth) in existing.iter() {
+            if cloth.owner == *fighter {
+                despawn_cloth(&mut commands, entity, cloth);
+            }
+        }
+        let find = |bone: &str| {
+            children
+                .iter_descendants(*fighter)
+                .find(|entity| names.get(*entity).is_ok_and(|name| name.as_str() == bone))
+        };
+        for strip in def.cloth.iter() {
+            let Some(bone) = find(&strip.bone) else {
+                warn!(
+                    "no bone named {} to hang {}'s cloth from",
+                    strip.bone, def.name
+                );
+                continue;
+            };
+            let mesh = meshes.add(Mesh::from(shape::Box::new(strip.width, 1.0, THICKNESS)));
+            let (r, g, b) = strip.color;
+            let material = materials.add(StandardMaterial {
+                base_color: Color::rgb(r, g, b),
+                perceptual_roughness: 0.9,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            });
+            let parts = (0..strip.segments)
+                .map(|_| {
+                    commands
+                        .spawn((
+                            PbrBundle {
+                                mesh: mesh.clone(),
+                                material: material.clone(),
+                                ..default()
+                            },
+                            NotShadowCaster,
+                            ClothPart,
+                        ))
+                        .id()
+                })
+                .collect();
+            commands.spawn(Cloth {
+                owner: *fighter,
+                bone,
+                offset: Vec3::from(strip.offset),
+                segment_length: strip.segment_length,
+                points: vec![Vec3::ZERO; strip.segments + 1],
+                previous: vec![Vec3::ZERO; strip.segments + 1],
+                parts,
+                hung: false,
+            });
+        }
+    }
+}
+
+fn despawn_cloth(commands: &mut Commands, entity: Entity, cloth: &Cloth) {
+    for part in cloth.parts.iter() {
+        commands.entity(*part).despawn();
+    }
+    commands.entity(entity).despawn();
+}
+
+// A hit throws the defender's cloth the way the blow was going.
+pub fn fling_cloth(
+    mut hits: EventReader<HitLanded>,
+    fighters: Query<&GlobalTransform, With<Character>>,
+    mut cloths: Query<&mut Cloth>,
+) {
+    for hit in hits.iter() {
+        let (Ok(attacker), Ok(defender)) = (fighters.get(hit.attacker), fighters.get(hit.defender))
+        else {
+            continue;
+        };
+        let mut away = defender.translation() - attacker.translation();
+        away.y = 0.0;
+        let away = away.normalize_or_zero();
+        for mut cloth in cloths
+            .iter_mut()
+            .filter(|cloth| cloth.owner == hit.defender)
+        {
+            let Cloth {
+                points, previous, ..
+            } = &mut *cloth;
+            for (point, previous) in points.iter().zip(previous.iter_mut()).skip(1) {
+                *previous = *point - away * HIT_FLING * MAX_STEP;
+            }
+        }
+    }
+}
+
+// Runs on the final pose, after propagation, so the top of each strip is
+// where the bone really is this frame. The strips aren't parented to
+// anything, so their parts' global transforms are set here directly.
+pub fn simulate_cloth(
+    mut commands: Commands,
+    time: Res<Time>,
+    fighters: Query<&GlobalTransform, With<Character>>,
+    bones: Query<&GlobalTransform, Without<ClothPart>>,
+    mut cloths: Query<(Entity, &mut Cloth)>,
+    mut parts: Query<(&mut Transform, &mut GlobalTransform), With<ClothPart>>,
+) {
+    let dt = time.delta_seconds().min(MAX_STEP);
+    for (entity, mut cloth) in cloths.iter_mut() {
+        let (Ok(fighter), Ok(bone)) = (fighters.get(cloth.owner), bones.get(cloth.bone)) else {
+            despawn_cloth(&mut commands, entity, &cloth);
+            continue;
+        };
+        let (_, facing, _) = fighter.to_scale_rotation_translation();
+        let anchor = bone.translation() + facing * cloth.offset;
+        let floor = fighter.translation().y + FLOOR_CLEARANCE;
+        let length = cloth.segment_length;
+        let Cloth {
+            points,
+            previous,
+            hung,
+            ..
+        } = &mut *cloth;
+
+        // New strips start hanging straight down from the anchor.
+        if !*hung {
+            *hung = true;
+            for (index, (point, previous)) in points.iter_mut().zip(previous.iter_mut()).enumerate()
+            {
+                *point = anchor - Vec3::Y * length * index as f32;
+                *previous = *point;
+            }
+        }
+
+        points[0] = anchor;
+        previous[0] = anchor;
+        for (point, previous) in points.iter_mut().zip(previous.iter_mut()).skip(1) {
+            let velocity = (*point - *previous) * DAMPING;
+            *previous = *point;
+            *point += velocity + GRAVITY * dt * dt;
+        }
+
+        for _ in 0..ITERATIONS {
+            for index in 1..points.len() {
+                let (parent, point) = (points[index - 1], points[index]);
+                let along = (point - parent).normalize_or_zero();
+                let mut fixed = parent + along * length;
+                // Cloth stays behind the body it hangs off and above the
+                // floor, which is all the collision it gets.
+                let local = facing.inverse() * (fixed - anchor);
+                if local.z > 0.0 {
+                    fixed = anchor + facing * Vec3::new(local.x, local.y, 0.0);
+                }
+                fixed.y = fixed.y.max(floor);
+                points[index] = fixed;
+            }
+        }
+
+        for (index, part) in cloth.parts.iter().enumerate() {
+            let (Some(top), Some(bottom)) = (cloth.points.get(index), cloth.points.get(index + 1))
+            else {
+                continue;
+            };
+            let Ok((mut transform, mut global)) = parts.get_mut(*part) else {
+                continue;
+            };
+            let span = *top - *bottom;
+            *transform = Transform::from_translation((*top + *bottom) / 2.0)
+                .with_rotation(Quat::from_rotation_arc(
+                    Vec3::Y,
+                    span.try_normalize().unwrap_or(Vec3::Y),
+                ))
+                .with_scale(Vec3::new(1.0, span.length(), 1.0));
+            *global = GlobalTransform::from(*transform);
+        }
+    }
+}
@@ -0,0 +1,729 @@
+use bevy::{
+    prelude::*,
+    scene::SceneInstanceReady,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::*;
+
+use serde::Deserialize;
+
+use crate::{
+    character::{
+        AnimationState, Character, CharacterState, Health, Knockback, Meter, Opponent, Team,
+    },
+    comeback::{Comeback, COMEBACK_METER_RATE},
+    gauntlet::Modifier,
+    moves::{AttackLevel, HitLevel, MoveId, MovePhase},
+    roster::CharacterDef,
+    round::MatchRules,
+    tag_team::TagEntrance,
+    tuning::{ComboCounter, Tuning, TuningHandle},
+    SimulationTick,
+};
+
+// Attacking limb. Only counts while its owner is performing `move_id`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Hitbox {
+    pub move_id: MoveId,
+    pub damage: f32,
+    pub knockback: f32,
+    pub hit_level: HitLevel,
+    pub launcher: bool,
+    pub level: AttackLevel,
+    pub guard_break: bool,
+    pub ground_bounce: Option<f32>,
+    pub hits: u32,
+    pub owner: Entity,
+}
+
+impl Hitbox {
+    pub fn new(move_id: MoveId, owner: Entity) -> Self {
+        let data = move_id.data();
+        Hitbox {
+            move_id,
+            damage: data.damage,
+            knockback: data.knockback,
+            hit_level: data.hit_level,
+            launcher: data.launcher,
+            level: data.level,
+            guard_break: data.guard_break,
+            ground_bounce: data.ground_bounce,
+            hits: data.hits,
+            owner,
+        }
+    }
+}
+
+// How fast an attacking limb is moving through its fighter's own space,
+// worked out on the simulation tick from where it last was, for how hard it
+// hits.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct LimbVelocity {
+    previous: Option<Vec3>,
+    // Ticks since the limb's pose last changed.
+    ticks: u32,
+    pub velocity: Vec3,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HurtRegion {
+    Head,
+    Torso,
+    Legs,
+}
+
+impl HurtRegion {
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            HurtRegion::Head => 1.5,
+            HurtRegion::Torso => 1.0,
+            HurtRegion::Legs => 0.8,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Hurtbox {
+    pub owner: Entity,
+    pub region: HurtRegion,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HitLanded {
+    pub attacker: Entity,
+    pub defender: Entity,
+    pub move_id: MoveId,
+    pub damage: f32,
+    pub hit_level: HitLevel,
+    pub region: HurtRegion,
+    pub blocked: bool,
+    // The defender was hit out of the startup or active part of an attack.
+    // Hits during recovery are punishes, not counters.
+    pub counter: bool,
+    // Where the limbs touched, in world space, and the direction out of the
+    // attacking limb there. Effects go off here rather than at either
+    // fighter's origin.
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+// Share of a move's damage and knockback that still gets through a block.
+pub const CHIP_DAMAGE_FRACTION: f32 = 0.1;
+pub const BLOCKED_KNOCKBACK_FRACTION: f32 = 0.5;
+
+// Meter gained per point of damage dealt; the defender gets half as much.
+pub const METER_PER_DAMAGE: f32 = 1.0;
+
+// Hits at or above this much damage get the bigger feedback effects.
+pub const HEAVY_HIT_DAMAGE: f32 = 10.0;
+
+// Metres a limb can shift between ticks without a new pose, from rounding as
+// its fighter moves.
+const UNMOVED_LIMB: f32 = 1e-4;
+// Most ticks a pose change is spread over: a slow frame's worth. A limb that
+// has been still for longer than that has only just started moving.
+const MAX_POSE_GAP: u32 = 4;
+
+// Both fighters are pushed apart and lose their attacks when they clash.
+pub const CLASH_KNOCKBACK: f32 = 4.0;
+pub const CLASH_RECOIL: f32 = 0.3;
+
+// Sent when both fighters' attacks meet on the same tick and cancel out.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Clash {
+    pub fighters: [Entity; 2],
+    pub position: Vec3,
+}
+
+impl Team {
+    // Limbs of one team only ever touch the other team's body, so a fighter
+    // can't hit itself and trading limbs doesn't raise events.
+    pub fn attack_groups(&self) -> CollisionGroups {
+        CollisionGroups::new(self.attack_group(), self.opponent().body_group())
+    }
+
+    pub fn body_groups(&self) -> CollisionGroups {
+        CollisionGroups::new(self.body_group(), self.opponent().attack_group())
+    }
+
+    // Stage hazards hit both teams' bodies, as if they were on both teams'
+    // attacking sides.
+    pub fn hazard_groups() -> CollisionGroups {
+        CollisionGroups::new(
+            Team::One.attack_group() | Team::Two.attack_group(),
+            Team::One.body_group() | Team::Two.body_group(),
+        )
+    }
+
+    fn attack_group(&self) -> Group {
+        match self {
+            Team::One => Group::GROUP_1,
+            Team::Two => Group::GROUP_3,
+        }
+    }
+
+    fn body_group(&self) -> Group {
+        match self {
+            Team::One => Group::GROUP_2,
+            Team::Two => Group::GROUP_4,
+        }
+    }
+}
+
+// A limb's collider. It hangs off its bone on an entity of its own so it can
+// be thrown away and made again, which is how a restored snapshot gets rapier
+// to forget the contacts of the fight it rolled back from.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LimbShape {
+    pub collision_groups: CollisionGroups,
+    pub debug_color: Color,
+    pub radius: f32,
+}
+
+impl LimbShape {
+    fn bundle(self) -> impl Bundle {
+        (
+            SpatialBundle::default(),
+            RigidBody::KinematicPositionBased,
+            Collider::ball(self.radius),
+            ActiveEvents::COLLISION_EVENTS,
+            ColliderDebugColor(self.debug_color),
+            self.collision_groups,
+            ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
+            self,
+        )
+    }
+}
+
+// Returns the collider's entity, for the hitbox or hurtbox to go on.
+pub fn add_collision_point(
+    commands: &mut Commands,
+    bone: Entity,
+    collision_groups: CollisionGroups,
+    debug_color: Color,
+    radius: f32,
+) -> Entity {
+    let shape = LimbShape {
+        collision_groups,
+        debug_color,
+        radius,
+    };
+    commands.spawn(shape.bundle()).set_parent(bone).id()
+}
+
+// Swaps every limb collider for a new one in the same place. Rapier only
+// reports a contact as it starts, so without this a limb already touching
+// something in the fight that was rolled back from wouldn't hit it again.
+pub fn rebuild_limb_colliders(world: &mut World) {
+    let limbs: Vec<_> = world
+        .query::<(
+            Entity,
+            &Parent,
+            &LimbShape,
+            Option<&Hitbox>,
+            Option<&Hurtbox>,
+            Option<&LimbVelocity>,
+        )>()
+        .iter(world)
+        .map(|(limb, bone, shape, hitbox, hurtbox, velocity)| {
+            (
+                limb,
+                bone.get(),
+                *shape,
+                hitbox.copied(),
+                hurtbox.copied(),
+                velocity.copied(),
+            )
+        })
+        .collect();
+    for (limb, bone, shape, hitbox, hurtbox, velocity) in limbs {
+        world.entity_mut(limb).despawn_recursive();
+        let mut rebuilt = world.spawn(shape.bundle());
+        rebuilt.set_parent(bone);
+        if let Some(hitbox) = hitbox {
+            rebuilt.insert(hitbox);
+        }
+        if let Some(hurtbox) = hurtbox {
+            rebuilt.insert(hurtbox);
+        }
+        if let Some(velocity) = velocity {
+            rebuilt.insert(velocity);
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum LimbKind {
+    Attack(MoveId),
+    Hurt(HurtRegion),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LimbCollider {
+    pub bone: String,
+    pub kind: LimbKind,
+    pub radius: f32,
+}
+
+impl LimbCollider {
+    fn attach(&self, commands: &mut Commands, bone: Entity, owner: Entity, team: Team) {
+        match self.kind {
+            LimbKind::Attack(move_id) => {
+                let limb = add_collision_point(
+                    commands,
+                    bone,
+                    team.attack_groups(),
+                    Color::BLUE,
+                    self.radius,
+                );
+                commands
+                    .entity(limb)
+                    .insert((Hitbox::new(move_id, owner), LimbVelocity::default()));
+            }
+            LimbKind::Hurt(region) => {
+                let limb = add_collision_point(
+                    commands,
+                    bone,
+                    team.body_groups(),
+                    Color::RED,
+                    self.radius,
+                );
+                commands.entity(limb).insert(Hurtbox { owner, region });
+            }
+        }
+    }
+}
+
+// Runs once per fighter, when its model's scene has finished spawning, so the
+// skeleton is only searched when there is a new one to search.
+pub fn calculate_collision_points(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    characters: Res<Assets<CharacterDef>>,
+    fighters: Query<(&Character, &Team, &Name)>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        // Other scenes, like the stage or the select screen preview, aren't
+        // fighters.
+        let Ok((character, team, fighter_name)) = fighters.get(*fighter) else {
+            continue;
+        };
+        let Some(def) = characters.get(&character.0) else {
+            continue;
+        };
+        let bones: HashMap<&str, Entity> = children
+            .iter_descendants(*fighter)
+            .filter_map(|entity| names.get(entity).ok().map(|name| (name.as_str(), entity)))
+            .collect();
+        let missing: Vec<&str> = def
+            .colliders
+            .iter()
+            .map(|limb| limb.bone.as_str())
+            .filter(|bone| !bones.contains_key(bone))
+            .collect();
+        if !missing.is_empty() {
+            warn!("{fighter_name} has no bones named {missing:?}, skipping their colliders");
+        }
+
+        for limb in def.colliders.iter() {
+            if let Some(bone) = bones.get(limb.bone.as_str()) {
+                limb.attach(&mut commands, *bone, *fighter, *team);
+            }
+        }
+    }
+}
+
+// A hitbox that reached a hurtbox this tick. Everything that depends on the
+// fighters' states is worked out before any strike is applied, so a trade
+// treats both sides the same.
+#[derive(Clone, Copy)]
+struct Strike {
+    hitbox: Hitbox,
+    hurtbox: Hurtbox,
+    limb: Entity,
+    hurt_limb: Entity,
+    blocked: bool,
+    counter: bool,
+    armored: bool,
+    direction: f32,
+    // Damage and knockback scale for how fast the limb was moving.
+    impact: f32,
+}
+
+// The deepest point of contact between an attacking limb and the limb it
+// hit, from rapier's contact manifolds. The collision may already have ended
+// by the time it's read, in which case it's taken as halfway between them.
+fn contact_point(
+    rapier_context: &RapierContext,
+    limbs: &Query<&GlobalTransform>,
+    limb: Entity,
+    hurt_limb: Entity,
+) -> (Vec3, Vec3) {
+    let (Ok(limb_transform), Ok(hurt_transform)) = (limbs.get(limb), limbs.get(hurt_limb)) else {
+        return (Vec3::ZERO, Vec3::X);
+    };
+    let (_, rotation, translation) = limb_transform.to_scale_rotation_translation();
+    let contact = rapier_context
+        .contact_pair(limb, hurt_limb)
+        .and_then(|pair| {
+            let limb_first = pair.collider1() == limb;
+            pair.manifolds()
+                .flat_map(|manifold| {
+                    let normal = if limb_first {
+                        manifold.local_n1()
+                    } else {
+                        manifold.local_n2()
+                    };
+                    manifold.points().map(move |point| {
+                        let local = if limb_first {
+                            point.local_p1()
+                        } else {
+                            point.local_p2()
+                        };
+                        (point.dist(), local, normal)
+                    })
+                })
+                .min_by(|(a, ..), (b, ..)| a.total_cmp(b))
+        });
+    match contact {
+        Some((_, point, normal)) => (translation + rotation * point, rotation * normal),
+        None => {
+            let hurt = hurt_transform.translation();
+            (
+                translation.lerp(hurt, 0.5),
+                (hurt - translation).try_normalize().unwrap_or(Vec3::X),
+            )
+        }
+    }
+}
+
+// Runs on the tick once physics has propagated the limbs to where the
+// fighters are. Positions are taken relative to the fighter, so walking or
+// being knocked back doesn't count as limb speed. The limbs are posed on the
+// tick, but a pose can hold for a while: a tick without a new pose keeps the
+// last velocity, and the next change is spread over the ticks it took.
+pub fn track_limb_velocities(
+    time: Res<Time>,
+    fighters: Query<&GlobalTransform, With<Character>>,
+    mut limbs: Query<(&Hitbox, &GlobalTransform, &mut LimbVelocity)>,
+) {
+    let delta = time.delta_seconds();
+    for (hitbox, transform, mut limb) in limbs.iter_mut() {
+        let Ok(fighter) = fighters.get(hitbox.owner) else {
+            continue;
+        };
+        let position = fighter
+            .affine()
+            .inverse()
+            .transform_point3(transform.translation());
+        limb.ticks += 1;
+        if let Some(previous) = limb.previous {
+            if position.distance(previous) < UNMOVED_LIMB {
+                continue;
+            }
+            let ticks = limb.ticks.min(MAX_POSE_GAP);
+            limb.velocity = (position - previous) / (delta * ticks as f32);
+        }
+        limb.previous = Some(position);
+        limb.ticks = 0;
+    }
+}
+
+// Systems take at most 16 parameters, so related ones are paired up.
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_hits(
+    rapier_context: Res<RapierContext>,
+    tick: Res<SimulationTick>,
+    rules: Res<MatchRules>,
+    (tuning, tunings): (Res<TuningHandle>, Res<Assets<Tuning>>),
+    mut collision_events: EventReader<CollisionEvent>,
+    mut hits: EventWriter<HitLanded>,
+    mut clashes: EventWriter<Clash>,
+    (hitboxes, hurtboxes): (Query<&Hitbox>, Query<&Hurtbox>),
+    (limbs, limb_velocities): (Query<&GlobalTransform>, Query<&LimbVelocity>),
+    mut states: Query<(&mut CharacterState, &Transform)>,
+    mut defenders: Query<(&mut Health, &mut Knockback, &mut ComboCounter)>,
+    mut meters: Query<(&mut Meter, &Comeback)>,
+    entrances: Query<(), With<TagEntrance>>,
+    modifiers: Query<&Modifier>,
+    opponents: Query<&Opponent>,
+) {
+    let tuning = tunings.get(&tuning.0).cloned().unwrap_or_default();
+    let mut strikes = Vec::new();
+    for collision_event in collision_events.iter() {
+        let CollisionEvent::Started(entity1, entity2, _flags) = collision_event else {
+            continue;
+        };
+        let (limb, hurt_limb, hitbox, hurtbox) =
+            match (hitboxes.get(*entity1), hurtboxes.get(*entity2)) {
+                (Ok(hitbox), Ok(hurtbox)) => (*entity1, *entity2, hitbox, hurtbox),
+                _ => match (hitboxes.get(*entity2), hurtboxes.get(*entity1)) {
+                    (Ok(hitbox), Ok(hurtbox)) => (*entity2, *entity1, hitbox, hurtbox),
+                    _ => continue,
+                },
+            };
+        // The collision groups only keep each team's limbs off its own
+        // bodies. With more than one pair about, as in a stress test, a limb
+        // can still reach someone else's opponent, so it has to be the
+        // attacker's own.
+        let attacker = hitbox.owner;
+        let defender = hurtbox.owner;
+        if attacker == defender
+            || opponents
+                .get(attacker)
+                .is_ok_and(|opponent| opponent.0 != defender)
+        {
+            continue;
+        }
+
+        let Ok((attacker_state, attacker_transform)) = states.get(hitbox.owner) else {
+            continue;
+        };
+        if MoveId::from_state(attacker_state.player_state) != Some(hitbox.move_id)
+            || attacker_state.move_phase != Some(MovePhase::Active)
+        {
+            continue;
+        }
+        let Ok((defender_state, defender_transform)) = states.get(hurtbox.owner) else {
+            continue;
+        };
+        // Crouching ducks under high attacks, jumping clears low ones.
+        let evaded = match hitbox.hit_level {
+            HitLevel::High => defender_state.player_state == AnimationState::Crouching,
+            HitLevel::Low => defender_state.airborne,
+            HitLevel::Mid => false,
+        };
+        // Nothing lands on a fighter still leaping in from a tag.
+        if evaded
+            || entrances.contains(hurtbox.owner)
+            || !defender_state.hittable(hitbox.ground_bounce.is_some())
+        {
+            continue;
+        }
+        strikes.push(Strike {
+            hitbox: *hitbox,
+            hurtbox: *hurtbox,
+            limb,
+            hurt_limb,
+            blocked: defender_state.player_state == AnimationState::Blocking
+                && !hitbox.guard_break,
+            counter: matches!(
+                defender_state.move_phase,
+                Some(MovePhase::Startup | MovePhase::Active)
+            ),
+            armored: defender_state.armored()
+                || modifiers
+                    .get(hurtbox.owner)
+                    .is_ok_and(|modifier| modifier.armors(defender_state)),
+            direction: (defender_transform.translation.x - attacker_transform.translation.x)
+                .signum(),
+            impact: limb_velocities
+                .get(limb)
+                .map_or(1.0, |limb| tuning.impact.scale(limb.velocity.length())),
+        });
+    }
+
+    // Fighters who hit each other this tick with moves of the same level
+    // clash instead of trading. Strikes are indexed by who hit whom so each
+    // one finds its answer without scanning the rest; reversed so the first
+    // limb to connect is the one kept.
+    let by_fighters: HashMap<(Entity, Entity), &Strike> = strikes
+        .iter()
+        .rev()
+        .map(|strike| ((strike.hitbox.owner, strike.hurtbox.owner), strike))
+        .collect();
+    let mut clashed = HashSet::new();
+    for strike in strikes.iter() {
+        let Some(&answer) = by_fighters.get(&(strike.hurtbox.owner, strike.hitbox.owner)) else {
+            continue;
+        };
+        if strike.hitbox.level != answer.hitbox.level
+            || clashed.contains(&strike.hitbox.owner)
+        {
+            continue;
+        }
+        clashed.extend([strike.hitbox.owner, answer.hitbox.owner]);
+        for pushed in [strike, answer] {
+            if let Ok((mut state, _)) = states.get_mut(pushed.hurtbox.owner) {
+                state.stun(CLASH_RECOIL, AnimationState::Idle);
+            }
+            if let Ok((_, mut knockback, _)) = defenders.get_mut(pushed.hurtbox.owner) {
+                knockback.0 += Vec3::X * pushed.direction * CLASH_KNOCKBACK;
+            }
+        }
+        let position = match (limbs.get(strike.limb), limbs.get(answer.limb)) {
+            (Ok(a), Ok(b)) => a.translation().lerp(b.translation(), 0.5),
+            _ => Vec3::ZERO,
+        };
+        clashes.send(Clash {
+            fighters: [strike.hitbox.owner, answer.hitbox.owner],
+            position,
+        });
+    }
+
+    for strike in strikes
+        .iter()
+        .filter(|strike| !clashed.contains(&strike.hitbox.owner))
+    {
+        let Strike {
+            hitbox,
+            hurtbox,
+            limb,
+            hurt_limb,
+            blocked,
+            counter,
+            armored,
+            direction,
+            impact,
+            ..
+        } = *strike;
+        // Each use of a move only connects as many times as it's meant to,
+        // however many contacts its limbs make.
+        let Ok((mut attacker_state, _)) = states.get_mut(hitbox.owner) else {
+            continue;
+        };
+        if !attacker_state.strike(hurtbox.owner, hitbox.hits) {
+            continue;
+        }
+        attacker_state.move_connected = true;
+        let Ok((mut health, mut knockback, mut combo)) = defenders.get_mut(hurtbox.owner) else {
+            continue;
+        };
+        let Ok((mut defender_state, _)) = states.get_mut(hurtbox.owner) else {
+            continue;
+        };
+        // A juggle is one combo however long the defender hangs in the air,
+        // or lies on the floor after it.
+        if defender_state.juggle.is_none()
+            && !defender_state.downed
+            && tuning.combo.expired(combo.last_hit, tick.0)
+        {
+            combo.moves.clear();
+        }
+        // Blocked hits don't extend a combo, and chip damage isn't scaled.
+        let (damage, knockback_scale) = if blocked {
+            (hitbox.damage * CHIP_DAMAGE_FRACTION, BLOCKED_KNOCKBACK_FRACTION)
+        } else {
+            let scale = tuning.combo.scale(&combo.moves, hitbox.move_id);
+            combo.moves.push(hitbox.move_id);
+            combo.last_hit = tick.0;
+            (hitbox.damage * hurtbox.region.damage_multiplier() * scale, 1.0)
+        };
+        let damage = damage * impact * rules.damage_multiplier;
+        health.current = (health.current - damage).max(0.0);
+        for (fighter, share) in [(hitbox.owner, 1.0), (hurtbox.owner, 0.5)] {
+            if let Ok((mut meter, comeback)) = meters.get_mut(fighter) {
+                let rate = if comeback.active { COMEBACK_METER_RATE } else { 1.0 };
+                meter.gain(damage * METER_PER_DAMAGE * share * rate);
+            }
+        }
+        if armored {
+            // The damage goes through but the defender's attack carries on.
+            defender_state.armor_spent = true;
+        } else {
+            knockback.0 += Vec3::X * direction * hitbox.knockback * knockback_scale * impact;
+            if blocked {
+                defender_state.stun(hitbox.level.blockstun(), AnimationState::Blocking);
+            } else {
+                defender_state.stun(hitbox.level.hitstun(), AnimationState::Idle);
+                match hitbox.ground_bounce {
+                    Some(speed) => {
+                        defender_state.ground_bounce_hit(&tuning.juggle, hitbox.launcher, speed)
+                    }
+                    None => defender_state.juggle_hit(&tuning.juggle, hitbox.launcher),
+                }
+            }
+        }
+
+        let (point, normal) = contact_point(&rapier_context, &limbs, limb, hurt_limb);
+        hits.send(HitLanded {
+            attacker: hitbox.owner,
+            defender: hurtbox.owner,
+            move_id: hitbox.move_id,
+            damage,
+            hit_level: hitbox.hit_level,
+            region: hurtbox.region,
+            blocked,
+            counter,
+            point,
+            normal,
+        });
+    }
+}
+
+/*
+fn spawn_particles(
+    commands: &mut Commands,
+    effects: &mut ResMut<Assets<EffectAsset>>,
+    position: Vec3,
+) {
+    let mut color_gradient1 = Gradient::new();
+    color_gradient1.add_key(0.0, Vec4::new(0.0, 0.0, 0.0, 1.0));
+    color_gradient1.add_key(1.0, Vec4::new(0.3, 0.3, 0.3, 0.2));
+
+    let mut size_gradient1 = Gradient::new();
+    size_gradient1.add_key(0.2, Vec2::splat(0.01));
+    size_gradient1.add_key(0.2, Vec2::splat(0.1));
+
+    let writer = ExprWriter::new();
+
+    // Give a bit of variation by randomizing the age per particle. This will
+    // control the starting color and starting size of particles.
+    let age = writer.lit(0.).uniform(writer.lit(0.2)).expr();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, age);
+
+    // Give a bit of variation by randomizing the lifetime per particle
+    let lifetime = writer.lit(0.8).uniform(writer.lit(1.2)).expr();
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
+
+
+    let init_pos = SetPositionSphereModifier {
+        center: writer.lit(position).expr(),
+        radius: writer.lit(0.2).expr(),
+        dimension: ShapeDimension::Volume,
+    };
+
+    // Give a bit of variation by randomizing the initial speed
+    let init_vel = SetVelocitySphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        speed: (writer.rand(ScalarType::Float) * writer.lit(2.0) - writer.lit(2.0)).expr(),
+    };
+
+    let effect = EffectAsset::new(
+        2048,
+        Spawner::once(250.0.into(), true),
+        writer.finish(),
+    )
+    .with_name("firework")
+    .init(init_pos)
+    .init(init_vel)
+    .init(init_age)
+    .init(init_lifetime)
+    .render(ColorOverLifetimeModifier {
+        gradient: color_gradient1,
+    })
+    .render(SizeOverLifetimeModifier {
+        gradient: size_gradient1,
+        screen_space_size: false,
+    });
+
+    let effect1 = effects.add(effect);
+
+    /*commands.spawn((
+        Name::new("firework"),
+        ParticleEffectBundle {
+            effect: ParticleEffect::new(effect1),
+            transform: Transform::IDENTITY,
+            ..Default::default()
+        },
+    ));*/
+}
+*/
+
+// Sent once when the match is over. `None` for a draw.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FightEnded {
+    pub winner: Option<Team>,
+}
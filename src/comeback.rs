@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use bevy::{
+    audio::{Decodable, Source},
+    prelude::*,
+    reflect::TypePath,
+};
+
+use crate::{
+    character::{Health, Team},
+    post_process::PostProcessSettings,
+    round::{MatchRules, Round, RoundPhase},
+};
+
+// Share of health a fighter has to drop under to get their comeback.
+pub const COMEBACK_HEALTH: f32 = 0.15;
+// Meter gain multiplier while the comeback is active.
+pub const COMEBACK_METER_RATE: f32 = 1.25;
+
+const HEARTBEAT_BPM: f32 = 70.0;
+const COMEBACK_VIGNETTE: f32 = 0.35;
+const HEARTBEAT_SAMPLE_RATE: u32 = 44100;
+
+// Granted once per match, and lasts until the round it was earned in ends.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Comeback {
+    pub active: bool,
+    pub spent: bool,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ComebackTriggered {
+    pub team: Team,
+}
+
+pub fn trigger_comeback(
+    rules: Res<MatchRules>,
+    round: Res<Round>,
+    mut fighters: Query<(&Health, &Team, &mut Comeback)>,
+    mut triggered: EventWriter<ComebackTriggered>,
+) {
+    for (health, team, mut comeback) in fighters.iter_mut() {
+        if round.phase == RoundPhase::Intro {
+            comeback.active = false;
+            continue;
+        }
+        let ratio = health.current / health.max;
+        if rules.comeback && !comeback.spent && ratio > 0.0 && ratio < COMEBACK_HEALTH {
+            comeback.active = true;
+            comeback.spent = true;
+            triggered.send(ComebackTriggered { team: *team });
+        }
+    }
+}
+
+fn heartbeat_phase(seconds: f32) -> f32 {
+    (seconds * HEARTBEAT_BPM / 60.0).fract()
+}
+
+// "Lub-dub": a low thump and a softer one a quarter of a beat later.
+fn heartbeat_level(phase: f32) -> f32 {
+    let beat = 60.0 / HEARTBEAT_BPM;
+    let thump = |start: f32, strength: f32| {
+        let t = phase * beat - start;
+        if t < 0.0 {
+            return 0.0;
+        }
+        strength * (-t * 18.0).exp()
+    };
+    thump(0.0, 1.0) + thump(0.25, 0.6)
+}
+
+// A synthesised heartbeat so the layer needs no sample on disk. It never
+// ends; the sink is paused while no one has a comeback.
+#[derive(Asset, TypePath)]
+pub struct Heartbeat;
+
+pub struct HeartbeatDecoder {
+    sample: u32,
+}
+
+impl Iterator for HeartbeatDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let seconds = self.sample as f32 / HEARTBEAT_SAMPLE_RATE as f32;
+        self.sample = self.sample.wrapping_add(1);
+        let tone = (seconds * 55.0 * std::f32::consts::TAU).sin();
+        Some(tone * heartbeat_level(heartbeat_phase(seconds)) * 0.6)
+    }
+}
+
+impl Source for HeartbeatDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        HEARTBEAT_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for Heartbeat {
+    type DecoderItem = <HeartbeatDecoder as Iterator>::Item;
+    type Decoder = HeartbeatDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        HeartbeatDecoder { sample: 0 }
+    }
+}
+
+#[derive(Component)]
+pub struct HeartbeatLayer;
+
+pub fn setup_heartbeat(mut commands: Commands, mut heartbeats: ResMut<Assets<Heartbeat>>) {
+    commands.spawn((
+        AudioSourceBundle {
+            source: heartbeats.add(Heartbeat),
+            settings: PlaybackSettings {
+                paused: true,
+                ..default()
+            },
+        },
+        HeartbeatLayer,
+    ));
+}
+
+// While anyone's comeback is active the heartbeat plays and the vignette
+// closes in at the same tempo.
+pub fn drive_comeback_effects(
+    time: Res<Time<Real>>,
+    fighters: Query<&Comeback>,
+    heartbeat: Query<&AudioSink, With<HeartbeatLayer>>,
+    mut settings: Query<&mut PostProcessSettings>,
+) {
+    let active = fighters.iter().any(|comeback| comeback.active);
+    for sink in heartbeat.iter() {
+        if active && sink.is_paused() {
+            sink.play();
+        } else if !active && !sink.is_paused() {
+            sink.pause();
+        }
+    }
+
+    let base = PostProcessSettings::default().vignette;
+    let pulse = if active {
+        COMEBACK_VIGNETTE * (0.6 + 0.4 * heartbeat_level(heartbeat_phase(time.elapsed_seconds())))
+    } else {
+        0.0
+    };
+    for mut settings in settings.iter_mut() {
+        settings.vignette = base + pulse;
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::{
+    audio::{PlaybackMode, Volume, VolumeLevel},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{
+    combat::HitLanded,
+    round::RoundEnded,
+    stage::CrowdAudio,
+};
+
+// Hits by the same fighter closer together than this count as one combo.
+pub const COMBO_WINDOW: f32 = 1.0;
+const HIT_EXCITEMENT: f32 = 0.08;
+const KO_EXCITEMENT: f32 = 1.0;
+// Share of excitement lost per second.
+const EXCITEMENT_DECAY: f32 = 0.35;
+const AMBIENCE_VOLUME: f32 = 0.25;
+const CHEER_VOLUME: f32 = 0.6;
+const CHEER_PITCH: f32 = 0.15;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CrowdLayer {
+    Ambience,
+    Cheer,
+}
+
+// How worked up the crowd is, from 0 to 1. Each hit in a combo adds more
+// than the last and a KO maxes it out.
+#[derive(Resource, Default)]
+pub struct Excitement {
+    pub level: f32,
+    combos: HashMap<Entity, (f32, u32)>,
+}
+
+pub fn spawn_crowd(commands: &mut Commands, asset_server: &AssetServer, crowd: &CrowdAudio) {
+    for (layer, path, volume) in [
+        (CrowdLayer::Ambience, &crowd.ambience, AMBIENCE_VOLUME),
+        (CrowdLayer::Cheer, &crowd.cheer, 0.0),
+    ] {
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load(path.clone()),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Loop,
+                    volume: Volume::Relative(VolumeLevel::new(volume)),
+                    ..default()
+                },
+            },
+            layer,
+        ));
+    }
+}
+
+pub fn excite_crowd(
+    time: Res<Time>,
+    mut excitement: ResMut<Excitement>,
+    mut hits: EventReader<HitLanded>,
+    mut ended: EventReader<RoundEnded>,
+) {
+    let now = time.elapsed_seconds();
+    for hit in hits.iter().filter(|hit| !hit.blocked) {
+        let (last, count) = excitement
+            .combos
+            .get(&hit.attacker)
+            .copied()
+            .unwrap_or((f32::MIN, 0));
+        let count = if now - last < COMBO_WINDOW { count + 1 } else { 1 };
+        excitement.combos.insert(hit.attacker, (now, count));
+        excitement.level += HIT_EXCITEMENT * count as f32;
+    }
+    if ended.iter().any(|round| round.reason.beaten()) {
+        excitement.level += KO_EXCITEMENT;
+    }
+    let decay = (1.0 - EXCITEMENT_DECAY * time.delta_seconds()).max(0.0);
+    excitement.level = (excitement.level * decay).clamp(0.0, 1.0);
+}
+
+pub fn mix_crowd(excitement: Res<Excitement>, layers: Query<(&CrowdLayer, &AudioSink)>) {
+    for (layer, sink) in layers.iter() {
+        if *layer == CrowdLayer::Cheer {
+            sink.set_volume(CHEER_VOLUME * excitement.level);
+            sink.set_speed(1.0 + CHEER_PITCH * excitement.level);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::{camera::Cameraman, combat::HitLanded};
+
+// Real seconds a number floats for, and how far it rises in that time.
+const NUMBER_SECONDS: f32 = 0.8;
+const NUMBER_RISE: f32 = 0.5;
+const NUMBER_COLOR: Color = Color::rgb(1.0, 0.9, 0.3);
+const CHIP_COLOR: Color = Color::rgb(0.7, 0.7, 0.7);
+
+// The damage a hit did, drawn over the point the limbs met and drifting up
+// off it.
+#[derive(Component)]
+pub struct DamageNumber {
+    point: Vec3,
+    timer: Timer,
+}
+
+pub fn spawn_damage_numbers(mut commands: Commands, mut hits: EventReader<HitLanded>) {
+    for hit in hits.iter() {
+        commands.spawn((
+            TextBundle::from_section(
+                format!("{:.0}", hit.damage),
+                TextStyle {
+                    font_size: 26.0,
+                    color: if hit.blocked {
+                        CHIP_COLOR
+                    } else {
+                        NUMBER_COLOR
+                    },
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+            DamageNumber {
+                point: hit.point,
+                timer: Timer::from_seconds(NUMBER_SECONDS, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+// Re-projected every frame, so the numbers stay on the spot in the world as
+// the camera moves.
+pub fn float_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Cameraman>>,
+    mut numbers: Query<(Entity, &mut DamageNumber, &mut Style, &mut Text)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    for (entity, mut number, mut style, mut text) in numbers.iter_mut() {
+        if number.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let t = number.timer.percent();
+        let point = number.point + Vec3::Y * NUMBER_RISE * t;
+        let Some(position) = camera.world_to_viewport(camera_transform, point) else {
+            style.display = Display::None;
+            continue;
+        };
+        style.display = Display::Flex;
+        style.left = Val::Px(position.x);
+        style.top = Val::Px(position.y);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(1.0 - t * t);
+        }
+    }
+}
+
+// Numbers still up when the fight ends go with it.
+pub fn clear_damage_numbers(mut commands: Commands, numbers: Query<Entity, With<DamageNumber>>) {
+    for entity in numbers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
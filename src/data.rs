@@ -0,0 +1,99 @@
+use std::{fs, marker::PhantomData};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+// Registers `T` as an asset loaded from RON files with the given extensions,
+// e.g. `RonAssetPlugin::<CharacterDef>::new(&["character.ron"])`.
+pub struct RonAssetPlugin<T> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> RonAssetPlugin<T> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        RonAssetPlugin {
+            extensions,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Plugin for RonAssetPlugin<T>
+where
+    T: Asset + for<'de> Deserialize<'de>,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<T>()
+            .register_asset_loader(RonAssetLoader::<T> {
+                extensions: self.extensions,
+                _marker: PhantomData,
+            });
+    }
+}
+
+struct RonAssetLoader<T> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, Error)]
+pub enum RonLoaderError {
+    #[error("could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl<T> AssetLoader for RonAssetLoader<T>
+where
+    T: Asset + for<'de> Deserialize<'de>,
+{
+    type Asset = T;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<T, RonLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes::<T>(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+// Files the game writes itself (settings, profile) live next to the
+// executable rather than in assets/. A missing or unreadable file gives the
+// default so a fresh install or an old format still starts.
+pub fn read_ron_file<T: DeserializeOwned + Default>(path: &str) -> T {
+    let Ok(text) = fs::read_to_string(path) else {
+        return T::default();
+    };
+    ron::from_str(&text).unwrap_or_else(|err| {
+        warn!("Ignoring {path}: {err}");
+        T::default()
+    })
+}
+
+pub fn write_ron_file<T: Serialize>(path: &str, value: &T) {
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|text| fs::write(path, text).map_err(|err| err.to_string()));
+    if let Err(err) = result {
+        warn!("Can't save {path}: {err}");
+    }
+}
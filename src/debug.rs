@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    character::CharacterState,
+    combat::{Hitbox, Hurtbox},
+    moves::{MoveId, MovePhase},
+    settings::Settings,
+    SIMULATION_HZ,
+};
+
+pub const HITBOX_VIEW_KEY: KeyCode = KeyCode::F9;
+
+const INACTIVE_ALPHA: f32 = 0.15;
+
+#[derive(Resource, Default)]
+pub struct HitboxView {
+    pub enabled: bool,
+}
+
+pub fn toggle_hitbox_view(keys: Res<Input<KeyCode>>, mut view: ResMut<HitboxView>) {
+    if keys.just_pressed(HITBOX_VIEW_KEY) {
+        view.enabled = !view.enabled;
+    }
+}
+
+fn radius(collider: &Collider) -> f32 {
+    collider.as_ball().map_or(0.1, |ball| ball.radius())
+}
+
+// Fighting-game convention: live hitboxes red, hurtboxes green, anything that
+// can't currently connect drawn faded. Colourblind palettes swap the colours.
+pub fn draw_hitboxes(
+    view: Res<HitboxView>,
+    settings: Res<Settings>,
+    mut gizmos: Gizmos,
+    states: Query<&CharacterState>,
+    hitboxes: Query<(&Hitbox, &Collider, &GlobalTransform)>,
+    hurtboxes: Query<(&Collider, &GlobalTransform), With<Hurtbox>>,
+) {
+    if !view.enabled {
+        return;
+    }
+    let colors = settings.palette.colors();
+    for (hitbox, collider, transform) in &hitboxes {
+        let active = states.get(hitbox.owner).map_or(false, |state| {
+            MoveId::from_state(state.player_state) == Some(hitbox.move_id)
+                && state.move_phase == Some(MovePhase::Active)
+        });
+        let color = if active {
+            colors.hitbox
+        } else {
+            colors.hitbox.with_a(INACTIVE_ALPHA)
+        };
+        gizmos.sphere(transform.translation(), Quat::IDENTITY, radius(collider), color);
+    }
+    for (collider, transform) in &hurtboxes {
+        gizmos.sphere(
+            transform.translation(),
+            Quat::IDENTITY,
+            radius(collider),
+            colors.hurtbox,
+        );
+    }
+}
+
+// Shown with the hitbox view: each fighter's move, its phase and the
+// startup/active/recovery split in simulation frames.
+#[derive(Component)]
+pub struct FrameDataOverlay;
+
+pub fn setup_frame_data_overlay(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        FrameDataOverlay,
+    ));
+}
+
+fn frames(seconds: f32) -> u32 {
+    (seconds * SIMULATION_HZ as f32).round() as u32
+}
+
+pub fn update_frame_data_overlay(
+    view: Res<HitboxView>,
+    fighters: Query<(&Name, &CharacterState)>,
+    mut overlays: Query<(&mut Text, &mut Style), With<FrameDataOverlay>>,
+) {
+    for (mut text, mut style) in overlays.iter_mut() {
+        style.display = if view.enabled {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if !view.enabled {
+            continue;
+        }
+        let lines: Vec<String> = fighters
+            .iter()
+            .map(|(name, state)| {
+                let (Some(move_id), Some(phase), Some(timer)) = (
+                    MoveId::from_state(state.player_state),
+                    state.move_phase,
+                    state.current_animation_timer.as_ref(),
+                ) else {
+                    return format!("{name}: {:?}", state.player_state);
+                };
+                let data = move_id.data();
+                let startup = data.level.startup();
+                let recovery = timer.duration().as_secs_f32() - startup - data.active;
+                format!(
+                    "{name}: {move_id:?} {phase:?} {}f  ({}/{}/{})",
+                    frames(timer.elapsed_secs()),
+                    frames(startup),
+                    frames(data.active),
+                    frames(recovery.max(0.0)),
+                )
+            })
+            .collect();
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+pub const QUARTER_SPEED_KEY: KeyCode = KeyCode::F6;
+pub const HALF_SPEED_KEY: KeyCode = KeyCode::F7;
+pub const FULL_SPEED_KEY: KeyCode = KeyCode::F8;
+pub const PAUSE_KEY: KeyCode = KeyCode::Pause;
+pub const FRAME_STEP_KEY: KeyCode = KeyCode::Period;
+
+pub fn time_scale_keys(keys: Res<Input<KeyCode>>, mut time: ResMut<Time<Virtual>>) {
+    if keys.just_pressed(QUARTER_SPEED_KEY) {
+        time.set_relative_speed(0.25);
+    }
+    if keys.just_pressed(HALF_SPEED_KEY) {
+        time.set_relative_speed(0.5);
+    }
+    if keys.just_pressed(FULL_SPEED_KEY) {
+        time.set_relative_speed(1.0);
+    }
+    if keys.just_pressed(PAUSE_KEY) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+}
+
+// While paused, runs exactly one fixed simulation tick per key press. Mirrors
+// what bevy's own fixed loop does so systems see a full timestep.
+pub fn frame_step(world: &mut World) {
+    if !world.resource::<Time<Virtual>>().is_paused()
+        || !world
+            .resource::<Input<KeyCode>>()
+            .just_pressed(FRAME_STEP_KEY)
+    {
+        return;
+    }
+    let mut fixed = world.resource_mut::<Time<Fixed>>();
+    let timestep = fixed.timestep();
+    fixed.advance_by(timestep);
+    *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+    world.run_schedule(FixedUpdate);
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
@@ -0,0 +1,178 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    character::KnockedDown,
+    rng::GameRng,
+    stage::{CurrentStage, StageDef},
+};
+
+// Seconds a mark stays on the floor, fading out over the last of them.
+const DECAL_LIFETIME: f32 = 12.0;
+const DECAL_FADE: f32 = 4.0;
+// Oldest marks go first past this many.
+const MAX_DECALS: usize = 16;
+// Just above the floor so it doesn't fight with it.
+const DECAL_HEIGHT: f32 = 0.015;
+const DECAL_OPACITY: f32 = 0.8;
+// Size of the mark from a knockdown with no extra hits, and how much each
+// juggle hit on top adds.
+const BASE_SIZE: f32 = 0.5;
+const SIZE_PER_HIT: f32 = 0.1;
+const MAX_SIZE: f32 = 1.0;
+const CRACK_LINES: usize = 5;
+const CRACK_WIDTH: f32 = 0.04;
+
+// What a knockdown leaves on a stage's floor. Stages whose floor doesn't
+// take marks leave it out.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloorDecal {
+    // A dark burn.
+    Scorch,
+    // Splits running out from the impact.
+    Crack,
+}
+
+impl FloorDecal {
+    fn color(self) -> Color {
+        match self {
+            FloorDecal::Scorch => Color::rgb(0.08, 0.05, 0.03),
+            FloorDecal::Crack => Color::rgb(0.12, 0.1, 0.08),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Decal {
+    timer: Timer,
+}
+
+// Leaves a mark where a juggled fighter hits the floor, bigger the longer
+// the juggle went on. Purely for show, so it draws on the cosmetic stream.
+pub fn spawn_knockdown_decals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut rng: ResMut<GameRng>,
+    stage: Option<Res<CurrentStage>>,
+    stages: Res<Assets<StageDef>>,
+    mut knocked_down: EventReader<KnockedDown>,
+    decals: Query<(Entity, &Decal)>,
+) {
+    let kind = stage
+        .and_then(|stage| stages.get(&stage.def))
+        .and_then(|def| def.decals);
+    let Some(kind) = kind else {
+        knocked_down.clear();
+        return;
+    };
+
+    let mut existing: Vec<(Entity, f32)> = decals
+        .iter()
+        .map(|(entity, decal)| (entity, decal.timer.elapsed_secs()))
+        .collect();
+    for knockdown in knocked_down.iter() {
+        if existing.len() >= MAX_DECALS {
+            existing.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let (oldest, _) = existing.remove(0);
+            commands.entity(oldest).despawn_recursive();
+        }
+
+        let rng = rng.cosmetic();
+        let size = (BASE_SIZE + SIZE_PER_HIT * knockdown.hits as f32).min(MAX_SIZE);
+        let material = materials.add(StandardMaterial {
+            base_color: kind.color().with_a(DECAL_OPACITY),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        let position = Vec3::new(
+            knockdown.position.x,
+            knockdown.position.y + DECAL_HEIGHT,
+            knockdown.position.z,
+        );
+        let turn = rng.gen_range(0.0..std::f32::consts::TAU);
+        let mut decal = commands.spawn((
+            SpatialBundle::from_transform(
+                Transform::from_translation(position).with_rotation(Quat::from_rotation_y(turn)),
+            ),
+            Decal {
+                timer: Timer::from_seconds(DECAL_LIFETIME, TimerMode::Once),
+            },
+        ));
+        decal.with_children(|decal| match kind {
+            FloorDecal::Scorch => {
+                decal.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Mesh::from(shape::Circle::new(size))),
+                        material: material.clone(),
+                        transform: Transform::from_rotation(Quat::from_rotation_x(
+                            -std::f32::consts::FRAC_PI_2,
+                        )),
+                        ..default()
+                    },
+                    NotShadowCaster,
+                ));
+            }
+            FloorDecal::Crack => {
+                for line in 0..CRACK_LINES {
+                    let length = size * rng.gen_range(0.5..1.0);
+                    let angle = std::f32::consts::TAU * line as f32 / CRACK_LINES as f32
+                        + rng.gen_range(-0.4..0.4);
+                    let direction = Quat::from_rotation_y(angle);
+                    decal.spawn((
+                        PbrBundle {
+                            mesh: meshes.add(Mesh::from(shape::Box::new(
+                                length,
+                                0.001,
+                                CRACK_WIDTH,
+                            ))),
+                            material: material.clone(),
+                            transform: Transform::from_rotation(direction)
+                                .with_translation(direction * Vec3::X * length / 2.0),
+                            ..default()
+                        },
+                        NotShadowCaster,
+                    ));
+                }
+            }
+        });
+        existing.push((decal.id(), 0.0));
+    }
+}
+
+// Marks hold for a while, then fade away and are cleared.
+pub fn fade_decals(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut decals: Query<(Entity, &mut Decal, &Children)>,
+    parts: Query<&Handle<StandardMaterial>>,
+) {
+    for (entity, mut decal, children) in decals.iter_mut() {
+        decal.timer.tick(time.delta());
+        if decal.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let left = decal.timer.remaining_secs();
+        if left > DECAL_FADE {
+            continue;
+        }
+        // The parts of a mark share one material.
+        let Some(material) = children.first().and_then(|part| parts.get(*part).ok()) else {
+            continue;
+        };
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_a(DECAL_OPACITY * left / DECAL_FADE);
+        }
+    }
+}
+
+// A new fight starts on a clean floor.
+pub fn clear_decals(mut commands: Commands, decals: Query<Entity, With<Decal>>) {
+    for entity in decals.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,303 @@
+use bevy::{
+    core_pipeline::{core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state},
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            BindGroupEntries, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+            BindingType, BufferBindingType, CachedRenderPipelineId, ColorTargetState, ColorWrites,
+            FragmentState, MultisampleState, Operations, PipelineCache, PrimitiveState,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType,
+            SpecializedRenderPipeline, SpecializedRenderPipelines, TextureFormat,
+            TextureSampleType, TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::{ExtractedView, ViewDepthTexture, ViewTarget},
+        Render, RenderApp, RenderSet,
+    },
+};
+
+use crate::post_process::PostProcessNode;
+
+pub const DEPTH_OF_FIELD_SHADER: &str = "shaders/depth_of_field.wgsl";
+
+// Blurs what's nearer or further than `focus_distance`, up to `max_blur` of
+// the screen's height once it's `focus_range` out of focus. Depths come from
+// the main pass, so the camera's depth texture has to be bindable. Field
+// order must match `DepthOfField` in the shader.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct DepthOfField {
+    pub focus_distance: f32,
+    pub focus_range: f32,
+    pub max_blur: f32,
+    // The camera's near plane, to turn depths back into distances.
+    pub near: f32,
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        DepthOfField {
+            focus_distance: 10.0,
+            focus_range: 8.0,
+            max_blur: 0.012,
+            near: 0.1,
+        }
+    }
+}
+
+pub struct DepthOfFieldPlugin;
+
+impl Plugin for DepthOfFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<DepthOfField>::default(),
+            UniformComponentPlugin::<DepthOfField>::default(),
+        ));
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<DepthOfFieldPipeline>>()
+            .add_systems(
+                Render,
+                prepare_depth_of_field_pipelines.in_set(RenderSet::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<DepthOfFieldNode>>(
+                core_3d::graph::NAME,
+                DepthOfFieldNode::NAME,
+            )
+            // Ahead of the vignette, which shouldn't be blurred.
+            .add_render_graph_edges(
+                core_3d::graph::NAME,
+                &[
+                    core_3d::graph::node::TONEMAPPING,
+                    DepthOfFieldNode::NAME,
+                    PostProcessNode::NAME,
+                ],
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<DepthOfFieldPipeline>();
+    }
+}
+
+// HDR cameras keep a float target through tonemapping, and with MSAA on the
+// depth texture has a sample per subpixel, so there's a pipeline for each.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct DepthOfFieldKey {
+    hdr: bool,
+    multisampled: bool,
+}
+
+#[derive(Component)]
+struct DepthOfFieldPipelineId {
+    id: CachedRenderPipelineId,
+    multisampled: bool,
+}
+
+fn prepare_depth_of_field_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<DepthOfFieldPipeline>>,
+    pipeline: Res<DepthOfFieldPipeline>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedView), With<DepthOfField>>,
+) {
+    let multisampled = msaa.samples() > 1;
+    for (entity, view) in views.iter() {
+        let key = DepthOfFieldKey {
+            hdr: view.hdr,
+            multisampled,
+        };
+        let id = pipelines.specialize(&pipeline_cache, &pipeline, key);
+        commands
+            .entity(entity)
+            .insert(DepthOfFieldPipelineId { id, multisampled });
+    }
+}
+
+#[derive(Default)]
+struct DepthOfFieldNode;
+
+impl DepthOfFieldNode {
+    const NAME: &'static str = "depth_of_field";
+}
+
+impl ViewNode for DepthOfFieldNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static DepthOfFieldPipelineId,
+        &'static DynamicUniformIndex<DepthOfField>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, depth, pipeline_id, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let depth_of_field_pipeline = world.resource::<DepthOfFieldPipeline>();
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(pipeline_id.id)
+        else {
+            return Ok(());
+        };
+        let Some(settings_binding) = world
+            .resource::<ComponentUniforms<DepthOfField>>()
+            .uniforms()
+            .binding()
+        else {
+            return Ok(());
+        };
+        let layout = if pipeline_id.multisampled {
+            &depth_of_field_pipeline.multisampled_layout
+        } else {
+            &depth_of_field_pipeline.layout
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "depth_of_field_bind_group",
+            layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &depth_of_field_pipeline.sampler,
+                &depth.view,
+                settings_binding,
+            )),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("depth_of_field_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct DepthOfFieldPipeline {
+    layout: BindGroupLayout,
+    multisampled_layout: BindGroupLayout,
+    sampler: Sampler,
+    shader: Handle<Shader>,
+}
+
+impl FromWorld for DepthOfFieldPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = |multisampled: bool| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("depth_of_field_bind_group_layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(DepthOfField::min_size()),
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        DepthOfFieldPipeline {
+            layout: layout(false),
+            multisampled_layout: layout(true),
+            sampler: render_device.create_sampler(&SamplerDescriptor::default()),
+            shader: world.resource::<AssetServer>().load(DEPTH_OF_FIELD_SHADER),
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for DepthOfFieldPipeline {
+    type Key = DepthOfFieldKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let (layout, shader_defs) = if key.multisampled {
+            (
+                self.multisampled_layout.clone(),
+                vec!["MULTISAMPLED".into()],
+            )
+        } else {
+            (self.layout.clone(), Vec::new())
+        };
+        let format = if key.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        RenderPipelineDescriptor {
+            label: Some("depth_of_field_pipeline".into()),
+            layout: vec![layout],
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    character::{AnimationState, CharacterState},
+    combat::HitLanded,
+    input::ControlInput,
+};
+
+pub const STUN_GAUGE_MAX: f32 = 100.0;
+// Gauge filled per point of damage from a clean hit, and drained a second.
+const STUN_PER_DAMAGE: f32 = 2.0;
+const STUN_DECAY: f32 = 15.0;
+// Seconds a dizzy fighter is out for, less `MASH_SECONDS` for every button
+// they press.
+const DIZZY_SECONDS: f32 = 3.0;
+const MASH_SECONDS: f32 = 0.12;
+
+// A fighter's stun gauge filled up and they've gone dizzy.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Dizzied {
+    pub fighter: Entity,
+}
+
+// Clean hits fill the defender's gauge. The first hit on a dizzy fighter
+// wakes them up, and the combo it starts plays out as any other would.
+pub fn build_stun(mut hits: EventReader<HitLanded>, mut states: Query<&mut CharacterState>) {
+    for hit in hits.iter().filter(|hit| !hit.blocked) {
+        let Ok(mut state) = states.get_mut(hit.defender) else {
+            continue;
+        };
+        if state.dizzy {
+            state.dizzy = false;
+            continue;
+        }
+        state.stun_gauge = (state.stun_gauge + hit.damage * STUN_PER_DAMAGE).min(STUN_GAUGE_MAX);
+    }
+}
+
+// Runs before anything takes the tick's presses, so mashing out of a dizzy
+// can count them. A full gauge waits for the combo that filled it to end,
+// then leaves the fighter stood there open.
+pub fn update_dizzy(
+    time: Res<Time>,
+    mut fighters: Query<(Entity, &mut CharacterState, &ControlInput)>,
+    mut dizzied: EventWriter<Dizzied>,
+) {
+    let dt = time.delta_seconds();
+    for (fighter, mut state, input) in fighters.iter_mut() {
+        if state.dizzy {
+            let presses = [input.punch, input.kick, input.jump, input.throw]
+                .into_iter()
+                .filter(|pressed| *pressed)
+                .count();
+            // Over once the timer's run out and they're free to act again.
+            let attacking = state.move_phase.is_some();
+            match state.current_animation_timer.as_mut() {
+                Some(timer) if !attacking => {
+                    timer.tick(Duration::from_secs_f32(MASH_SECONDS * presses as f32));
+                }
+                _ => state.dizzy = false,
+            }
+            continue;
+        }
+        let stunned = state.current_animation_timer.is_some() && state.move_phase.is_none();
+        let reeling = stunned || state.airborne || state.downed || state.grabbed.is_some();
+        if state.stun_gauge < STUN_GAUGE_MAX {
+            state.stun_gauge = (state.stun_gauge - STUN_DECAY * dt).max(0.0);
+        } else if !reeling {
+            state.stun_gauge = 0.0;
+            state.dizzy = true;
+            state.stun(DIZZY_SECONDS, AnimationState::Idle);
+            dizzied.send(Dizzied { fighter });
+        }
+    }
+}
+
+#[cfg(feature = "particles")]
+pub use birds::*;
+
+#[cfg(feature = "particles")]
+mod birds {
+    use bevy::prelude::*;
+    use bevy_hanabi::prelude::*;
+
+    use crate::character::CharacterState;
+
+    // A ring of little birds over a dizzy fighter's head. The particles sit
+    // still in the emitter's space and the emitter spins, so they circle.
+    const BIRD_HEIGHT: f32 = 2.0;
+    const BIRD_RADIUS: f32 = 0.3;
+    const BIRD_RATE: f32 = 12.0;
+    const BIRD_LIFETIME: f32 = 0.5;
+    // Radians a second.
+    const BIRD_SPIN: f32 = 5.0;
+
+    #[derive(Resource)]
+    pub struct DizzyBirdsEffect(Handle<EffectAsset>);
+
+    #[derive(Component)]
+    pub struct DizzyBirds {
+        fighter: Entity,
+    }
+
+    pub fn setup_dizzy_birds(mut commands: Commands, mut effects: ResMut<Assets<EffectAsset>>) {
+        let writer = ExprWriter::new();
+        let init_position = SetPositionCircleModifier {
+            center: writer.lit(Vec3::ZERO).expr(),
+            axis: writer.lit(Vec3::Y).expr(),
+            radius: writer.lit(BIRD_RADIUS).expr(),
+            dimension: ShapeDimension::Surface,
+        };
+        let init_velocity =
+            SetAttributeModifier::new(Attribute::VELOCITY, writer.lit(Vec3::ZERO).expr());
+        let init_lifetime =
+            SetAttributeModifier::new(Attribute::LIFETIME, writer.lit(BIRD_LIFETIME).expr());
+
+        let mut gradient = Gradient::new();
+        gradient.add_key(0.0, Vec4::new(1.0, 0.9, 0.3, 0.0));
+        gradient.add_key(0.2, Vec4::new(1.0, 0.9, 0.3, 1.0));
+        gradient.add_key(1.0, Vec4::new(1.0, 0.9, 0.3, 0.0));
+
+        let capacity = (BIRD_RATE * BIRD_LIFETIME * 2.0).ceil() as u32;
+        let effect = EffectAsset::new(capacity, Spawner::rate(BIRD_RATE.into()), writer.finish())
+            .with_name("dizzy_birds")
+            .with_simulation_space(SimulationSpace::Local)
+            .init(init_position)
+            .init(init_velocity)
+            .init(init_lifetime)
+            .render(ColorOverLifetimeModifier { gradient })
+            .render(SetSizeModifier {
+                size: Vec2::splat(0.06).into(),
+                screen_space_size: false,
+            });
+        commands.insert_resource(DizzyBirdsEffect(effects.add(effect)));
+    }
+
+    // Birds go up when a fighter goes dizzy and away when they come round.
+    pub fn show_dizzy_birds(
+        mut commands: Commands,
+        time: Res<Time>,
+        effect: Res<DizzyBirdsEffect>,
+        fighters: Query<(Entity, &CharacterState)>,
+        mut birds: Query<(Entity, &DizzyBirds, &mut Transform)>,
+    ) {
+        for (entity, bird, mut transform) in birds.iter_mut() {
+            if fighters
+                .get(bird.fighter)
+                .is_ok_and(|(_, state)| state.dizzy)
+            {
+                transform.rotate_y(BIRD_SPIN * time.delta_seconds());
+            } else {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        for (fighter, state) in fighters.iter() {
+            if !state.dizzy || birds.iter().any(|(_, bird, _)| bird.fighter == fighter) {
+                continue;
+            }
+            let entity = commands
+                .spawn((
+                    Name::new("dizzy_birds"),
+                    ParticleEffectBundle {
+                        effect: ParticleEffect::new(effect.0.clone()),
+                        transform: Transform::from_xyz(0.0, BIRD_HEIGHT, 0.0),
+                        ..default()
+                    },
+                    DizzyBirds { fighter },
+                ))
+                .id();
+            commands.entity(fighter).add_child(entity);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::{
+    core_pipeline::Skybox,
+    pbr::EnvironmentMapLight,
+    prelude::*,
+    render::render_resource::{TextureViewDescriptor, TextureViewDimension},
+};
+use serde::Deserialize;
+
+use crate::{
+    camera::Cameraman,
+    stage::{CurrentStage, StageDef},
+};
+
+// What the camera sees behind the stage and the light the surroundings cast
+// on it, for the fighters' PBR materials to reflect.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct EnvironmentDef {
+    // A cubemap: either a KTX2 cube or a PNG with the six faces stacked top
+    // to bottom.
+    pub skybox: String,
+    // Prefiltered KTX2 cubemaps of the same surroundings, for diffuse and
+    // specular lighting.
+    pub diffuse_map: String,
+    pub specular_map: String,
+}
+
+// Runs once the time of day has been picked, which can have surroundings of
+// its own. A stage without any is drawn over the clear color as before.
+pub fn apply_environment(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stage: Option<Res<CurrentStage>>,
+    stages: Res<Assets<StageDef>>,
+    cameras: Query<Entity, With<Cameraman>>,
+) {
+    let Some((stage, def)) = stage.and_then(|stage| {
+        let def = stages.get(&stage.def)?;
+        Some((stage, def))
+    }) else {
+        return;
+    };
+    let environment = stage
+        .time_of_day
+        .and_then(|time| def.lighting.get(&time))
+        .and_then(|lighting| lighting.environment.as_ref())
+        .or(def.environment.as_ref());
+    for camera in cameras.iter() {
+        let mut camera = commands.entity(camera);
+        match environment {
+            Some(environment) => {
+                camera.insert((
+                    Skybox(asset_server.load(environment.skybox.clone())),
+                    EnvironmentMapLight {
+                        diffuse_map: asset_server.load(environment.diffuse_map.clone()),
+                        specular_map: asset_server.load(environment.specular_map.clone()),
+                    },
+                ));
+            }
+            None => {
+                camera.remove::<(Skybox, EnvironmentMapLight)>();
+            }
+        }
+    }
+}
+
+// A skybox loaded from a PNG arrives as one tall image, which has to be
+// split into its six faces before it can be sampled as a cube.
+pub fn prepare_skyboxes(
+    mut events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    skyboxes: Query<&Skybox>,
+) {
+    for event in events.iter() {
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+        if !skyboxes.iter().any(|skybox| skybox.0.id() == *id) {
+            continue;
+        }
+        let Some(image) = images.get_mut(*id) else {
+            continue;
+        };
+        if image.texture_descriptor.array_layer_count() == 1 {
+            image.reinterpret_stacked_2d_as_array(image.height() / image.width());
+            image.texture_view_descriptor = Some(TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::Cube),
+                ..default()
+            });
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::{prelude::*, scene::SceneInstanceReady};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    character::{Character, CharacterState, PUSHBOX_GROUP, STAGE_GROUP},
+    roster::CharacterDef,
+    settings::Settings,
+};
+
+// How far above the hip the floor is searched for, and how far below.
+const PROBE_ABOVE: f32 = 0.5;
+const PROBE_BELOW: f32 = 2.0;
+// Feet already this close to where they should be are left alone.
+const IK_TOLERANCE: f32 = 0.005;
+
+// The bones of one leg, hip to ankle, as named in the character's model.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LegBones {
+    pub thigh: String,
+    pub calf: String,
+    pub foot: String,
+}
+
+// Each leg's thigh, calf and foot bone.
+#[derive(Component)]
+pub struct FootIk {
+    legs: Vec<[Entity; 3]>,
+}
+
+pub fn foot_ik_enabled(settings: Res<Settings>) -> bool {
+    settings.foot_ik
+}
+
+pub fn find_leg_bones(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    characters: Res<Assets<CharacterDef>>,
+    fighters: Query<&Character>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        let Some(def) = fighters
+            .get(*fighter)
+            .ok()
+            .and_then(|character| characters.get(&character.0))
+        else {
+            continue;
+        };
+        let find = |bone: &str| {
+            children
+                .iter_descendants(*fighter)
+                .find(|entity| names.get(*entity).is_ok_and(|name| name.as_str() == bone))
+        };
+        let legs: Vec<[Entity; 3]> = def
+            .legs
+            .iter()
+            .filter_map(|leg| Some([find(&leg.thigh)?, find(&leg.calf)?, find(&leg.foot)?]))
+            .collect();
+        if legs.len() != def.legs.len() {
+            warn!("{} is missing leg bones, its feet won't follow the floor", def.name);
+        }
+        if !legs.is_empty() {
+            commands.entity(*fighter).insert(FootIk { legs });
+        }
+    }
+}
+
+// Recomputes the global transforms under `entity` after its local transform
+// changed, since propagation for this frame has already run.
+fn repropagate(
+    entity: Entity,
+    parent: GlobalTransform,
+    children: &Query<&Children>,
+    bones: &mut Query<(&mut Transform, &mut GlobalTransform), Without<FootIk>>,
+) {
+    let Ok((local, mut global)) = bones.get_mut(entity) else {
+        return;
+    };
+    *global = parent.mul_transform(*local);
+    let global = *global;
+    for child in children.get(entity).into_iter().flatten() {
+        repropagate(*child, global, children, bones);
+    }
+}
+
+// Bends each grounded fighter's legs so the feet land on the floor beneath
+// them rather than on the flat plane the clips were made for, and tilts the
+// feet to match its slope. Runs on the final pose, after propagation.
+pub fn plant_feet(
+    rapier: Res<RapierContext>,
+    fighters: Query<(&FootIk, &CharacterState, &GlobalTransform)>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    mut bones: Query<(&mut Transform, &mut GlobalTransform), Without<FootIk>>,
+) {
+    let floor = QueryFilter::new().groups(CollisionGroups::new(PUSHBOX_GROUP, STAGE_GROUP));
+    for (ik, state, fighter) in fighters.iter() {
+        if state.airborne {
+            continue;
+        }
+        for &[thigh, calf, foot] in ik.legs.iter() {
+            let global = |entity| bones.get(entity).map(|(_, global)| *global).ok();
+            let (Some(thigh_global), Some(calf_global), Some(foot_global)) =
+                (global(thigh), global(calf), global(foot))
+            else {
+                continue;
+            };
+            let Some(hip_parent) = parents.get(thigh).ok().and_then(|parent| global(parent.get()))
+            else {
+                continue;
+            };
+            let (hip, knee, ankle) = (
+                thigh_global.translation(),
+                calf_global.translation(),
+                foot_global.translation(),
+            );
+
+            // The clip's ankle height above the fighter's feet, kept above
+            // whatever floor is actually under the foot.
+            let origin = Vec3::new(ankle.x, hip.y + PROBE_ABOVE, ankle.z);
+            let Some((_, hit)) = rapier.cast_ray_and_get_normal(
+                origin,
+                Vec3::NEG_Y,
+                PROBE_ABOVE + PROBE_BELOW,
+                true,
+                floor,
+            ) else {
+                continue;
+            };
+            let target = Vec3::new(
+                ankle.x,
+                hit.point.y + ankle.y - fighter.translation().y,
+                ankle.z,
+            );
+            if (target - ankle).length() < IK_TOLERANCE && hit.normal.y > 0.999 {
+                continue;
+            }
+
+            // Two-bone solve: open or close the knee until the leg is as
+            // long as the hip-to-target distance, then swing the whole leg
+            // from the hip to point at the target.
+            let (upper, lower) = (knee - hip, ankle - knee);
+            let (upper_length, lower_length) = (upper.length(), lower.length());
+            let reach = (target - hip)
+                .length()
+                .clamp(0.01, upper_length + lower_length - 0.001);
+            let current = (-upper).angle_between(lower);
+            let wanted = ((upper_length.powi(2) + lower_length.powi(2) - reach.powi(2))
+                / (2.0 * upper_length * lower_length))
+                .clamp(-1.0, 1.0)
+                .acos();
+            let bend_axis = upper.cross(lower).try_normalize().unwrap_or(Vec3::Z);
+            let knee_turn = Quat::from_axis_angle(bend_axis, current - wanted);
+            let bent_ankle = knee + knee_turn * lower;
+            let hip_turn = Quat::from_rotation_arc(
+                (bent_ankle - hip).normalize(),
+                (target - hip).normalize(),
+            );
+            let ground_tilt = Quat::from_rotation_arc(Vec3::Y, hit.normal);
+
+            let world_rotation = |global: GlobalTransform| global.to_scale_rotation_translation().1;
+            let thigh_rotation = hip_turn * world_rotation(thigh_global);
+            let calf_rotation = hip_turn * knee_turn * world_rotation(calf_global);
+            let foot_rotation = ground_tilt * world_rotation(foot_global);
+            for (bone, parent_rotation, rotation) in [
+                (thigh, world_rotation(hip_parent), thigh_rotation),
+                (calf, thigh_rotation, calf_rotation),
+                (foot, calf_rotation, foot_rotation),
+            ] {
+                if let Ok((mut local, _)) = bones.get_mut(bone) {
+                    local.rotation = parent_rotation.inverse() * rotation;
+                }
+            }
+            repropagate(thigh, hip_parent, &children, &mut bones);
+        }
+    }
+}
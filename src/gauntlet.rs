@@ -0,0 +1,283 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    ai::Difficulty,
+    character::{CharacterState, Health, Team},
+    combat::FightEnded,
+    locale::Strings,
+    moves::{AttackLevel, MoveId, MovePhase},
+    profile::{Profile, PERFECT_BONUS, ROUND_WIN_POINTS},
+    rng::GameRng,
+    roster::{CharacterDef, MatchSetup, Roster},
+    round::{MatchRules, RoundEnded},
+    AppState,
+};
+
+// Walking speed of a swift opponent, as a multiple of the usual.
+pub const SWIFT_SPEED: f32 = 1.35;
+// Health a regenerating opponent gets back a second.
+const REGENERATION_RATE: f32 = 2.0;
+// And what everyone gets back with the match's regeneration rule on.
+const CASUAL_REGENERATION_RATE: f32 = 1.0;
+// Opponents beaten before the AI steps up to the next difficulty.
+const NORMAL_AFTER: u32 = 2;
+const HARD_AFTER: u32 = 5;
+// The gauntlet never runs out of opponents, so beating this many counts as
+// clearing it.
+pub const CLEAR_AFTER: u32 = 8;
+
+// What makes a gauntlet opponent tougher than usual. Each one is rolled when
+// the opponent is, and sits on their fighter for the fight.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Modifier {
+    // Walks faster.
+    Swift,
+    // Heavy attacks can't be knocked out of, start to finish.
+    Armored,
+    // Slowly heals through the round.
+    Regenerating,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 3] = [Modifier::Swift, Modifier::Armored, Modifier::Regenerating];
+
+    // Points for rounds taken off an opponent are scaled by how much harder
+    // their modifier makes them.
+    pub fn score_multiplier(self) -> f32 {
+        match self {
+            Modifier::Swift => 1.25,
+            Modifier::Armored => 1.5,
+            Modifier::Regenerating => 2.0,
+        }
+    }
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Modifier::Swift => "gauntlet.modifier.swift",
+            Modifier::Armored => "gauntlet.modifier.armored",
+            Modifier::Regenerating => "gauntlet.modifier.regenerating",
+        }
+    }
+
+    pub fn description_key(self) -> &'static str {
+        match self {
+            Modifier::Swift => "gauntlet.modifier.swift.description",
+            Modifier::Armored => "gauntlet.modifier.armored.description",
+            Modifier::Regenerating => "gauntlet.modifier.regenerating.description",
+        }
+    }
+
+    // Whether a hit on a fighter in `state` lands on armor. Armored heavies
+    // shrug off every hit until their active frames are over.
+    pub fn armors(self, state: &CharacterState) -> bool {
+        self == Modifier::Armored
+            && matches!(
+                state.move_phase,
+                Some(MovePhase::Startup | MovePhase::Active)
+            )
+            && MoveId::from_state(state.player_state)
+                .is_some_and(|move_id| move_id.data().level == AttackLevel::Heavy)
+    }
+}
+
+// The player's run: one fighter against an endless line of AI opponents,
+// each with a modifier, until they lose.
+#[derive(Resource, Default, Debug)]
+pub struct Gauntlet {
+    // Opponents beaten so far.
+    pub wins: u32,
+    pub score: u32,
+    // The current opponent's modifier, once they've been rolled.
+    pub modifier: Option<Modifier>,
+    // How many wins the run had when the current opponent was rolled. A
+    // win since means it's time for the next one.
+    rolled_at: Option<u32>,
+    // The player has lost and the run is done.
+    pub over: bool,
+}
+
+impl Gauntlet {
+    // The AI sharpens up the longer the run goes.
+    pub fn difficulty(&self) -> Difficulty {
+        if self.wins >= HARD_AFTER {
+            Difficulty::Hard
+        } else if self.wins >= NORMAL_AFTER {
+            Difficulty::Normal
+        } else {
+            Difficulty::Easy
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ChallengerScreen;
+
+// Rolls the next opponent and their modifier unless one is already waiting,
+// then shows who's coming. After a loss it shows how the run went instead.
+pub fn setup_challenger_screen(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    strings: Strings,
+    mut setup: ResMut<MatchSetup>,
+    mut gauntlet: ResMut<Gauntlet>,
+) {
+    if !gauntlet.over && gauntlet.rolled_at != Some(gauntlet.wins) {
+        gauntlet.rolled_at = Some(gauntlet.wins);
+        let rng = rng.cosmetic();
+        setup.enemy = rng.gen_range(0..roster.characters.len());
+        setup.difficulty = gauntlet.difficulty();
+        gauntlet.modifier = Some(Modifier::ALL[rng.gen_range(0..Modifier::ALL.len())]);
+    }
+
+    let mut lines = vec![(
+        strings.fill(
+            "gauntlet.record",
+            &[("wins", &gauntlet.wins), ("score", &gauntlet.score)],
+        ),
+        24.0,
+    )];
+    match gauntlet.modifier.filter(|_| !gauntlet.over) {
+        Some(modifier) => {
+            let name = roster
+                .characters
+                .get(setup.enemy)
+                .and_then(|handle| characters.get(handle))
+                .map_or(String::new(), |def| def.name.clone());
+            lines.extend([
+                (strings.get("gauntlet.challenger"), 32.0),
+                (name, 48.0),
+                (strings.get(modifier.name_key()), 36.0),
+                (strings.get(modifier.description_key()), 22.0),
+                (
+                    strings.fill(
+                        "gauntlet.multiplier",
+                        &[("value", &modifier.score_multiplier())],
+                    ),
+                    22.0,
+                ),
+                (strings.get("gauntlet.fight_hint"), 20.0),
+            ]);
+        }
+        None => lines.extend([
+            (strings.get("gauntlet.over"), 48.0),
+            (strings.get("gauntlet.over_hint"), 20.0),
+        ]),
+    }
+
+    // Drawn over the loading cover, which stays down until the fight starts.
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                z_index: ZIndex::Global(101),
+                ..default()
+            },
+            ChallengerScreen,
+        ))
+        .with_children(|screen| {
+            for (value, font_size) in lines {
+                screen.spawn(TextBundle::from_section(
+                    value,
+                    TextStyle {
+                        font_size,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+pub fn close_challenger_screen(
+    mut commands: Commands,
+    screens: Query<Entity, With<ChallengerScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Enter takes on the challenger, or once the run is over, starts a new one
+// from character select.
+pub fn challenger_keys(
+    keys: Res<Input<KeyCode>>,
+    mut gauntlet: ResMut<Gauntlet>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keys.just_pressed(KeyCode::Return) {
+        return;
+    }
+    if gauntlet.over {
+        *gauntlet = Gauntlet::default();
+        next_state.set(AppState::CharacterSelect);
+    } else {
+        next_state.set(AppState::Fight);
+    }
+}
+
+// Rounds the player takes score as in arcade, scaled by the modifier.
+pub fn score_gauntlet_rounds(mut gauntlet: ResMut<Gauntlet>, mut ended: EventReader<RoundEnded>) {
+    let multiplier = gauntlet.modifier.map_or(1.0, Modifier::score_multiplier);
+    for round in ended.iter().filter(|round| round.winner == Some(Team::One)) {
+        let mut points = ROUND_WIN_POINTS;
+        if round.perfect {
+            points += PERFECT_BONUS;
+        }
+        gauntlet.score += (points as f32 * multiplier).round() as u32;
+    }
+}
+
+// A win brings on a fresh opponent next time; anything else ends the run,
+// and the best run is kept on the profile. Getting far enough is a clear.
+pub fn record_gauntlet_result(
+    mut gauntlet: ResMut<Gauntlet>,
+    mut profile: ResMut<Profile>,
+    mut ended: EventReader<FightEnded>,
+) {
+    for fight in ended.iter() {
+        if fight.winner == Some(Team::One) {
+            gauntlet.wins += 1;
+            if gauntlet.wins == CLEAR_AFTER {
+                profile.record_arcade_clear();
+                profile.save();
+            }
+            continue;
+        }
+        gauntlet.over = true;
+        if gauntlet.wins > profile.best_gauntlet {
+            profile.best_gauntlet = gauntlet.wins;
+            profile.save();
+        }
+    }
+}
+
+// Regenerating opponents heal at their own rate, which the casual rule
+// doesn't add to.
+pub fn regenerate_health(
+    time: Res<Time>,
+    rules: Res<MatchRules>,
+    mut fighters: Query<(Option<&Modifier>, &mut Health)>,
+) {
+    for (modifier, mut health) in fighters.iter_mut() {
+        let rate = match modifier {
+            Some(Modifier::Regenerating) => REGENERATION_RATE,
+            _ if rules.regeneration => CASUAL_REGENERATION_RATE,
+            _ => continue,
+        };
+        if health.current > 0.0 {
+            health.current = (health.current + rate * time.delta_seconds()).min(health.max);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    character::{AnimationState, CharacterState, Health, Knockback, Team},
+    combat::Hurtbox,
+    moves::AttackLevel,
+    round::{MatchRules, Round, RoundPhase},
+    tag_team::TagEntrance,
+    SIMULATION_HZ,
+};
+
+const BOOM_WIDTH: f32 = 0.25;
+const BARREL_LENGTH: f32 = 0.8;
+const WARNING_COLOR: Color = Color::rgb(1.0, 0.2, 0.1);
+const WARNING_OPACITY: f32 = 0.5;
+const WARNING_DEPTH: f32 = 1.0;
+// Flashes a second the warning strip goes through.
+const WARNING_FLASH_RATE: f32 = 3.0;
+// Just above the floor so it doesn't fight with it, and below any decals.
+const WARNING_HEIGHT: f32 = 0.01;
+
+// Something on a stage that goes off on a timer and hurts whoever is in the
+// way, on either side.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum HazardKind {
+    // A boom hanging from a mast above the stage, swung `arc` radians either
+    // side of straight down: one way on one go, back the next.
+    Boom {
+        pivot: (f32, f32, f32),
+        length: f32,
+        arc: f32,
+    },
+    // A barrel rolled along the floor from `from` to `to`, which are best
+    // kept off the ends of the stage.
+    Barrel {
+        from: f32,
+        to: f32,
+        radius: f32,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct HazardDef {
+    pub kind: HazardKind,
+    // Seconds from the start of the round to the end of the first go, and
+    // from the end of each go to the end of the next.
+    pub interval: f32,
+    // Seconds the warning shows before a go, and that the go takes.
+    pub warning: f32,
+    pub duration: f32,
+    pub damage: f32,
+    pub knockback: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HazardPhase {
+    Resting,
+    Warning,
+    // `pass` counts the goes this round; `progress` runs from 0 to 1.
+    Active { pass: u32, progress: f32 },
+}
+
+impl HazardDef {
+    // Where in its cycle the hazard is this far into a round.
+    fn phase(&self, seconds: f32) -> HazardPhase {
+        let pass = (seconds / self.interval) as u32;
+        let left = self.interval - seconds % self.interval;
+        if left <= self.duration {
+            HazardPhase::Active {
+                pass,
+                progress: 1.0 - left / self.duration,
+            }
+        } else if left <= self.duration + self.warning {
+            HazardPhase::Warning
+        } else {
+            HazardPhase::Resting
+        }
+    }
+
+    // Where the hazard is a share of the way through go number `pass`, and
+    // which way along x it's heading.
+    fn pose(&self, ground: f32, pass: u32, progress: f32) -> (Transform, f32) {
+        match self.kind {
+            HazardKind::Boom { pivot, length, arc } => {
+                let direction = if pass % 2 == 0 { 1.0 } else { -1.0 };
+                // Eased, so it hangs at either end and is fastest at the
+                // bottom of the swing.
+                let swing = (1.0 - (progress * std::f32::consts::PI).cos()) / 2.0;
+                let rotation = Quat::from_rotation_z(direction * arc * (2.0 * swing - 1.0));
+                let pivot = Vec3::from(pivot);
+                (
+                    Transform::from_translation(pivot + rotation * Vec3::NEG_Y * length / 2.0)
+                        .with_rotation(rotation),
+                    direction,
+                )
+            }
+            HazardKind::Barrel { from, to, radius } => {
+                let x = from + (to - from) * progress;
+                (
+                    Transform::from_xyz(x, ground + radius, 0.0).with_rotation(
+                        Quat::from_rotation_z(-(x - from) / radius)
+                            * Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+                    ),
+                    (to - from).signum(),
+                )
+            }
+        }
+    }
+
+    // The stretch of floor, from left to right, that a go passes over.
+    fn span(&self) -> (f32, f32) {
+        match self.kind {
+            HazardKind::Boom { pivot, length, arc } => {
+                let reach = length * arc.sin();
+                (pivot.0 - reach, pivot.0 + reach)
+            }
+            HazardKind::Barrel { from, to, .. } => (from.min(to), from.max(to)),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Hazard {
+    def: HazardDef,
+    ground: f32,
+    phase: HazardPhase,
+    // Which way along x the hazard is going, for knockback.
+    direction: f32,
+    // Fighters already hit on this go.
+    struck: Vec<Entity>,
+}
+
+// A strip on the floor where a hazard is about to go, flashing while it
+// warns.
+#[derive(Component)]
+pub struct HazardWarning {
+    hazard: Entity,
+}
+
+// Called with the stage, which only spawns once, so hazards stay up from one
+// fight to the next and sit still between rounds.
+pub fn spawn_hazards(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    ground: f32,
+    hazards: &[HazardDef],
+) {
+    for def in hazards {
+        let (mesh, collider, color) = match def.kind {
+            HazardKind::Boom { length, .. } => (
+                Mesh::from(shape::Box::new(BOOM_WIDTH, length, BOOM_WIDTH)),
+                Collider::cuboid(BOOM_WIDTH / 2.0, length / 2.0, BOOM_WIDTH / 2.0),
+                Color::rgb(0.45, 0.3, 0.18),
+            ),
+            HazardKind::Barrel { radius, .. } => (
+                Mesh::from(shape::Cylinder {
+                    radius,
+                    height: BARREL_LENGTH,
+                    ..default()
+                }),
+                Collider::ball(radius),
+                Color::rgb(0.5, 0.32, 0.16),
+            ),
+        };
+        let (transform, direction) = def.pose(ground, 0, 0.0);
+        let hazard = commands
+            .spawn((
+                Name::new("hazard"),
+                PbrBundle {
+                    mesh: meshes.add(mesh),
+                    material: materials.add(StandardMaterial {
+                        base_color: color,
+                        perceptual_roughness: 0.8,
+                        ..default()
+                    }),
+                    transform,
+                    ..default()
+                },
+                RigidBody::KinematicPositionBased,
+                collider,
+                ActiveEvents::COLLISION_EVENTS,
+                ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
+                Team::hazard_groups(),
+                Hazard {
+                    def: *def,
+                    ground,
+                    phase: HazardPhase::Resting,
+                    direction,
+                    struck: Vec::new(),
+                },
+            ))
+            .id();
+
+        let (left, right) = def.span();
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Box::new(
+                    right - left,
+                    0.001,
+                    WARNING_DEPTH,
+                ))),
+                material: materials.add(StandardMaterial {
+                    base_color: WARNING_COLOR.with_a(0.0),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_xyz((left + right) / 2.0, ground + WARNING_HEIGHT, 0.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            NotShadowCaster,
+            HazardWarning { hazard },
+        ));
+    }
+}
+
+// Hazards run on the round clock, so they go at the same moments in a
+// replay as they did live and roll back with the round. Training has no
+// rounds and leaves them be.
+pub fn move_hazards(
+    rules: Res<MatchRules>,
+    round: Res<Round>,
+    mut hazards: Query<(&mut Hazard, &mut Transform)>,
+) {
+    let seconds = match round.phase {
+        RoundPhase::Fighting if rules.hazards => round.ticks as f32 / SIMULATION_HZ as f32,
+        _ => 0.0,
+    };
+    for (mut hazard, mut transform) in hazards.iter_mut() {
+        hazard.phase = hazard.def.phase(seconds);
+        let (pass, progress) = match hazard.phase {
+            HazardPhase::Active { pass, progress } => (pass, progress),
+            // Waiting where the next go starts.
+            _ => ((seconds / hazard.def.interval) as u32, 0.0),
+        };
+        let (pose, direction) = hazard.def.pose(hazard.ground, pass, progress);
+        *transform = pose;
+        hazard.direction = direction;
+        if !matches!(hazard.phase, HazardPhase::Active { .. }) {
+            hazard.struck.clear();
+        }
+    }
+}
+
+// A moving hazard hurts anyone it touches, once a go. There's no blocking
+// it; the warning is the chance to get out of the way.
+pub fn hazard_hits(
+    rules: Res<MatchRules>,
+    mut round: ResMut<Round>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut hazards: Query<&mut Hazard>,
+    hurtboxes: Query<&Hurtbox>,
+    mut fighters: Query<(&mut Health, &mut Knockback, &mut CharacterState, &Team)>,
+    entrances: Query<(), With<TagEntrance>>,
+) {
+    for collision_event in collision_events.iter() {
+        let CollisionEvent::Started(entity1, entity2, _flags) = collision_event else {
+            continue;
+        };
+        let (hazard, hurtbox) = match (hurtboxes.get(*entity2), hurtboxes.get(*entity1)) {
+            (Ok(hurtbox), _) => (*entity1, hurtbox),
+            (_, Ok(hurtbox)) => (*entity2, hurtbox),
+            _ => continue,
+        };
+        let Ok(mut hazard) = hazards.get_mut(hazard) else {
+            continue;
+        };
+        if !matches!(hazard.phase, HazardPhase::Active { .. })
+            || hazard.struck.contains(&hurtbox.owner)
+            || entrances.contains(hurtbox.owner)
+        {
+            continue;
+        }
+        let Ok((mut health, mut knockback, mut state, team)) = fighters.get_mut(hurtbox.owner)
+        else {
+            continue;
+        };
+        hazard.struck.push(hurtbox.owner);
+        let damage = hazard.def.damage * rules.damage_multiplier;
+        health.current = (health.current - damage).max(0.0);
+        round.take_damage(*team, damage);
+        knockback.0 += Vec3::X * hazard.direction * hazard.def.knockback;
+        state.stun(AttackLevel::Heavy.hitstun(), AnimationState::Idle);
+    }
+}
+
+pub fn flash_hazard_warnings(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    hazards: Query<&Hazard>,
+    mut warnings: Query<(&HazardWarning, &Handle<StandardMaterial>, &mut Visibility)>,
+) {
+    let flash = (time.elapsed_seconds() * WARNING_FLASH_RATE * std::f32::consts::TAU).cos();
+    for (warning, material, mut visibility) in warnings.iter_mut() {
+        let warning = hazards
+            .get(warning.hazard)
+            .is_ok_and(|hazard| hazard.phase == HazardPhase::Warning);
+        *visibility = if warning {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if let Some(material) = materials.get_mut(material).filter(|_| warning) {
+            material
+                .base_color
+                .set_a(WARNING_OPACITY * (0.5 + 0.5 * flash));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use bevy::{
+    asset::AssetApp,
+    prelude::*,
+    scene::{SceneInstanceReady, ScenePlugin},
+    time::TimeUpdateStrategy,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ai::run_ai,
+    character::{process_movement, AnimationState, Character, CharacterState, Team},
+    input::ControlInput,
+    moves::MovePhase,
+    roster::{EnemyControl, MatchSetup},
+    round::MatchRules,
+    AppState, GameSet, SimulationPlugin, SimulationTick, SIMULATION_HZ,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct ScriptedInput {
+    pub tick: u32,
+    pub team: Team,
+    pub input: ControlInput,
+}
+
+// Inputs fed to the fighters in place of a keyboard. Each entry replaces the
+// team's ControlInput on the tick it is scheduled for.
+#[derive(Resource, Default)]
+pub struct ScriptedInputs(pub Vec<ScriptedInput>);
+
+impl ScriptedInputs {
+    pub fn at(mut self, tick: u32, team: Team, input: ControlInput) -> Self {
+        self.0.push(ScriptedInput { tick, team, input });
+        self
+    }
+}
+
+// Stand-in for a skinned bone. Without a renderer there are no glTF scenes, so
+// limbs are placed by hand and pushed out to `reach` while an attack is
+// active.
+#[derive(Component)]
+pub struct ProxyLimb {
+    pub rest: Vec3,
+    pub reach: Vec3,
+    pub extended_in: Option<AnimationState>,
+}
+
+const PROXY_LIMBS: [(&str, Vec3, Vec3, Option<AnimationState>); 12] = [
+    (
+        "hand_l",
+        Vec3::new(0.2, 1.4, 0.3),
+        Vec3::new(0.2, 1.4, 1.1),
+        Some(AnimationState::Punching),
+    ),
+    (
+        "hand_r",
+        Vec3::new(-0.2, 1.4, 0.3),
+        Vec3::new(-0.2, 1.4, 0.3),
+        None,
+    ),
+    (
+        "lowerarm_l",
+        Vec3::new(0.2, 1.3, 0.1),
+        Vec3::new(0.2, 1.35, 0.6),
+        Some(AnimationState::Elbowing),
+    ),
+    (
+        "lowerarm_r",
+        Vec3::new(-0.2, 1.3, 0.1),
+        Vec3::new(-0.2, 1.3, 0.1),
+        None,
+    ),
+    (
+        "upperarm_l",
+        Vec3::new(0.2, 1.45, 0.0),
+        Vec3::new(0.2, 1.45, 0.9),
+        Some(AnimationState::Charging),
+    ),
+    (
+        "upperarm_r",
+        Vec3::new(-0.2, 1.45, 0.0),
+        Vec3::new(-0.2, 1.45, 0.0),
+        None,
+    ),
+    (
+        "foot_l",
+        Vec3::new(0.15, 0.1, 0.0),
+        Vec3::new(0.15, 1.0, 1.3),
+        Some(AnimationState::Kicking),
+    ),
+    (
+        "foot_r",
+        Vec3::new(-0.15, 0.1, 0.0),
+        Vec3::new(-0.15, 0.1, 0.0),
+        None,
+    ),
+    (
+        "head",
+        Vec3::new(0.0, 1.7, 0.0),
+        Vec3::new(0.0, 1.7, 0.0),
+        None,
+    ),
+    (
+        "spine_02",
+        Vec3::new(0.0, 1.2, 0.0),
+        Vec3::new(0.0, 1.2, 0.0),
+        None,
+    ),
+    (
+        "calf_l",
+        Vec3::new(0.15, 0.5, 0.0),
+        Vec3::new(0.15, 0.5, 0.0),
+        None,
+    ),
+    (
+        "calf_r",
+        Vec3::new(-0.15, 0.5, 0.0),
+        Vec3::new(-0.15, 0.5, 0.0),
+        None,
+    ),
+];
+
+// Stands in for the scene spawner too, announcing each skeleton as ready so
+// the colliders get attached.
+fn spawn_proxy_skeletons(
+    mut commands: Commands,
+    mut ready: EventWriter<SceneInstanceReady>,
+    fighters: Query<Entity, Added<Character>>,
+) {
+    for fighter in &fighters {
+        ready.send(SceneInstanceReady { parent: fighter });
+        commands.entity(fighter).with_children(|parent| {
+            for (name, rest, reach, extended_in) in PROXY_LIMBS {
+                parent
+                    .spawn(SpatialBundle::from_transform(Transform::from_translation(rest)))
+                    .insert(Name::new(name))
+                    .insert(ProxyLimb {
+                        rest,
+                        reach,
+                        extended_in,
+                    });
+            }
+        });
+    }
+}
+
+// Runs on the tick, before physics, so the limbs are posed from exactly what
+// the simulation says and a script always lands its hits on the same ticks.
+fn pose_proxy_skeletons(
+    fighters: Query<&CharacterState>,
+    mut limbs: Query<(&Parent, &ProxyLimb, &mut Transform)>,
+) {
+    for (parent, limb, mut transform) in limbs.iter_mut() {
+        let Ok(state) = fighters.get(parent.get()) else {
+            continue;
+        };
+        let extended = limb.extended_in == Some(state.player_state)
+            && state.move_phase == Some(MovePhase::Active);
+        transform.translation = if extended { limb.reach } else { limb.rest };
+    }
+}
+
+fn apply_scripted_inputs(
+    tick: Res<SimulationTick>,
+    script: Res<ScriptedInputs>,
+    mut fighters: Query<(&mut ControlInput, &Team)>,
+) {
+    for scripted in script.0.iter().filter(|scripted| scripted.tick == tick.0) {
+        for (mut input, team) in fighters.iter_mut() {
+            if *team == scripted.team {
+                *input = scripted.input;
+            }
+        }
+    }
+}
+
+// Builds an app that runs the combat simulation without a window, renderer or
+// audio device. Every `App::update` advances exactly one simulation tick.
+pub fn headless_app(script: ScriptedInputs) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        ScenePlugin,
+    ))
+    .init_asset::<Mesh>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / SIMULATION_HZ,
+    )))
+    .add_plugins(SimulationPlugin)
+    .insert_resource(MatchSetup {
+        enemy_control: EnemyControl::Idle,
+        // Same seed every run, so a script always plays out the same way.
+        seed: Some(0),
+        ..default()
+    })
+    // Scripts count ticks from the spawn, so skip the round intro.
+    .insert_resource(MatchRules {
+        intro_ticks: 0,
+        ..default()
+    })
+    .insert_resource(script)
+    .add_systems(OnEnter(AppState::CharacterSelect), skip_menus)
+    .add_systems(
+        FixedUpdate,
+        // Ahead of the throws too, so a throw break is seen by the grab
+        // rather than eaten as a throw of its own.
+        apply_scripted_inputs
+            .before(run_ai)
+            .run_if(in_state(AppState::Fight)),
+    )
+    .add_systems(Update, spawn_proxy_skeletons.in_set(GameSet::Animation))
+    .add_systems(
+        FixedUpdate,
+        pose_proxy_skeletons
+            .after(process_movement)
+            .before(PhysicsSet::SyncBackend),
+    );
+    app
+}
+
+// Scripted runs keep whatever `MatchSetup` and `MatchRules` they were given.
+fn skip_menus(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Fight);
+}
+
+// Pumps the app until the roster has loaded and the fighters are spawned.
+pub fn wait_for_fight(app: &mut App) {
+    for _ in 0..600 {
+        app.update();
+        if *app.world.resource::<State<AppState>>().get() == AppState::Fight {
+            app.update();
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("roster never finished loading");
+}
+
+pub fn run_ticks(app: &mut App, ticks: u32) {
+    for _ in 0..ticks {
+        app.update();
+    }
+}
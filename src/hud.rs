@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Health, Meter, Team},
+    settings::Settings,
+    tag_team::TagTeam,
+    GameMode,
+};
+
+// Share of health below which the bar switches to the low-health colour.
+const LOW_HEALTH: f32 = 0.25;
+const HEALTH_BAR_WIDTH: f32 = 40.0;
+const METER_WIDTH: f32 = 25.0;
+const PARTNER_WIDTH: f32 = 20.0;
+// The benched partner's bar is drawn fainter than the fighter's own.
+const PARTNER_ALPHA: f32 = 0.6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Gauge {
+    Health,
+    Meter,
+    // Health of the tag team partner waiting off-screen.
+    Partner,
+}
+
+// The coloured part of a bar; its width tracks the fighter's value.
+#[derive(Component)]
+pub struct GaugeFill {
+    pub team: Team,
+    pub gauge: Gauge,
+}
+
+// A row of bars, removed when the fight is torn down.
+#[derive(Component)]
+pub struct Hud;
+
+// The bar's outline, in the team's colour.
+#[derive(Component)]
+pub struct GaugeFrame(pub Team);
+
+fn spawn_gauge(parent: &mut ChildBuilder, team: Team, gauge: Gauge) {
+    let (width, height) = match gauge {
+        Gauge::Health => (HEALTH_BAR_WIDTH, 20.0),
+        Gauge::Meter => (METER_WIDTH, 10.0),
+        Gauge::Partner => (PARTNER_WIDTH, 8.0),
+    };
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(width),
+                    height: Val::Px(height),
+                    border: UiRect::all(Val::Px(2.0)),
+                    // Player two's bars drain towards the middle of the screen.
+                    justify_content: match team {
+                        Team::One => JustifyContent::FlexStart,
+                        Team::Two => JustifyContent::FlexEnd,
+                    },
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            GaugeFrame(team),
+        ))
+        .with_children(|frame| {
+            frame.spawn((
+                NodeBundle {
+                    style: Style {
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                GaugeFill { team, gauge },
+            ));
+        });
+}
+
+pub fn setup_hud(mut commands: Commands, mode: Res<GameMode>) {
+    let mut rows = vec![
+        (Gauge::Health, Val::Px(16.0), Val::Auto),
+        (Gauge::Meter, Val::Auto, Val::Px(16.0)),
+    ];
+    if *mode == GameMode::TagTeam {
+        rows.push((Gauge::Partner, Val::Px(44.0), Val::Auto));
+    }
+    for (gauge, top, bottom) in rows {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top,
+                        bottom,
+                        width: Val::Percent(100.0),
+                        padding: UiRect::horizontal(Val::Px(16.0)),
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Hud,
+            ))
+            .with_children(|row| {
+                spawn_gauge(row, Team::One, gauge);
+                spawn_gauge(row, Team::Two, gauge);
+            });
+    }
+}
+
+pub fn update_hud(
+    settings: Res<Settings>,
+    fighters: Query<(&Team, &Health, &Meter, Option<&TagTeam>)>,
+    mut fills: Query<(&GaugeFill, &mut Style, &mut BackgroundColor)>,
+    mut frames: Query<(&GaugeFrame, &mut BorderColor)>,
+) {
+    let colors = settings.palette.colors();
+    for (fill, mut style, mut background) in fills.iter_mut() {
+        let Some((_, health, meter, tag)) = fighters.iter().find(|(team, ..)| **team == fill.team)
+        else {
+            continue;
+        };
+        let (share, color) = match fill.gauge {
+            Gauge::Health => {
+                let share = health.current / health.max;
+                let color = if share < LOW_HEALTH {
+                    colors.low_health
+                } else {
+                    colors.health
+                };
+                (share, color)
+            }
+            Gauge::Meter => (meter.current / meter.max, colors.meter),
+            Gauge::Partner => {
+                let Some(tag) = tag else {
+                    continue;
+                };
+                let partner = tag.partner.health;
+                let mut color = colors.health;
+                color.set_a(PARTNER_ALPHA);
+                (partner.current / partner.max, color)
+            }
+        };
+        style.width = Val::Percent(share.clamp(0.0, 1.0) * 100.0);
+        background.0 = color;
+    }
+    for (frame, mut border) in frames.iter_mut() {
+        border.0 = colors.team(frame.0);
+    }
+}
@@ -0,0 +1,526 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::AiController,
+    animation::FighterAnimations,
+    character::{
+        facing, AnimationState, Character, CharacterState, Opponent, Player, Team, CHARGE_DURATION,
+        ELBOW_DURATION, JUMP_VELOCITY, KICK_DURATION, PUNCH_DURATION,
+    },
+    data::{read_ron_file, write_ron_file},
+    moves::{MoveId, MovePhase},
+    roster::CharacterDef,
+    settings::Settings,
+};
+
+pub const LEFT_KEY: KeyCode = KeyCode::A;
+pub const RIGHT_KEY: KeyCode = KeyCode::D;
+pub const PUNCH_KEY: KeyCode = KeyCode::P;
+pub const KICK_KEY: KeyCode = KeyCode::K;
+pub const BLOCK_KEY: KeyCode = KeyCode::L;
+pub const CROUCH_KEY: KeyCode = KeyCode::S;
+pub const JUMP_KEY: KeyCode = KeyCode::W;
+// Swaps in the partner in tag team matches.
+pub const TAG_KEY: KeyCode = KeyCode::I;
+// Throws when close enough, and breaks out of the opponent's throw.
+pub const THROW_KEY: KeyCode = KeyCode::J;
+
+// The keys one side fights with. Player one has the keys above; when two
+// people share the keyboard, player two's sit under the right hand.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerKeys {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub punch: KeyCode,
+    pub kick: KeyCode,
+    pub block: KeyCode,
+    pub crouch: KeyCode,
+    pub jump: KeyCode,
+    pub tag: KeyCode,
+    pub throw: KeyCode,
+}
+
+pub const PLAYER_ONE_KEYS: PlayerKeys = PlayerKeys {
+    left: LEFT_KEY,
+    right: RIGHT_KEY,
+    punch: PUNCH_KEY,
+    kick: KICK_KEY,
+    block: BLOCK_KEY,
+    crouch: CROUCH_KEY,
+    jump: JUMP_KEY,
+    tag: TAG_KEY,
+    throw: THROW_KEY,
+};
+
+pub const PLAYER_TWO_KEYS: PlayerKeys = PlayerKeys {
+    left: KeyCode::Left,
+    right: KeyCode::Right,
+    punch: KeyCode::Numpad1,
+    kick: KeyCode::Numpad2,
+    block: KeyCode::Numpad3,
+    crouch: KeyCode::Down,
+    jump: KeyCode::Up,
+    tag: KeyCode::Numpad4,
+    throw: KeyCode::Numpad5,
+};
+
+pub fn player_keys(team: Team) -> &'static PlayerKeys {
+    match team {
+        Team::One => &PLAYER_ONE_KEYS,
+        Team::Two => &PLAYER_TWO_KEYS,
+    }
+}
+
+// The buttons a side fights with on a gamepad, which works alongside their
+// keys. The d-pad or the left stick walks and crouches.
+#[derive(Clone, Copy, Debug)]
+pub struct PadButtons {
+    pub punch: GamepadButtonType,
+    pub kick: GamepadButtonType,
+    pub block: GamepadButtonType,
+    pub jump: GamepadButtonType,
+    pub tag: GamepadButtonType,
+    pub throw: GamepadButtonType,
+}
+
+pub const PAD_BUTTONS: PadButtons = PadButtons {
+    punch: GamepadButtonType::West,
+    kick: GamepadButtonType::North,
+    block: GamepadButtonType::RightTrigger,
+    jump: GamepadButtonType::South,
+    tag: GamepadButtonType::RightTrigger2,
+    throw: GamepadButtonType::LeftTrigger,
+};
+
+// How far the left stick has to be pushed to count as a direction.
+const STICK_DEAD_ZONE: f32 = 0.5;
+
+pub const BINDINGS_PATH: &str = "bindings.ron";
+
+// Simple controls: every attack comes out of the punch key as an auto combo,
+// and the launcher sits on the kick key as a shortcut.
+const AUTO_COMBO: [MoveId; 3] = [MoveId::Punch, MoveId::Punch, MoveId::Kick];
+// Seconds after an attack ends before the auto combo starts over.
+const AUTO_COMBO_RESET: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ControlScheme {
+    #[default]
+    Standard,
+    Simple,
+}
+
+impl ControlScheme {
+    pub fn next(self) -> Self {
+        match self {
+            ControlScheme::Standard => ControlScheme::Simple,
+            ControlScheme::Simple => ControlScheme::Standard,
+        }
+    }
+}
+
+// Toggle latches block or crouch on with one press and off with the next,
+// for players who can't hold a key down.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ButtonMode {
+    #[default]
+    Hold,
+    Toggle,
+}
+
+impl ButtonMode {
+    pub fn next(self) -> Self {
+        match self {
+            ButtonMode::Hold => ButtonMode::Toggle,
+            ButtonMode::Toggle => ButtonMode::Hold,
+        }
+    }
+
+    fn read(self, keys: &Input<KeyCode>, key: KeyCode, latched: bool) -> bool {
+        match self {
+            ButtonMode::Hold => keys.pressed(key),
+            ButtonMode::Toggle => latched != keys.just_pressed(key),
+        }
+    }
+
+    // Folds a second device into what `read` made of the first: held adds to
+    // a hold, and a fresh press flips a toggle.
+    fn add(self, read: bool, held: bool, just_pressed: bool) -> bool {
+        match self {
+            ButtonMode::Hold => read || held,
+            ButtonMode::Toggle => read != just_pressed,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(default)]
+pub struct PlayerBindings {
+    pub block: ButtonMode,
+    pub crouch: ButtonMode,
+}
+
+// Per-player control config, persisted to BINDINGS_PATH. Indexed by team.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Bindings {
+    pub players: [PlayerBindings; 2],
+}
+
+impl Bindings {
+    pub fn load() -> Self {
+        read_ron_file(BINDINGS_PATH)
+    }
+
+    pub fn save(&self) {
+        write_ron_file(BINDINGS_PATH, self);
+    }
+
+    pub fn player(&self, team: Team) -> &PlayerBindings {
+        match team {
+            Team::One => &self.players[0],
+            Team::Two => &self.players[1],
+        }
+    }
+
+    pub fn player_mut(&mut self, team: Team) -> &mut PlayerBindings {
+        match team {
+            Team::One => &mut self.players[0],
+            Team::Two => &mut self.players[1],
+        }
+    }
+}
+
+// Held directions, block and crouch are level-triggered, attack, jump, tag
+// and throw buttons are latched until the next simulation tick consumes them so presses
+// between ticks aren't lost. Directions are in world space; facing is applied
+// by the simulation.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ControlInput {
+    pub left: bool,
+    pub right: bool,
+    pub punch: bool,
+    pub kick: bool,
+    pub block: bool,
+    pub crouch: bool,
+    pub jump: bool,
+    pub tag: bool,
+    pub throw: bool,
+}
+
+pub fn read_keyboard_input(
+    keys: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+    bindings: Res<Bindings>,
+    mut players: Query<(&mut ControlInput, &Team), (With<Player>, Without<AiController>)>,
+) {
+    for (mut input, team) in players.iter_mut() {
+        let bindings = bindings.player(*team);
+        let player = player_keys(*team);
+        input.left = keys.pressed(player.left);
+        input.right = keys.pressed(player.right);
+        input.block = bindings.block.read(&keys, player.block, input.block);
+        input.crouch = bindings.crouch.read(&keys, player.crouch, input.crouch);
+        input.jump |= keys.just_pressed(player.jump);
+        input.tag |= keys.just_pressed(player.tag);
+        input.throw |= keys.just_pressed(player.throw);
+        // Simple controls pick the attack themselves.
+        if settings.controls == ControlScheme::Standard {
+            input.punch |= keys.just_pressed(player.punch);
+            input.kick |= keys.just_pressed(player.kick);
+        }
+    }
+}
+
+// Where the player is in the auto combo, and the attack waiting for the
+// current one to finish.
+#[derive(Component, Default, Clone)]
+pub struct SimpleControls {
+    step: usize,
+    queued: Option<MoveId>,
+    // Seconds since the last attack ended.
+    idle: f32,
+}
+
+fn latch(input: &mut ControlInput, move_id: MoveId) {
+    match move_id {
+        MoveId::Punch | MoveId::Elbow => input.punch = true,
+        MoveId::Kick => input.kick = true,
+        MoveId::ShoulderCharge => {
+            input.punch = true;
+            input.kick = true;
+        }
+    }
+}
+
+// Maps the simple scheme onto the same ControlInput the standard keys fill.
+// Presses made mid-attack are held and latched again every frame until the
+// move actually starts, so mashing one key strings the combo together.
+pub fn read_simple_input(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut players: Query<
+        (&mut ControlInput, &mut SimpleControls, &CharacterState, &Opponent, &Team),
+        (With<Player>, Without<AiController>),
+    >,
+    fighters: Query<&CharacterState>,
+) {
+    if settings.controls != ControlScheme::Simple {
+        return;
+    }
+    for (mut input, mut simple, state, opponent, team) in players.iter_mut() {
+        let player = player_keys(*team);
+        let attacking = MoveId::from_state(state.player_state);
+        match (attacking, state.move_phase) {
+            (Some(move_id), Some(MovePhase::Startup)) => {
+                simple.idle = 0.0;
+                // The queued move has come out; the next press continues.
+                if simple.queued == Some(move_id) {
+                    simple.queued = None;
+                    simple.step = (simple.step + 1) % AUTO_COMBO.len();
+                }
+            }
+            (Some(_), Some(_)) => simple.idle = 0.0,
+            _ => {
+                simple.idle += time.delta_seconds();
+                if simple.idle > AUTO_COMBO_RESET {
+                    simple.step = 0;
+                }
+            }
+        }
+        // Stunned or knocked into the air: drop whatever was waiting.
+        if (state.current_animation_timer.is_some() && attacking.is_none()) || state.airborne {
+            simple.queued = None;
+        }
+
+        let opponent_juggled = fighters
+            .get(opponent.0)
+            .is_ok_and(|other| other.juggle.is_some());
+        let pressed_in_startup = state.move_phase == Some(MovePhase::Startup);
+        if keys.just_pressed(player.kick) && !pressed_in_startup {
+            simple.queued = Some(MoveId::Kick);
+            // The launcher finishes the combo, so the next press starts over.
+            simple.step = AUTO_COMBO.len() - 1;
+        } else if keys.just_pressed(player.punch) && !pressed_in_startup {
+            // Quick hits keep a juggled opponent in the air.
+            simple.queued = Some(if opponent_juggled {
+                MoveId::Punch
+            } else {
+                AUTO_COMBO[simple.step]
+            });
+        }
+        if let Some(move_id) = simple.queued {
+            latch(&mut input, move_id);
+        }
+    }
+}
+
+// The first gamepad connected plays for player one and the second for player
+// two.
+pub fn team_gamepad(gamepads: &Gamepads, team: Team) -> Option<Gamepad> {
+    let mut pads: Vec<Gamepad> = gamepads.iter().collect();
+    pads.sort_by_key(|pad| pad.id);
+    pads.get(match team {
+        Team::One => 0,
+        Team::Two => 1,
+    })
+    .copied()
+}
+
+// Runs after the keyboard and adds to what it read, so either works. Simple
+// controls' auto combo stays on the keys. `sticks_down` holds the pads whose
+// stick was pushed down last frame, so pushing it down can toggle crouch the
+// way pressing down on the d-pad does.
+pub fn read_gamepad_input(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    settings: Res<Settings>,
+    bindings: Res<Bindings>,
+    mut sticks_down: Local<HashSet<Gamepad>>,
+    mut players: Query<(&mut ControlInput, &Team), (With<Player>, Without<AiController>)>,
+) {
+    for (mut input, team) in players.iter_mut() {
+        let Some(gamepad) = team_gamepad(&gamepads, *team) else {
+            continue;
+        };
+        let bindings = bindings.player(*team);
+        let button = |kind| GamepadButton::new(gamepad, kind);
+        let stick = |kind| axes.get(GamepadAxis::new(gamepad, kind)).unwrap_or(0.0);
+        let (x, y) = (
+            stick(GamepadAxisType::LeftStickX),
+            stick(GamepadAxisType::LeftStickY),
+        );
+        input.left |= buttons.pressed(button(GamepadButtonType::DPadLeft)) || x < -STICK_DEAD_ZONE;
+        input.right |= buttons.pressed(button(GamepadButtonType::DPadRight)) || x > STICK_DEAD_ZONE;
+        let stick_down = y < -STICK_DEAD_ZONE;
+        let stick_pushed = if stick_down {
+            sticks_down.insert(gamepad)
+        } else {
+            sticks_down.remove(&gamepad);
+            false
+        };
+        let down = button(GamepadButtonType::DPadDown);
+        input.crouch = bindings.crouch.add(
+            input.crouch,
+            buttons.pressed(down) || stick_down,
+            buttons.just_pressed(down) || stick_pushed,
+        );
+        let block = button(PAD_BUTTONS.block);
+        input.block = bindings.block.add(
+            input.block,
+            buttons.pressed(block),
+            buttons.just_pressed(block),
+        );
+        input.jump |= buttons.just_pressed(button(PAD_BUTTONS.jump));
+        input.tag |= buttons.just_pressed(button(PAD_BUTTONS.tag));
+        input.throw |= buttons.just_pressed(button(PAD_BUTTONS.throw));
+        if settings.controls == ControlScheme::Standard {
+            input.punch |= buttons.just_pressed(button(PAD_BUTTONS.punch));
+            input.kick |= buttons.just_pressed(button(PAD_BUTTONS.kick));
+        }
+    }
+}
+
+// Which way a fighter walks for the directions held.
+fn stride(forward: bool, back: bool) -> AnimationState {
+    if forward && !back {
+        AnimationState::Running
+    } else if back && !forward {
+        AnimationState::RunningBackwards
+    } else {
+        AnimationState::Idle
+    }
+}
+
+// Seconds a move holds its fighter: the length of its clip at the speed the
+// character's animation graph plays it, or `fallback` without the clip.
+fn move_lockout(
+    state: AnimationState,
+    fallback: f32,
+    fighter: Option<(&Character, &FighterAnimations)>,
+    characters: &Assets<CharacterDef>,
+    clips: Option<&Assets<AnimationClip>>,
+) -> f32 {
+    fighter
+        .zip(clips)
+        .and_then(|((character, animations), clips)| {
+            characters
+                .get(&character.0)?
+                .animation_graph
+                .clip_duration(state, animations, clips)
+        })
+        .unwrap_or(fallback)
+}
+
+// The state a move plays in, and how long it holds its fighter without a clip.
+fn move_state(move_id: MoveId) -> (AnimationState, f32) {
+    match move_id {
+        MoveId::Punch => (AnimationState::Punching, PUNCH_DURATION),
+        MoveId::Kick => (AnimationState::Kicking, KICK_DURATION),
+        MoveId::Elbow => (AnimationState::Elbowing, ELBOW_DURATION),
+        MoveId::ShoulderCharge => (AnimationState::Charging, CHARGE_DURATION),
+    }
+}
+
+pub fn process_input(
+    time: Res<Time>,
+    characters: Res<Assets<CharacterDef>>,
+    clips: Option<Res<Assets<AnimationClip>>>,
+    mut players: Query<(
+        &mut CharacterState,
+        &mut ControlInput,
+        &Transform,
+        Option<&Opponent>,
+        Option<(&Character, &FighterAnimations)>,
+    )>,
+    positions: Query<&Transform>,
+) {
+    for (mut player, mut input, transform, opponent, fighter) in players.iter_mut() {
+        let mut punch = std::mem::take(&mut input.punch);
+        let mut kick = std::mem::take(&mut input.kick);
+        let jump = std::mem::take(&mut input.jump);
+
+        if player.airborne {
+            continue;
+        }
+
+        let (forward, back) = if facing(transform) > 0.0 {
+            (input.right, input.left)
+        } else {
+            (input.left, input.right)
+        };
+        player.stride = stride(forward, back);
+
+        if player.current_animation_timer.is_some() {
+            if player
+                .current_animation_timer
+                .as_mut()
+                .unwrap()
+                .tick(time.delta())
+                .finished()
+            {
+                player.current_animation_timer = None;
+                player.move_phase = None;
+                player.downed = false;
+            } else {
+                player.refresh_move_phase();
+                let Some(cancel) = [(MoveId::Punch, punch), (MoveId::Kick, kick)]
+                    .into_iter()
+                    .find(|&(move_id, pressed)| pressed && player.can_cancel_into(move_id))
+                    .map(|(move_id, _)| move_id)
+                else {
+                    continue;
+                };
+                // Cut short into the cancel as if it had been pressed from
+                // neutral, ignoring anything else pressed with it.
+                punch = cancel == MoveId::Punch;
+                kick = cancel == MoveId::Kick;
+                player.current_animation_timer = None;
+            }
+        }
+        player.armor_spent = false;
+        player.move_connected = false;
+        let lockout = |state, fallback| {
+            let seconds = move_lockout(state, fallback, fighter, &characters, clips.as_deref());
+            Some(Timer::from_seconds(seconds, TimerMode::Once))
+        };
+        // Moves with a point-blank version do that instead with the opponent
+        // close enough.
+        let point_blank = |move_id: MoveId| {
+            let point_blank = move_id.data().point_blank?;
+            let opponent = positions.get(opponent?.0).ok()?;
+            let distance = (opponent.translation - transform.translation).length();
+            (distance <= point_blank.range).then_some(point_blank.move_id)
+        };
+        let pressed = if punch && kick {
+            Some(MoveId::ShoulderCharge)
+        } else if punch {
+            Some(MoveId::Punch)
+        } else if kick {
+            Some(MoveId::Kick)
+        } else {
+            None
+        };
+        let new_state = if let Some(move_id) = pressed {
+            player.begin_attack();
+            let (state, fallback) = move_state(point_blank(move_id).unwrap_or(move_id));
+            player.current_animation_timer = lockout(state, fallback);
+            state
+        } else if jump {
+            player.airborne = true;
+            player.vertical_velocity = JUMP_VELOCITY;
+            AnimationState::Jumping
+        } else if input.block {
+            AnimationState::Blocking
+        } else if input.crouch {
+            AnimationState::Crouching
+        } else {
+            player.stride
+        };
+        player.update_player_state(new_state);
+        player.refresh_move_phase();
+    }
+}
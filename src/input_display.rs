@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    character::{facing, Player},
+    input::ControlInput,
+};
+
+// Rows kept on screen; older inputs scroll off the bottom.
+const HISTORY_ROWS: usize = 20;
+// Counts stop here so a long hold doesn't widen the column.
+const MAX_FRAMES: u32 = 999;
+
+// One tick's input as the player sees it: direction relative to the way the
+// fighter faces, plus the buttons.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct InputFrame {
+    // Numpad notation: 6 is forward, 2 is down, 5 is neutral.
+    pub direction: u8,
+    pub punch: bool,
+    pub kick: bool,
+    pub block: bool,
+}
+
+impl InputFrame {
+    fn new(input: &ControlInput, facing_right: bool) -> Self {
+        let (forward, back) = if facing_right {
+            (input.right, input.left)
+        } else {
+            (input.left, input.right)
+        };
+        let column = match (back, forward) {
+            (true, false) => 1,
+            (false, true) => 3,
+            _ => 2,
+        };
+        let row = if input.jump {
+            2
+        } else if input.crouch {
+            0
+        } else {
+            1
+        };
+        InputFrame {
+            direction: row * 3 + column,
+            punch: input.punch,
+            kick: input.kick,
+            block: input.block,
+        }
+    }
+
+    fn label(&self) -> String {
+        let mut label = self.direction.to_string();
+        for (pressed, button) in [(self.punch, " P"), (self.kick, " K"), (self.block, " B")] {
+            if pressed {
+                label.push_str(button);
+            }
+        }
+        label
+    }
+}
+
+// Newest first. `frames` is how many ticks the input was held before the
+// next one, so the gaps between presses can be read straight off.
+#[derive(Resource, Default)]
+pub struct InputHistory {
+    pub entries: VecDeque<(InputFrame, u32)>,
+}
+
+#[derive(Component)]
+pub struct InputDisplay;
+
+// Samples the player's input each tick, before the simulation consumes the
+// latched buttons.
+pub fn record_input_history(
+    mut history: ResMut<InputHistory>,
+    players: Query<(&ControlInput, &Transform), With<Player>>,
+) {
+    let Ok((input, transform)) = players.get_single() else {
+        return;
+    };
+    let frame = InputFrame::new(input, facing(transform) > 0.0);
+    match history.entries.front_mut() {
+        Some((last, frames)) if *last == frame => *frames = (*frames + 1).min(MAX_FRAMES),
+        _ => {
+            history.entries.push_front((frame, 1));
+            history.entries.truncate(HISTORY_ROWS);
+        }
+    }
+}
+
+pub fn setup_input_display(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.4)),
+        InputDisplay,
+    ));
+}
+
+pub fn update_input_display(
+    history: Res<InputHistory>,
+    mut displays: Query<&mut Text, With<InputDisplay>>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+    let lines: Vec<String> = history
+        .entries
+        .iter()
+        .map(|(frame, frames)| format!("{frames:>3}  {}", frame.label()))
+        .collect();
+    for mut text in displays.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
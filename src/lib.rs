@@ -0,0 +1,925 @@
+use bevy::{
+    animation::animation_player,
+    audio::AddAudioSource,
+    diagnostic::FrameTimeDiagnosticsPlugin,
+    input::InputSystem,
+    prelude::*,
+    transform::TransformSystem,
+    ui::UiSystem,
+    window::close_on_esc,
+};
+use bevy_rapier3d::prelude::*;
+
+pub mod ai;
+pub mod animation;
+pub mod asset_cache;
+pub mod attract;
+pub mod banners;
+pub mod body_layers;
+pub mod captions;
+pub mod camera;
+pub mod camera_rig;
+pub mod capture;
+pub mod character;
+pub mod character_select;
+pub mod clash;
+pub mod cloth;
+pub mod combat;
+pub mod comeback;
+pub mod crowd;
+pub mod damage_numbers;
+pub mod data;
+pub mod debug;
+pub mod decals;
+pub mod depth_of_field;
+pub mod dizzy;
+pub mod environment;
+pub mod foot_ik;
+pub mod gauntlet;
+pub mod hazards;
+pub mod headless;
+pub mod hud;
+pub mod input;
+pub mod input_display;
+pub mod locale;
+pub mod match_options;
+pub mod menu;
+pub mod moves;
+pub mod options;
+pub mod palette;
+pub mod pause;
+#[cfg(feature = "particles")]
+pub mod particle_pool;
+pub mod photo;
+pub mod post_process;
+pub mod profile;
+pub mod replay;
+pub mod rng;
+pub mod rim_light;
+pub mod roster;
+pub mod round;
+pub mod rumble;
+pub mod settings;
+pub mod sfx;
+pub mod skins;
+pub mod snapshot;
+pub mod spectators;
+pub mod stage;
+pub mod stress;
+pub mod tag_team;
+pub mod throws;
+pub mod toasts;
+pub mod training;
+pub mod transition;
+pub mod trials;
+pub mod tournament;
+pub mod tuning;
+pub mod tutorial;
+pub mod versus;
+pub mod voice;
+pub mod weather;
+
+use ai::*;
+use animation::*;
+use asset_cache::*;
+use attract::*;
+use banners::*;
+use body_layers::*;
+use captions::*;
+use camera::*;
+use camera_rig::*;
+use capture::*;
+use character::*;
+use character_select::*;
+use clash::*;
+use cloth::*;
+use combat::*;
+use comeback::*;
+use crowd::*;
+use damage_numbers::*;
+use data::*;
+use debug::*;
+use decals::*;
+use depth_of_field::*;
+use dizzy::*;
+use environment::*;
+use foot_ik::*;
+use gauntlet::*;
+use hazards::*;
+use hud::*;
+use input::*;
+use input_display::*;
+use locale::*;
+use match_options::*;
+use menu::*;
+use options::*;
+use pause::*;
+use photo::*;
+use post_process::*;
+use profile::*;
+use replay::*;
+use rng::*;
+use rim_light::*;
+use roster::*;
+use round::*;
+use rumble::*;
+use settings::*;
+use sfx::*;
+use skins::*;
+use snapshot::*;
+use spectators::*;
+use stage::*;
+use stress::*;
+use tag_team::*;
+use throws::*;
+use toasts::*;
+use tournament::*;
+use training::*;
+use transition::*;
+use trials::*;
+use tuning::*;
+use tutorial::*;
+use versus::*;
+use voice::*;
+use weather::*;
+
+pub const SIMULATION_HZ: f64 = 60.0;
+
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AppState {
+    #[default]
+    Loading,
+    // Tournament entry and the bracket between matches.
+    Bracket,
+    // The next gauntlet opponent and their modifier, or how the run ended.
+    Challenger,
+    CharacterSelect,
+    MatchOptions,
+    Fight,
+    Finished,
+}
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    #[default]
+    Versus,
+    Training,
+    // Training against the dummy with a combo to land.
+    Trials,
+    // Objectives one at a time against a scripted opponent.
+    Tutorial,
+    // AI against AI, started when the menu sits idle.
+    Demo,
+    // Many AI pairs fighting side by side, for profiling.
+    Stress,
+    // Local players knock each other out of a bracket.
+    Tournament,
+    // Two fighters a side, swapped in and out with a tag.
+    TagTeam,
+    // One fighter against AI opponents with modifiers until they lose.
+    Gauntlet,
+}
+
+impl GameMode {
+    // Fought over rounds to a winner, rather than open-ended practice.
+    pub fn plays_rounds(self) -> bool {
+        matches!(
+            self,
+            GameMode::Versus
+                | GameMode::Demo
+                | GameMode::Tournament
+                | GameMode::TagTeam
+                | GameMode::Gauntlet
+        )
+    }
+
+    // Practice modes put the training dummy on the other side.
+    pub fn fights_dummy(self) -> bool {
+        matches!(self, GameMode::Training | GameMode::Trials | GameMode::Tutorial)
+    }
+
+    // Where the game goes once everything has loaded.
+    pub fn first_screen(self) -> AppState {
+        match self {
+            GameMode::Tournament => AppState::Bracket,
+            _ => AppState::CharacterSelect,
+        }
+    }
+}
+
+pub fn plays_rounds(mode: Res<GameMode>) -> bool {
+    mode.plays_rounds()
+}
+
+// Modes that wait on the result screen for the players to move on, rather
+// than looping back on their own like the attract demo.
+pub fn finishes_to_menu(mode: Res<GameMode>) -> bool {
+    matches!(
+        *mode,
+        GameMode::Versus | GameMode::Tournament | GameMode::TagTeam | GameMode::Gauntlet
+    )
+}
+
+// The order each frame's Update systems run in. By the time Update starts,
+// PreUpdate has turned devices into fighter input and the fixed tick has
+// stepped the simulation, so within the frame:
+//
+// Input: menu, debug and mode keys that change what the frame should do.
+// Combat: everything that reacts to the hits, clashes and knockouts the fixed
+// tick worked out.
+// Movement: things that follow where the fighters ended up.
+// Animation: clips and attack sounds for the fighters' new states.
+// Presentation: camera, lights, effects, audio mixing and UI, which read
+// everything above and never feed back into it this frame.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    Input,
+    Combat,
+    Movement,
+    Animation,
+    Presentation,
+}
+
+// Number of fixed simulation ticks run since startup.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SimulationTick(pub u32);
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+// Everything that decides the outcome of a fight. Runs on a fixed tick and
+// never touches windows, rendering or audio so it can be driven headless.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        add_physics(app);
+        app.add_state::<AppState>()
+            .add_plugins(RonAssetPlugin::<RosterDef>::new(&["roster.ron"]))
+            .add_plugins(RonAssetPlugin::<CharacterDef>::new(&["character.ron"]))
+            .add_plugins(RonAssetPlugin::<AiProfile>::new(&["ai.ron"]))
+            .add_plugins(RonAssetPlugin::<Tuning>::new(&["tuning.ron"]))
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .init_resource::<SimulationTick>()
+            .init_resource::<MatchSetup>()
+            .init_resource::<GameMode>()
+            .init_resource::<DummySettings>()
+            .init_resource::<MatchRules>()
+            .init_resource::<GameRng>()
+            .init_resource::<StressTest>()
+            .init_resource::<AssetCache>()
+            .init_resource::<Round>()
+            .init_resource::<Gauntlet>()
+            .init_resource::<Snapshots>()
+            .init_resource::<RingOut>()
+            .add_event::<HitLanded>()
+            .add_event::<KnockedDown>()
+            .add_event::<WallHit>()
+            .add_event::<ThrowLanded>()
+            .add_event::<ThrowTeched>()
+            .add_event::<Dizzied>()
+            .add_event::<Clash>()
+            .add_event::<ComebackTriggered>()
+            .add_event::<RoundStarted>()
+            .add_event::<FightStarted>()
+            .add_event::<RoundEnded>()
+            .add_event::<FightEnded>()
+            .configure_sets(
+                Update,
+                (
+                    GameSet::Input,
+                    GameSet::Combat,
+                    GameSet::Movement,
+                    GameSet::Animation,
+                    GameSet::Presentation,
+                )
+                    .chain(),
+            )
+            .add_systems(Startup, (load_roster, load_tuning, spawn_stage_bounds))
+            .add_systems(Update, wait_for_roster.run_if(in_state(AppState::Loading)))
+            .add_systems(OnExit(AppState::Loading), cache_ai_profiles)
+            .add_systems(
+                OnEnter(AppState::Fight),
+                (spawn_fighters, start_match, clear_snapshots),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    take_snapshot,
+                    (
+                        run_ai,
+                        drive_dummy,
+                        update_dizzy,
+                        resolve_throws,
+                        start_throws,
+                        process_input,
+                    )
+                        .chain()
+                        .run_if(round_in_progress),
+                    process_movement,
+                    move_hazards,
+                    // Physics steps here.
+                    track_limb_velocities,
+                    (resolve_hits, build_stun, brace_dummy).chain(),
+                    hazard_hits,
+                    (check_knockout, check_ring_out, trigger_comeback)
+                        .chain()
+                        .run_if(plays_rounds),
+                    regenerate_health.run_if(round_in_progress),
+                    advance_round.run_if(plays_rounds),
+                    advance_tick,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Fight)),
+            )
+            .add_systems(
+                FixedUpdate,
+                tag_fighters
+                    .before(process_input)
+                    .run_if(round_in_progress)
+                    .run_if(in_state(AppState::Fight))
+                    .run_if(resource_equals(GameMode::TagTeam)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (bank_red_health, tag_in_on_knockout)
+                    .chain()
+                    .after(resolve_hits)
+                    .before(check_knockout)
+                    .run_if(in_state(AppState::Fight))
+                    .run_if(resource_equals(GameMode::TagTeam)),
+            )
+            .add_systems(
+                Update,
+                (reset_partners, reskin_tagged_fighters)
+                    .in_set(GameSet::Combat)
+                    .run_if(resource_equals(GameMode::TagTeam)),
+            )
+            .add_systems(Update, calculate_collision_points.in_set(GameSet::Combat));
+    }
+}
+
+// Rapier steps once a simulation tick, by exactly one tick, between the
+// fighters moving and their hits being worked out. Transforms are propagated
+// as part of it, so the limbs are wherever the fighters ended up this tick.
+fn add_physics(app: &mut App) {
+    app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false))
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: (1.0 / SIMULATION_HZ) as f32,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .configure_sets(
+            FixedUpdate,
+            (
+                PhysicsSet::SyncBackend,
+                PhysicsSet::SyncBackendFlush,
+                PhysicsSet::StepSimulation,
+                PhysicsSet::Writeback,
+            )
+                .chain()
+                .after(move_hazards)
+                .before(track_limb_velocities),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackend)
+                    .in_set(PhysicsSet::SyncBackend),
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackendFlush)
+                    .in_set(PhysicsSet::SyncBackendFlush),
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::StepSimulation)
+                    .in_set(PhysicsSet::StepSimulation),
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::Writeback)
+                    .in_set(PhysicsSet::Writeback),
+            ),
+        );
+}
+
+pub fn game_app() -> App {
+    let settings = Settings::load();
+    let mut app = App::new();
+    app
+        /*/.insert_resource(WindowDescriptor {
+            title: "Bob Ross".to_string(),
+            width: 1024.,
+            height: 512.,
+            ..default()
+        })*/
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(settings.display.window()),
+            ..default()
+        }))
+        .add_plugins(RapierDebugRenderPlugin::default())
+        .add_plugins(FrameTimeDiagnosticsPlugin)
+        .add_plugins(SimulationPlugin)
+        .add_plugins(PostProcessPlugin)
+        .add_plugins(DepthOfFieldPlugin)
+        .add_plugins(ReadbackPlugin)
+        .add_plugins(MaterialPlugin::<FighterMaterial>::default())
+        .add_audio_source::<Heartbeat>()
+        .add_audio_source::<MenuBlip>()
+        .add_audio_source::<Whoosh>()
+        .add_plugins(RonAssetPlugin::<StageDef>::new(&["stage.ron"]))
+        .add_plugins(RonAssetPlugin::<LocaleTable>::new(&["locale.ron"]))
+        .add_plugins(RonAssetPlugin::<TrialList>::new(&["trials.ron"]))
+        .init_resource::<HitboxView>()
+        .init_resource::<ClipBuffer>()
+        .init_resource::<PhotoMode>()
+        .init_resource::<OptionsMenu>()
+        .init_resource::<PauseMenu>()
+        .init_resource::<CharacterSelect>()
+        .init_resource::<SpotlightMoment>()
+        .init_resource::<SunConfig>()
+        .init_resource::<Excitement>()
+        .init_resource::<ClashSlowMotion>()
+        .init_resource::<ArcadeScore>()
+        .init_resource::<Rivalry>()
+        .init_resource::<Tournament>()
+        .init_resource::<Replay>()
+        .init_resource::<InputHistory>()
+        .init_resource::<ComboTrial>()
+        .init_resource::<Tutorial>()
+        .init_resource::<Attract>()
+        .add_event::<ObjectiveComplete>()
+        .add_event::<ResetPositions>()
+        .add_event::<RewindTraining>()
+        .insert_resource(ScreenTransition::covered())
+        .add_event::<TransitionCovered>()
+        .add_event::<MenuEvent>()
+        .add_event::<PlaySfx>()
+        .add_event::<PlaySfx<Whoosh>>()
+        .add_event::<Barked>()
+        .add_event::<ShowToast>()
+        .insert_resource(settings)
+        .insert_resource(Profile::load())
+        .insert_resource(Bindings::load())
+        .add_systems(
+            Startup,
+            (
+                setup_camera,
+                load_stage,
+                setup_options_panel,
+                setup_transition_overlay,
+                setup_frame_data_overlay,
+                setup_heartbeat,
+                setup_menu_sounds,
+                setup_whooshes,
+                load_locales,
+                setup_captions,
+                load_trials,
+            ),
+        )
+        .add_systems(PreUpdate, track_locale_tables)
+        .add_systems(PreUpdate, navigate_menus.after(UiSystem::Focus))
+        .add_systems(
+            PreUpdate,
+            (read_keyboard_input, read_gamepad_input, read_simple_input)
+                .chain()
+                .after(InputSystem)
+                .run_if(photo_mode_inactive),
+        )
+        .add_systems(
+            Update,
+            (
+                (toggle_borderless, options_menu_keys).chain(),
+                toggle_hitbox_view,
+                (time_scale_keys, frame_step)
+                    .chain()
+                    .run_if(photo_mode_inactive),
+                close_on_esc,
+            )
+                .in_set(GameSet::Input),
+        )
+        .add_systems(
+            Update,
+            (
+                clash_slow_motion,
+                (score_rounds, record_fight_result)
+                    .chain()
+                    .run_if(live_match),
+            )
+                .in_set(GameSet::Combat),
+        )
+        .add_systems(
+            Update,
+            (apply_sun_config, spawn_blob_shadows, update_blob_shadows).in_set(GameSet::Movement),
+        )
+        .add_systems(
+            Update,
+            (
+                attach_fighter_models,
+                setup_scene_once_loaded,
+                find_root_bones,
+                find_leg_bones,
+                find_look_bones,
+                find_lower_body_bones,
+                attach_cloth,
+                start_spectator_animations,
+                cheer_spectators,
+                process_animation,
+                start_head_snaps,
+            )
+                .in_set(GameSet::Animation),
+        )
+        .add_systems(
+            Update,
+            (
+                highlight_menu_items,
+                highlight_clickables,
+                show_cursor,
+                (apply_skins, apply_outline_palette).chain(),
+                rumble_on_hits,
+                fling_cloth,
+                (start_hit_flash, apply_hit_flash).chain(),
+                drive_comeback_effects.after(apply_impact_pulse),
+                (start_ko_shot, finish_ko_shot, update_camera_rig)
+                    .chain()
+                    .run_if(photo_mode_inactive),
+                (
+                    toggle_photo_mode,
+                    fly_photo_camera,
+                    focus_photo_camera,
+                    photo_mode_keys,
+                    develop_photos,
+                    mirror_fight_camera.after(update_camera_rig),
+                )
+                    .chain(),
+                (draw_hitboxes, update_frame_data_overlay).chain(),
+                (
+                    update_options_panel,
+                    apply_display_settings,
+                    apply_graphics_settings,
+                )
+                    .chain(),
+            )
+                .in_set(GameSet::Presentation),
+        )
+        .add_systems(
+            OnExit(AppState::Loading),
+            (cache_fighter_assets, cache_announcer_calls),
+        )
+        .add_systems(OnEnter(AppState::CharacterSelect), setup_character_select)
+        .add_systems(
+            Update,
+            (
+                (character_select_keys, start_attract_demo).in_set(GameSet::Input),
+                (update_preview_model, spin_preview_model, update_select_panel)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            )
+                .run_if(in_state(AppState::CharacterSelect)),
+        )
+        .add_systems(OnExit(AppState::CharacterSelect), close_character_select)
+        .add_systems(
+            OnEnter(AppState::Fight),
+            setup_demo_overlay.run_if(resource_equals(GameMode::Demo)),
+        )
+        .add_systems(
+            Update,
+            leave_attract_demo
+                .after(drive_transition)
+                .in_set(GameSet::Presentation)
+                .run_if(resource_equals(GameMode::Demo)),
+        )
+        .add_systems(
+            OnEnter(AppState::Fight),
+            count_rematches
+                .run_if(resource_equals(GameMode::Versus))
+                .run_if(live_match),
+        )
+        .add_systems(
+            OnEnter(AppState::Finished),
+            show_rematch_prompt.run_if(finishes_to_menu),
+        )
+        .add_systems(
+            Update,
+            record_streaks
+                .in_set(GameSet::Combat)
+                .run_if(resource_equals(GameMode::Versus))
+                .run_if(live_match),
+        )
+        .add_systems(
+            Update,
+            leave_finished_match
+                .after(drive_transition)
+                .in_set(GameSet::Presentation)
+                .run_if(in_state(AppState::Finished))
+                .run_if(photo_mode_inactive)
+                .run_if(finishes_to_menu),
+        )
+        .add_systems(OnEnter(AppState::Bracket), setup_bracket_screen)
+        .add_systems(
+            Update,
+            (
+                tournament_keys.in_set(GameSet::Input),
+                update_bracket_screen.in_set(GameSet::Presentation),
+            )
+                .run_if(in_state(AppState::Bracket)),
+        )
+        .add_systems(OnExit(AppState::Bracket), close_bracket_screen)
+        .add_systems(OnEnter(AppState::Challenger), setup_challenger_screen)
+        .add_systems(
+            Update,
+            challenger_keys
+                .in_set(GameSet::Input)
+                .run_if(in_state(AppState::Challenger)),
+        )
+        .add_systems(OnExit(AppState::Challenger), close_challenger_screen)
+        .add_systems(
+            Update,
+            (score_gauntlet_rounds, record_gauntlet_result)
+                .in_set(GameSet::Combat)
+                .run_if(resource_equals(GameMode::Gauntlet))
+                .run_if(live_match),
+        )
+        .add_systems(
+            Update,
+            record_tournament_result
+                .in_set(GameSet::Combat)
+                .run_if(resource_equals(GameMode::Tournament))
+                .run_if(live_match),
+        )
+        .add_systems(
+            OnEnter(AppState::Fight),
+            (begin_replay.after(start_match), setup_replay_panel)
+                .chain()
+                .run_if(finishes_to_menu),
+        )
+        .add_systems(
+            FixedUpdate,
+            sync_replay_inputs
+                .after(take_snapshot)
+                .after(run_ai)
+                .after(drive_dummy)
+                .before(process_input)
+                .before(tag_fighters)
+                .run_if(in_state(AppState::Fight))
+                .run_if(finishes_to_menu),
+        )
+        .add_systems(
+            FixedUpdate,
+            log_timeline_events
+                .after(advance_round)
+                .before(advance_tick)
+                .run_if(in_state(AppState::Fight))
+                .run_if(finishes_to_menu),
+        )
+        .add_systems(
+            Update,
+            (
+                update_replay_panel.in_set(GameSet::Presentation),
+                seek_replay,
+            )
+                .run_if(in_state(AppState::Fight))
+                .run_if(finishes_to_menu),
+        )
+        .add_systems(OnExit(AppState::Fight), close_replay_panel)
+        .add_systems(
+            OnEnter(AppState::Fight),
+            setup_pause_menu.run_if(not(resource_equals(GameMode::Demo))),
+        )
+        .add_systems(OnExit(AppState::Fight), close_pause_menu)
+        .add_systems(
+            Update,
+            (
+                pause_menu_keys.in_set(GameSet::Input),
+                update_pause_menu.in_set(GameSet::Presentation),
+            )
+                .run_if(in_state(AppState::Fight))
+                .run_if(not(resource_equals(GameMode::Demo))),
+        )
+        .add_systems(
+            OnEnter(AppState::Finished),
+            show_replay_prompt.run_if(finishes_to_menu),
+        )
+        .add_systems(
+            Update,
+            watch_replay
+                .after(drive_transition)
+                .in_set(GameSet::Presentation)
+                .run_if(in_state(AppState::Finished))
+                .run_if(photo_mode_inactive)
+                .run_if(finishes_to_menu),
+        )
+        .add_systems(OnEnter(AppState::MatchOptions), setup_match_options_panel)
+        .add_systems(
+            Update,
+            (
+                match_options_keys.in_set(GameSet::Input),
+                update_match_options_panel.in_set(GameSet::Presentation),
+            )
+                .run_if(in_state(AppState::MatchOptions)),
+        )
+        .add_systems(OnExit(AppState::MatchOptions), close_match_options_panel)
+        .add_systems(
+            OnEnter(AppState::Fight),
+            (
+                start_intro_sweep,
+                reveal_fight,
+                setup_hud,
+                setup_toasts,
+                (apply_time_of_day, apply_environment)
+                    .chain()
+                    .after(start_match),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                update_hud.run_if(in_state(AppState::Fight)),
+                (frame_next_round, reveal_next_round, drive_transition).chain(),
+                (queue_banners, animate_banners).chain(),
+                (spawn_stage, spawn_fog, drift_fog),
+                prepare_skyboxes,
+                (excite_crowd, mix_crowd).chain(),
+                (
+                    play_impact_sounds,
+                    play_wall_sounds,
+                    play_throw_sounds,
+                    play_sfx::<AudioSource>.after(animate_banners),
+                    play_sfx::<Whoosh>,
+                )
+                    .chain(),
+                schedule_barks
+                    .before(queue_captions)
+                    .before(play_sfx::<AudioSource>),
+                (queue_captions.after(excite_crowd), expire_captions).chain(),
+                (start_spotlight_moment, drive_stage_lights).chain(),
+                (spawn_knockdown_decals, fade_decals).chain(),
+                flash_hazard_warnings,
+            )
+                .in_set(GameSet::Presentation),
+        )
+        .add_systems(
+            Update,
+            (toast_combat_events, show_toasts, expire_toasts)
+                .chain()
+                .in_set(GameSet::Presentation),
+        )
+        .add_systems(OnEnter(AppState::Fight), clear_decals)
+        .add_systems(
+            FixedUpdate,
+            pose_skeletons_on_tick
+                .after(process_movement)
+                .before(PhysicsSet::SyncBackend)
+                .run_if(in_state(AppState::Fight)),
+        )
+        .add_systems(
+            PostUpdate,
+            (apply_leg_layer, apply_look_at, apply_head_snaps)
+                .chain()
+                .after(animation_player)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            PostUpdate,
+            plant_feet
+                .after(TransformSystem::TransformPropagate)
+                .run_if(foot_ik_enabled),
+        )
+        .add_systems(
+            PostUpdate,
+            simulate_cloth
+                .after(TransformSystem::TransformPropagate)
+                .after(plant_feet),
+        )
+        .add_systems(
+            PostUpdate,
+            extract_root_motion
+                .after(animation_player)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            Startup,
+            (setup_dummy_panel, setup_input_display).run_if(resource_equals(GameMode::Training)),
+        )
+        .add_systems(
+            Update,
+            (
+                dummy_settings_keys.in_set(GameSet::Input),
+                rewind_training
+                    .after(dummy_settings_keys)
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(AppState::Fight)),
+                reset_positions
+                    .after(dummy_settings_keys)
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(AppState::Fight)),
+                (update_dummy_panel, update_input_display).in_set(GameSet::Presentation),
+                (spawn_damage_numbers, float_damage_numbers)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            )
+                .run_if(resource_equals(GameMode::Training)),
+        )
+        .add_systems(OnExit(AppState::Fight), clear_damage_numbers)
+        .add_systems(
+            FixedUpdate,
+            record_input_history
+                .before(process_input)
+                .run_if(in_state(AppState::Fight))
+                .run_if(resource_equals(GameMode::Training)),
+        )
+        .add_systems(
+            OnEnter(AppState::Fight),
+            setup_trial_panel.run_if(resource_equals(GameMode::Trials)),
+        )
+        .add_systems(
+            Update,
+            (
+                trial_keys.in_set(GameSet::Input),
+                track_trial.in_set(GameSet::Combat),
+                update_trial_panel.in_set(GameSet::Presentation),
+            )
+                .run_if(resource_equals(GameMode::Trials)),
+        )
+        .add_systems(
+            Update,
+            report_stress_stats
+                .in_set(GameSet::Presentation)
+                .run_if(in_state(AppState::Fight))
+                .run_if(resource_equals(GameMode::Stress)),
+        )
+        .add_systems(
+            OnEnter(AppState::Fight),
+            setup_tutorial.run_if(resource_equals(GameMode::Tutorial)),
+        )
+        .add_systems(
+            Update,
+            (
+                (track_tutorial, advance_tutorial)
+                    .chain()
+                    .in_set(GameSet::Combat),
+                update_tutorial_prompt.in_set(GameSet::Presentation),
+            )
+                .run_if(in_state(AppState::Fight))
+                .run_if(resource_equals(GameMode::Tutorial)),
+        )
+        .add_systems(
+            FixedUpdate,
+            drive_tutorial_opponent
+                .after(drive_dummy)
+                .before(process_input)
+                .run_if(in_state(AppState::Fight))
+                .run_if(resource_equals(GameMode::Tutorial)),
+        )
+        .add_systems(
+            FixedUpdate,
+            drive_trial_demo
+                .before(process_input)
+                .run_if(in_state(AppState::Fight))
+                .run_if(resource_equals(GameMode::Trials)),
+        );
+
+    #[cfg(feature = "inspector")]
+    app.add_plugins(bevy_inspector_egui::quick::WorldInspectorPlugin::new());
+    #[cfg(feature = "particles")]
+    app.add_plugins(bevy_hanabi::HanabiPlugin)
+        .init_resource::<particle_pool::ParticlePool>()
+        .add_systems(Startup, (setup_clash_sparks, setup_dizzy_birds))
+        .add_systems(
+            Update,
+            (
+                spawn_weather_particles,
+                show_dizzy_birds,
+                (
+                    spawn_clash_sparks,
+                    spawn_hit_sparks,
+                    spawn_wall_dust,
+                    particle_pool::fire_pooled_bursts,
+                )
+                    .chain(),
+            )
+                .in_set(GameSet::Presentation),
+        );
+
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(Startup, setup_music)
+        .add_systems(
+            Update,
+            (
+                capture_screenshot,
+                export_clip,
+                record_clip_frames
+                    .before(mirror_fight_camera)
+                    .run_if(in_state(AppState::Fight)),
+            )
+                .chain()
+                .in_set(GameSet::Presentation),
+        )
+        .add_systems(OnEnter(AppState::Fight), spawn_clip_camera)
+        .add_systems(OnExit(AppState::Fight), clear_clip_buffer);
+    // Browsers block audio until the page gets a user gesture, and there's
+    // no filesystem to capture to.
+    #[cfg(target_arch = "wasm32")]
+    app.insert_resource(bevy::asset::AssetMetaCheck::Never)
+        .add_systems(
+            Update,
+            setup_music.run_if(any_user_input.and_then(run_once())),
+        );
+    app
+}
@@ -0,0 +1,108 @@
+use std::{collections::HashMap, fmt::Display};
+
+use bevy::{ecs::system::SystemParam, prelude::*, reflect::TypePath};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    pub fn next(self) -> Self {
+        match self {
+            Language::English => Language::Spanish,
+            Language::Spanish => Language::English,
+        }
+    }
+
+    // Always shown in the language itself so players can find their own.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Language::English => "locale/en.locale.ron",
+            Language::Spanish => "locale/es.locale.ron",
+        }
+    }
+}
+
+// Every on-screen string by key. Values can hold `{name}` placeholders that
+// `Strings::fill` swaps for arguments.
+#[derive(Asset, TypePath, Deserialize, Debug, Default)]
+#[serde(transparent)]
+pub struct LocaleTable {
+    pub strings: HashMap<String, String>,
+}
+
+#[derive(Resource, Default)]
+pub struct Locale {
+    tables: HashMap<Language, Handle<LocaleTable>>,
+}
+
+pub fn load_locales(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let tables = Language::ALL
+        .into_iter()
+        .map(|language| (language, asset_server.load(language.path())))
+        .collect();
+    commands.insert_resource(Locale { tables });
+}
+
+// Marks the locale changed as tables arrive or are edited on disk, so text
+// already on screen gets redrawn.
+pub fn track_locale_tables(
+    mut events: EventReader<AssetEvent<LocaleTable>>,
+    mut locale: ResMut<Locale>,
+) {
+    if events.iter().next().is_some() {
+        events.clear();
+        locale.set_changed();
+    }
+}
+
+// Looks strings up in the chosen language, falling back to English and then
+// to the key itself so a missing entry shows up rather than going blank.
+#[derive(SystemParam)]
+pub struct Strings<'w> {
+    settings: Res<'w, Settings>,
+    locale: Res<'w, Locale>,
+    tables: Res<'w, Assets<LocaleTable>>,
+}
+
+impl Strings<'_> {
+    pub fn get(&self, key: &str) -> String {
+        [self.settings.language, Language::English]
+            .iter()
+            .filter_map(|language| self.locale.tables.get(language))
+            .filter_map(|handle| self.tables.get(handle))
+            .find_map(|table| table.strings.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    pub fn fill(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter().fold(self.get(key), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+    }
+
+    pub fn on_off(&self, on: bool) -> String {
+        self.get(if on { "common.on" } else { "common.off" })
+    }
+
+    // Whether text built from these strings needs building again.
+    pub fn changed(&self) -> bool {
+        self.settings.is_changed() || self.locale.is_changed()
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::Team,
+    locale::Strings,
+    menu::{menu_item, Menu, MenuAction, MenuEvent, MenuItem},
+    profile::Profile,
+    roster::MatchSetup,
+    round::MatchRules,
+    stage::TimeOfDay,
+    AppState,
+};
+
+const BEST_OF: [u32; 4] = [1, 3, 5, 7];
+const ROUND_TIMES: [Option<f32>; 4] = [Some(30.0), Some(60.0), Some(99.0), None];
+const STARTING_HEALTH: [f32; 4] = [50.0, 100.0, 150.0, 200.0];
+const DAMAGE_MULTIPLIERS: [f32; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+const HANDICAPS: [f32; 5] = [0.5, 0.75, 1.0, 1.25, 1.5];
+const TIMES_OF_DAY: [Option<TimeOfDay>; 4] = [
+    None,
+    Some(TimeOfDay::Day),
+    Some(TimeOfDay::Sunset),
+    Some(TimeOfDay::Night),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MatchOptionRow {
+    BestOf,
+    RoundTime,
+    StartingHealth,
+    Damage,
+    Handicap(Team),
+    Regeneration,
+    Comeback,
+    RingOut,
+    Hazards,
+    TimeOfDay,
+    Fight,
+}
+
+const ROWS: [MatchOptionRow; 12] = [
+    MatchOptionRow::BestOf,
+    MatchOptionRow::RoundTime,
+    MatchOptionRow::StartingHealth,
+    MatchOptionRow::Damage,
+    MatchOptionRow::Handicap(Team::One),
+    MatchOptionRow::Handicap(Team::Two),
+    MatchOptionRow::Regeneration,
+    MatchOptionRow::Comeback,
+    MatchOptionRow::RingOut,
+    MatchOptionRow::Hazards,
+    MatchOptionRow::TimeOfDay,
+    MatchOptionRow::Fight,
+];
+
+#[derive(Component)]
+pub struct MatchOptionsPanel;
+
+#[derive(Component)]
+pub struct MatchOptionsTitle;
+
+fn row_text(value: &str) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size: 28.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+// Drawn over the loading cover, which stays down until the fight starts.
+pub fn setup_match_options_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(30.0),
+                    left: Val::Percent(35.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                z_index: ZIndex::Global(101),
+                ..default()
+            },
+            Menu::vertical(ROWS.len(), 0),
+            MatchOptionsPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((row_text(""), MatchOptionsTitle));
+            for index in 0..ROWS.len() {
+                panel.spawn(menu_item(index, row_text("")));
+            }
+        });
+}
+
+pub fn close_match_options_panel(
+    mut commands: Commands,
+    panels: Query<Entity, With<MatchOptionsPanel>>,
+) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Wraps around; a value that isn't one of the choices starts from the first.
+fn step_choice<T: PartialEq + Copy>(choices: &[T], current: T, step: isize) -> T {
+    let index = choices
+        .iter()
+        .position(|choice| *choice == current)
+        .map_or(0, |index| index as isize + step);
+    choices[index.rem_euclid(choices.len() as isize) as usize]
+}
+
+fn player_number(team: Team) -> u32 {
+    match team {
+        Team::One => 1,
+        Team::Two => 2,
+    }
+}
+
+fn best_of(rules: &MatchRules) -> u32 {
+    rules.rounds_to_win * 2 - 1
+}
+
+// Left/Right or confirm changes the focused rule, confirm on "Fight!" starts
+// the match and cancel goes back to character select.
+pub fn match_options_keys(
+    panels: Query<(), With<MatchOptionsPanel>>,
+    mut events: EventReader<MenuEvent>,
+    mut rules: ResMut<MatchRules>,
+    mut setup: ResMut<MatchSetup>,
+    profile: Res<Profile>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.iter() {
+        if !panels.contains(event.menu) {
+            continue;
+        }
+        let step = match (ROWS[event.item], event.action) {
+            (_, MenuAction::Cancel) => {
+                next_state.set(AppState::CharacterSelect);
+                continue;
+            }
+            (MatchOptionRow::Fight, MenuAction::Confirm) => {
+                next_state.set(AppState::Fight);
+                continue;
+            }
+            (_, MenuAction::Left) => -1,
+            (_, MenuAction::Right | MenuAction::Confirm) => 1,
+            _ => continue,
+        };
+        match ROWS[event.item] {
+            MatchOptionRow::BestOf => {
+                rules.rounds_to_win = (step_choice(&BEST_OF, best_of(&rules), step) + 1) / 2;
+            }
+            MatchOptionRow::RoundTime => {
+                rules.round_time = step_choice(&ROUND_TIMES, rules.round_time, step);
+            }
+            MatchOptionRow::StartingHealth => {
+                rules.starting_health =
+                    step_choice(&STARTING_HEALTH, rules.starting_health, step);
+            }
+            MatchOptionRow::Damage => {
+                rules.damage_multiplier =
+                    step_choice(&DAMAGE_MULTIPLIERS, rules.damage_multiplier, step);
+            }
+            MatchOptionRow::Handicap(team) => {
+                let handicap = step_choice(&HANDICAPS, rules.handicap(team), step);
+                rules.set_handicap(team, handicap);
+            }
+            MatchOptionRow::Regeneration => rules.regeneration = !rules.regeneration,
+            MatchOptionRow::Comeback => rules.comeback = !rules.comeback,
+            MatchOptionRow::RingOut => rules.ring_out = !rules.ring_out,
+            MatchOptionRow::Hazards => rules.hazards = !rules.hazards,
+            MatchOptionRow::TimeOfDay => {
+                // Night stays off the list until it's been unlocked.
+                let times: Vec<Option<TimeOfDay>> = TIMES_OF_DAY
+                    .into_iter()
+                    .filter(|time| time.map_or(true, |time| time.unlocked(&profile)))
+                    .collect();
+                setup.time_of_day = step_choice(&times, setup.time_of_day, step);
+            }
+            MatchOptionRow::Fight => {}
+        }
+    }
+}
+
+pub fn update_match_options_panel(
+    rules: Res<MatchRules>,
+    setup: Res<MatchSetup>,
+    strings: Strings,
+    panels: Query<(), With<MatchOptionsPanel>>,
+    added: Query<(), Added<MatchOptionsPanel>>,
+    mut titles: Query<&mut Text, (With<MatchOptionsTitle>, Without<MenuItem>)>,
+    mut items: Query<(&MenuItem, &Parent, &mut Text)>,
+) {
+    if !rules.is_changed() && !setup.is_changed() && !strings.changed() && added.is_empty() {
+        return;
+    }
+    let round_time = rules.round_time.map_or_else(
+        || strings.get("rules.infinite"),
+        |seconds| strings.fill("rules.seconds", &[("value", &seconds)]),
+    );
+    let time_of_day = setup.time_of_day.map_or_else(
+        || strings.get("stage.time.random"),
+        |time| strings.get(time.name_key()),
+    );
+    let row = |key: &str, value: &dyn std::fmt::Display| strings.fill(key, &[("value", value)]);
+    let rows = ROWS.map(|option| match option {
+        MatchOptionRow::BestOf => row("rules.best_of", &best_of(&rules)),
+        MatchOptionRow::RoundTime => row("rules.round_time", &round_time),
+        MatchOptionRow::StartingHealth => row("rules.starting_health", &rules.starting_health),
+        MatchOptionRow::Damage => row("rules.damage", &rules.damage_multiplier),
+        MatchOptionRow::Handicap(team) => strings.fill(
+            "rules.handicap",
+            &[
+                ("player", &player_number(team)),
+                ("value", &(rules.handicap(team) * 100.0)),
+            ],
+        ),
+        MatchOptionRow::Regeneration => {
+            row("rules.regeneration", &strings.on_off(rules.regeneration))
+        }
+        MatchOptionRow::Comeback => row("rules.comeback", &strings.on_off(rules.comeback)),
+        MatchOptionRow::RingOut => row("rules.ring_out", &strings.on_off(rules.ring_out)),
+        MatchOptionRow::Hazards => row("rules.hazards", &strings.on_off(rules.hazards)),
+        MatchOptionRow::TimeOfDay => row("rules.time_of_day", &time_of_day),
+        MatchOptionRow::Fight => strings.get("rules.fight"),
+    });
+    for mut title in titles.iter_mut() {
+        title.sections[0].value = strings.get("rules.title");
+    }
+    for (item, parent, mut text) in items.iter_mut() {
+        if panels.contains(parent.get()) {
+            text.sections[0].value = rows[item.0].clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_choice_wraps_both_ways() {
+        assert_eq!(step_choice(&[1, 2, 3], 3, 1), 1);
+        assert_eq!(step_choice(&[1, 2, 3], 1, -1), 3);
+        assert_eq!(step_choice(&[1, 2, 3], 2, 1), 3);
+    }
+
+    #[test]
+    fn step_choice_starts_over_from_an_unknown_value() {
+        assert_eq!(step_choice(&[1, 2, 3], 7, 1), 1);
+    }
+}
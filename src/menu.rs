@@ -0,0 +1,353 @@
+use std::time::Duration;
+
+use bevy::{
+    audio::{Decodable, PlaybackMode, Source, Volume, VolumeLevel},
+    prelude::*,
+    reflect::TypePath,
+    window::PrimaryWindow,
+};
+
+const FOCUSED_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+const PRESSED_COLOR: Color = Color::rgb(1.0, 0.55, 0.1);
+const BLIP_SAMPLE_RATE: u32 = 44100;
+// Seconds the mouse cursor stays up after it last moved.
+const CURSOR_IDLE: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MenuAxis {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+// A list of focusable `MenuItem`s somewhere below this entity. Only one menu
+// takes input at a time: the visible one with the highest `layer`, so a
+// panel opened over a screen steals the controls until it closes.
+#[derive(Component, Clone, Debug)]
+pub struct Menu {
+    pub focused: usize,
+    pub len: usize,
+    pub axis: MenuAxis,
+    pub layer: i32,
+}
+
+impl Menu {
+    pub fn vertical(len: usize, layer: i32) -> Self {
+        Menu {
+            focused: 0,
+            len,
+            axis: MenuAxis::Vertical,
+            layer,
+        }
+    }
+
+    pub fn horizontal(len: usize, layer: i32) -> Self {
+        Menu {
+            axis: MenuAxis::Horizontal,
+            ..Menu::vertical(len, layer)
+        }
+    }
+
+    fn step(&mut self, step: isize) {
+        if self.len > 0 {
+            self.focused = (self.focused as isize + step).rem_euclid(self.len as isize) as usize;
+        }
+    }
+}
+
+// Needs a `Node` to be clickable; `menu_item` bundles one up for a text row.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MenuItem(pub usize);
+
+pub fn menu_item(index: usize, text: TextBundle) -> impl Bundle {
+    (text, Interaction::default(), MenuItem(index))
+}
+
+// A text row outside of any `Menu` that can still be clicked, like the
+// training panel's. It lights up under the cursor and blips when pressed;
+// what a press does is up to whoever spawned it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Clickable;
+
+pub fn clickable(text: TextBundle) -> impl Bundle {
+    (text, Interaction::default(), Clickable)
+}
+
+// Everything the focused menu didn't handle itself: confirm, cancel and
+// presses across its axis, e.g. Left/Right to change a value in a list.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MenuEvent {
+    pub menu: Entity,
+    pub item: usize,
+    pub action: MenuAction,
+}
+
+// Short synthesised tones, so menus make noise without any samples on disk.
+#[derive(Asset, TypePath, Clone, Copy)]
+pub struct MenuBlip {
+    pub frequency: f32,
+    pub seconds: f32,
+}
+
+pub struct MenuBlipDecoder {
+    blip: MenuBlip,
+    sample: u32,
+}
+
+impl Iterator for MenuBlipDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let seconds = self.sample as f32 / BLIP_SAMPLE_RATE as f32;
+        if seconds >= self.blip.seconds {
+            return None;
+        }
+        self.sample += 1;
+        let fade = 1.0 - seconds / self.blip.seconds;
+        Some((seconds * self.blip.frequency * std::f32::consts::TAU).sin() * fade * 0.5)
+    }
+}
+
+impl Source for MenuBlipDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        BLIP_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.blip.seconds))
+    }
+}
+
+impl Decodable for MenuBlip {
+    type DecoderItem = <MenuBlipDecoder as Iterator>::Item;
+    type Decoder = MenuBlipDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        MenuBlipDecoder {
+            blip: *self,
+            sample: 0,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MenuSounds {
+    pub focus: Handle<MenuBlip>,
+    pub confirm: Handle<MenuBlip>,
+    pub cancel: Handle<MenuBlip>,
+}
+
+pub fn setup_menu_sounds(mut commands: Commands, mut blips: ResMut<Assets<MenuBlip>>) {
+    commands.insert_resource(MenuSounds {
+        focus: blips.add(MenuBlip {
+            frequency: 660.0,
+            seconds: 0.04,
+        }),
+        confirm: blips.add(MenuBlip {
+            frequency: 990.0,
+            seconds: 0.1,
+        }),
+        cancel: blips.add(MenuBlip {
+            frequency: 330.0,
+            seconds: 0.12,
+        }),
+    });
+}
+
+pub fn play_blip(commands: &mut Commands, blip: &Handle<MenuBlip>) {
+    commands.spawn(AudioSourceBundle {
+        source: blip.clone(),
+        settings: PlaybackSettings {
+            mode: PlaybackMode::Despawn,
+            volume: Volume::Relative(VolumeLevel::new(0.3)),
+            ..default()
+        },
+    });
+}
+
+fn pressed_actions(
+    keys: &Input<KeyCode>,
+    buttons: &Input<GamepadButton>,
+    gamepads: &Gamepads,
+) -> Vec<MenuAction> {
+    let bindings = [
+        (MenuAction::Up, KeyCode::Up, GamepadButtonType::DPadUp),
+        (MenuAction::Down, KeyCode::Down, GamepadButtonType::DPadDown),
+        (MenuAction::Left, KeyCode::Left, GamepadButtonType::DPadLeft),
+        (MenuAction::Right, KeyCode::Right, GamepadButtonType::DPadRight),
+        (MenuAction::Confirm, KeyCode::Return, GamepadButtonType::South),
+        (MenuAction::Cancel, KeyCode::Back, GamepadButtonType::East),
+    ];
+    bindings
+        .into_iter()
+        .filter(|(_, key, button)| {
+            keys.just_pressed(*key)
+                || gamepads
+                    .iter()
+                    .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, *button)))
+        })
+        .map(|(action, ..)| action)
+        .collect()
+}
+
+// Runs after the UI has worked out what the mouse is over, so hovering
+// focuses an item and clicking it confirms, same as the keys. A right click
+// steps a value back, as Left would.
+#[allow(clippy::too_many_arguments)]
+pub fn navigate_menus(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    sounds: Option<Res<MenuSounds>>,
+    mut menus: Query<(Entity, &mut Menu, &Style)>,
+    items: Query<(Entity, &MenuItem, Ref<Interaction>)>,
+    parents: Query<&Parent>,
+    mut events: EventWriter<MenuEvent>,
+) {
+    let Some((entity, mut menu, _)) = menus
+        .iter_mut()
+        .filter(|(_, _, style)| style.display != Display::None)
+        .max_by_key(|(_, menu, _)| menu.layer)
+    else {
+        return;
+    };
+
+    let mut actions = pressed_actions(&keys, &buttons, &gamepads);
+    for (item, index, interaction) in items.iter() {
+        if !parents.iter_ancestors(item).any(|ancestor| ancestor == entity) {
+            continue;
+        }
+        match *interaction {
+            Interaction::Hovered if mouse.just_pressed(MouseButton::Right) => {
+                menu.focused = index.0;
+                actions.push(MenuAction::Left);
+            }
+            _ if !interaction.is_changed() => {}
+            Interaction::Hovered if menu.focused != index.0 => {
+                menu.focused = index.0;
+                if let Some(sounds) = &sounds {
+                    play_blip(&mut commands, &sounds.focus);
+                }
+            }
+            Interaction::Pressed => {
+                menu.focused = index.0;
+                actions.push(MenuAction::Confirm);
+            }
+            _ => {}
+        }
+    }
+
+    for action in actions {
+        let step = match (menu.axis, action) {
+            (MenuAxis::Vertical, MenuAction::Up) | (MenuAxis::Horizontal, MenuAction::Left) => -1,
+            (MenuAxis::Vertical, MenuAction::Down)
+            | (MenuAxis::Horizontal, MenuAction::Right) => 1,
+            _ => 0,
+        };
+        if step != 0 {
+            menu.step(step);
+        } else {
+            events.send(MenuEvent {
+                menu: entity,
+                item: menu.focused,
+                action,
+            });
+        }
+        let Some(sounds) = &sounds else {
+            continue;
+        };
+        match action {
+            MenuAction::Confirm => play_blip(&mut commands, &sounds.confirm),
+            MenuAction::Cancel => play_blip(&mut commands, &sounds.cancel),
+            _ => play_blip(&mut commands, &sounds.focus),
+        }
+    }
+}
+
+pub fn highlight_menu_items(
+    menus: Query<&Menu>,
+    parents: Query<&Parent>,
+    mut items: Query<(Entity, &MenuItem, &Interaction, &mut Text)>,
+) {
+    for (entity, item, interaction, mut text) in items.iter_mut() {
+        let Some(menu) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| menus.get(ancestor).ok())
+        else {
+            continue;
+        };
+        let color = match (*interaction, menu.focused == item.0) {
+            (Interaction::Pressed, _) => PRESSED_COLOR,
+            (_, true) => FOCUSED_COLOR,
+            (_, false) => Color::WHITE,
+        };
+        set_text_color(&mut text, color);
+    }
+}
+
+fn set_text_color(text: &mut Text, color: Color) {
+    for section in text.sections.iter_mut() {
+        if section.style.color != color {
+            section.style.color = color;
+        }
+    }
+}
+
+pub fn highlight_clickables(
+    mut commands: Commands,
+    sounds: Option<Res<MenuSounds>>,
+    mut items: Query<(&Interaction, &mut Text), (With<Clickable>, Changed<Interaction>)>,
+) {
+    for (interaction, mut text) in items.iter_mut() {
+        let (color, blip) = match interaction {
+            Interaction::Pressed => (PRESSED_COLOR, sounds.as_ref().map(|s| &s.confirm)),
+            Interaction::Hovered => (FOCUSED_COLOR, sounds.as_ref().map(|s| &s.focus)),
+            Interaction::None => (Color::WHITE, None),
+        };
+        set_text_color(&mut text, color);
+        if let Some(blip) = blip {
+            play_blip(&mut commands, blip);
+        }
+    }
+}
+
+// The cursor shows while the mouse is in use and gets out of the way of the
+// fight once it's been left alone for a while.
+pub fn show_cursor(
+    time: Res<Time>,
+    mut moved: EventReader<CursorMoved>,
+    mut idle: Local<f32>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if moved.iter().next().is_some() {
+        *idle = 0.0;
+    } else {
+        *idle += time.delta_seconds();
+    }
+    let visible = *idle < CURSOR_IDLE;
+    for mut window in windows.iter_mut() {
+        if window.cursor.visible != visible {
+            window.cursor.visible = visible;
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use serde::Deserialize;
+
+use crate::character::AnimationState;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MoveId {
+    Punch,
+    Kick,
+    Elbow,
+    ShoulderCharge,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HitLevel {
+    High,
+    Mid,
+    Low,
+}
+
+// How heavy an attack is. Heavier attacks start slower but stun for longer.
+// Attacks of the same level clash when they meet; different levels trade.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum AttackLevel {
+    Light,
+    Medium,
+    Heavy,
+}
+
+impl AttackLevel {
+    // Seconds before the hitbox counts.
+    pub fn startup(&self) -> f32 {
+        match self {
+            AttackLevel::Light => 0.15,
+            AttackLevel::Medium => 0.25,
+            AttackLevel::Heavy => 0.35,
+        }
+    }
+
+    // Seconds the defender can't act after a clean hit.
+    pub fn hitstun(&self) -> f32 {
+        match self {
+            AttackLevel::Light => 0.3,
+            AttackLevel::Medium => 0.45,
+            AttackLevel::Heavy => 0.6,
+        }
+    }
+
+    // Seconds the defender is held in their guard after blocking.
+    pub fn blockstun(&self) -> f32 {
+        match self {
+            AttackLevel::Light => 0.15,
+            AttackLevel::Medium => 0.25,
+            AttackLevel::Heavy => 0.35,
+        }
+    }
+}
+
+// Where an attack is up to. Only active moves can hit; a move that whiffs
+// leaves its owner stuck in recovery, open to a punish.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MovePhase {
+    Startup,
+    Active,
+    Recovery,
+}
+
+// A move done in place of another when the opponent is within `range`,
+// origin to origin, as the button is pressed.
+#[derive(Clone, Copy, Debug)]
+pub struct PointBlank {
+    pub range: f32,
+    pub move_id: MoveId,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MoveData {
+    pub damage: f32,
+    pub knockback: f32,
+    pub hit_level: HitLevel,
+    // Knocks a grounded opponent into the air for a juggle.
+    pub launcher: bool,
+    pub level: AttackLevel,
+    // Seconds the hitbox stays live after startup. The rest of the move is
+    // recovery.
+    pub active: f32,
+    // Shrugs off the first hit taken during startup.
+    pub armor: bool,
+    // Can't be blocked.
+    pub guard_break: bool,
+    // Only uses the arms, so the fighter can keep walking through it.
+    pub upper_body: bool,
+    // Moves this one can be cut short into once it has connected, up until
+    // its recovery starts.
+    pub cancels_into: &'static [MoveId],
+    // Times one use of the move can connect with the same fighter. A limb
+    // sweeping through the body raises more than one contact, and the rest
+    // are ignored.
+    pub hits: u32,
+    // Can hit an opponent lying on the floor after a juggle, and bounces
+    // whoever it hits off the floor at this upward speed for more juggling.
+    pub ground_bounce: Option<f32>,
+    pub point_blank: Option<PointBlank>,
+}
+
+impl MoveData {
+    pub fn phase_at(&self, elapsed: f32) -> MovePhase {
+        let startup = self.level.startup();
+        if elapsed < startup {
+            MovePhase::Startup
+        } else if elapsed < startup + self.active {
+            MovePhase::Active
+        } else {
+            MovePhase::Recovery
+        }
+    }
+}
+
+impl MoveId {
+    pub fn data(&self) -> MoveData {
+        match self {
+            MoveId::Punch => MoveData {
+                damage: 8.0,
+                knockback: 2.0,
+                hit_level: HitLevel::High,
+                launcher: false,
+                level: AttackLevel::Light,
+                active: 0.15,
+                armor: false,
+                guard_break: false,
+                upper_body: true,
+                cancels_into: &[MoveId::Kick],
+                hits: 1,
+                ground_bounce: None,
+                point_blank: Some(PointBlank {
+                    range: 0.9,
+                    move_id: MoveId::Elbow,
+                }),
+            },
+            MoveId::Kick => MoveData {
+                damage: 12.0,
+                knockback: 3.5,
+                hit_level: HitLevel::Mid,
+                launcher: true,
+                level: AttackLevel::Heavy,
+                active: 0.25,
+                armor: true,
+                guard_break: false,
+                upper_body: false,
+                cancels_into: &[],
+                hits: 1,
+                ground_bounce: Some(6.0),
+                point_blank: None,
+            },
+            // The punch up close: shorter and stubbier, but it hits mid and
+            // recovers sooner.
+            MoveId::Elbow => MoveData {
+                damage: 9.0,
+                knockback: 1.0,
+                hit_level: HitLevel::Mid,
+                launcher: false,
+                level: AttackLevel::Light,
+                active: 0.1,
+                armor: false,
+                guard_break: false,
+                upper_body: true,
+                cancels_into: &[MoveId::Kick],
+                hits: 1,
+                ground_bounce: None,
+                point_blank: None,
+            },
+            // Punch and kick together: slow to come out, but it goes
+            // straight through a guard.
+            MoveId::ShoulderCharge => MoveData {
+                damage: 14.0,
+                knockback: 4.0,
+                hit_level: HitLevel::Mid,
+                launcher: false,
+                level: AttackLevel::Heavy,
+                active: 0.2,
+                armor: false,
+                guard_break: true,
+                upper_body: false,
+                cancels_into: &[],
+                hits: 1,
+                ground_bounce: None,
+                point_blank: None,
+            },
+        }
+    }
+
+    pub fn from_state(state: AnimationState) -> Option<MoveId> {
+        match state {
+            AnimationState::Punching => Some(MoveId::Punch),
+            AnimationState::Kicking => Some(MoveId::Kick),
+            AnimationState::Elbowing => Some(MoveId::Elbow),
+            AnimationState::Charging => Some(MoveId::ShoulderCharge),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_go_through_startup_active_and_recovery() {
+        let punch = MoveId::Punch.data();
+        let startup = punch.level.startup();
+        assert_eq!(punch.phase_at(0.0), MovePhase::Startup);
+        assert_eq!(punch.phase_at(startup - 0.01), MovePhase::Startup);
+        assert_eq!(punch.phase_at(startup + 0.01), MovePhase::Active);
+        assert_eq!(
+            punch.phase_at(startup + punch.active + 0.01),
+            MovePhase::Recovery
+        );
+    }
+
+    #[test]
+    fn every_attack_state_maps_back_to_its_move() {
+        for move_id in [
+            MoveId::Punch,
+            MoveId::Kick,
+            MoveId::Elbow,
+            MoveId::ShoulderCharge,
+        ] {
+            let state = match move_id {
+                MoveId::Punch => AnimationState::Punching,
+                MoveId::Kick => AnimationState::Kicking,
+                MoveId::Elbow => AnimationState::Elbowing,
+                MoveId::ShoulderCharge => AnimationState::Charging,
+            };
+            assert_eq!(MoveId::from_state(state), Some(move_id));
+        }
+        assert_eq!(MoveId::from_state(AnimationState::Idle), None);
+    }
+
+    #[test]
+    fn guard_breaks_are_slow() {
+        let charge = MoveId::ShoulderCharge.data();
+        assert!(charge.guard_break);
+        assert_eq!(charge.level, AttackLevel::Heavy);
+    }
+}
@@ -0,0 +1,299 @@
+use bevy::{prelude::*, window::PrimaryWindow, winit::WinitWindows};
+
+use crate::{
+    character::Team,
+    input::{Bindings, ButtonMode, ControlScheme},
+    locale::Strings,
+    menu::{menu_item, Menu, MenuAction, MenuEvent, MenuItem},
+    palette::HudPalette,
+    settings::{available_resolutions, DisplayMode, GraphicsQuality, Settings},
+};
+
+pub const OPTIONS_KEY: KeyCode = KeyCode::O;
+pub const OPTIONS_BUTTON: GamepadButtonType = GamepadButtonType::Start;
+// Above every screen's menu, so it takes the controls while it's open.
+const OPTIONS_MENU_LAYER: i32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum OptionRow {
+    DisplayMode,
+    Resolution,
+    Vsync,
+    Quality,
+    FootIk,
+    Rumble,
+    Language,
+    Captions,
+    Palette,
+    ReduceFlashing,
+    Controls,
+    BlockMode,
+    CrouchMode,
+}
+
+const ROWS: [OptionRow; 13] = [
+    OptionRow::DisplayMode,
+    OptionRow::Resolution,
+    OptionRow::Vsync,
+    OptionRow::Quality,
+    OptionRow::FootIk,
+    OptionRow::Rumble,
+    OptionRow::Language,
+    OptionRow::Captions,
+    OptionRow::Palette,
+    OptionRow::ReduceFlashing,
+    OptionRow::Controls,
+    OptionRow::BlockMode,
+    OptionRow::CrouchMode,
+];
+
+#[derive(Resource, Default)]
+pub struct OptionsMenu {
+    pub open: bool,
+}
+
+#[derive(Component)]
+pub struct OptionsPanel;
+
+#[derive(Component)]
+pub struct OptionsTitle;
+
+fn row_text(value: &str) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size: 24.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+pub fn setup_options_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(30.0),
+                    left: Val::Percent(35.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                z_index: ZIndex::Global(110),
+                ..default()
+            },
+            Menu::vertical(ROWS.len(), OPTIONS_MENU_LAYER),
+            OptionsPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((row_text(""), OptionsTitle));
+            for index in 0..ROWS.len() {
+                panel.spawn(menu_item(index, row_text("")));
+            }
+        });
+}
+
+fn step_resolution(
+    current: Option<(u32, u32)>,
+    resolutions: &[(u32, u32)],
+    step: isize,
+) -> Option<(u32, u32)> {
+    if resolutions.is_empty() {
+        return current;
+    }
+    let index = current
+        .and_then(|current| resolutions.iter().position(|r| *r == current))
+        .map_or(0, |index| index as isize + step);
+    Some(resolutions[index.rem_euclid(resolutions.len() as isize) as usize])
+}
+
+// Left/Right or confirm changes the focused row, cancel closes the panel.
+// Every change is applied and saved straight away.
+#[allow(clippy::too_many_arguments)]
+pub fn options_menu_keys(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut menu: ResMut<OptionsMenu>,
+    mut settings: ResMut<Settings>,
+    mut bindings: ResMut<Bindings>,
+    panels: Query<(), With<OptionsPanel>>,
+    mut events: EventReader<MenuEvent>,
+    winit_windows: NonSend<WinitWindows>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+) {
+    let options_button = gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, OPTIONS_BUTTON)));
+    if keys.just_pressed(OPTIONS_KEY) || options_button {
+        menu.open = !menu.open;
+    }
+    for event in events.iter() {
+        if !menu.open || !panels.contains(event.menu) {
+            continue;
+        }
+        let step = match event.action {
+            MenuAction::Left => -1,
+            MenuAction::Right | MenuAction::Confirm => 1,
+            MenuAction::Cancel => {
+                menu.open = false;
+                continue;
+            }
+            _ => continue,
+        };
+        let row = ROWS[event.item];
+        match row {
+            OptionRow::BlockMode | OptionRow::CrouchMode => change_binding(&mut bindings, row),
+            _ => change_setting(&mut settings, row, step, &winit_windows, &windows),
+        }
+    }
+}
+
+// The panel only edits the keyboard player's bindings.
+fn change_binding(bindings: &mut Bindings, row: OptionRow) {
+    let player = bindings.player_mut(Team::One);
+    match row {
+        OptionRow::BlockMode => player.block = player.block.next(),
+        OptionRow::CrouchMode => player.crouch = player.crouch.next(),
+        _ => return,
+    }
+    bindings.save();
+}
+
+fn change_setting(
+    settings: &mut Settings,
+    row: OptionRow,
+    step: isize,
+    winit_windows: &WinitWindows,
+    windows: &Query<Entity, With<PrimaryWindow>>,
+) {
+    let display = &mut settings.display;
+    match row {
+        OptionRow::DisplayMode => display.mode = display.mode.next(),
+        OptionRow::Resolution => {
+            let Ok(window) = windows.get_single() else {
+                return;
+            };
+            let resolutions = available_resolutions(winit_windows, window);
+            display.resolution = step_resolution(display.resolution, &resolutions, step);
+        }
+        OptionRow::Vsync => display.vsync = !display.vsync,
+        OptionRow::Quality => settings.quality = settings.quality.next(),
+        OptionRow::FootIk => settings.foot_ik = !settings.foot_ik,
+        OptionRow::Rumble => settings.rumble = !settings.rumble,
+        OptionRow::Language => settings.language = settings.language.next(),
+        OptionRow::Captions => settings.captions = !settings.captions,
+        OptionRow::Palette => settings.palette = settings.palette.next(),
+        OptionRow::ReduceFlashing => settings.reduce_flashing = !settings.reduce_flashing,
+        OptionRow::Controls => settings.controls = settings.controls.next(),
+        OptionRow::BlockMode | OptionRow::CrouchMode => return,
+    }
+    settings.save();
+}
+
+fn display_mode_key(mode: DisplayMode) -> &'static str {
+    match mode {
+        DisplayMode::Windowed => "options.display.windowed",
+        DisplayMode::Borderless => "options.display.borderless",
+        DisplayMode::Fullscreen => "options.display.fullscreen",
+    }
+}
+
+fn quality_key(quality: GraphicsQuality) -> &'static str {
+    match quality {
+        GraphicsQuality::Low => "options.quality.low",
+        GraphicsQuality::Medium => "options.quality.medium",
+        GraphicsQuality::High => "options.quality.high",
+    }
+}
+
+fn controls_key(controls: ControlScheme) -> &'static str {
+    match controls {
+        ControlScheme::Standard => "options.controls.standard",
+        ControlScheme::Simple => "options.controls.simple",
+    }
+}
+
+fn button_mode_key(mode: ButtonMode) -> &'static str {
+    match mode {
+        ButtonMode::Hold => "options.button_mode.hold",
+        ButtonMode::Toggle => "options.button_mode.toggle",
+    }
+}
+
+fn palette_key(palette: HudPalette) -> &'static str {
+    match palette {
+        HudPalette::Standard => "options.palette.standard",
+        HudPalette::Deuteranopia => "options.palette.deuteranopia",
+        HudPalette::Protanopia => "options.palette.protanopia",
+        HudPalette::Tritanopia => "options.palette.tritanopia",
+    }
+}
+
+pub fn update_options_panel(
+    menu: Res<OptionsMenu>,
+    settings: Res<Settings>,
+    bindings: Res<Bindings>,
+    strings: Strings,
+    mut panels: Query<&mut Style, With<OptionsPanel>>,
+    mut titles: Query<&mut Text, (With<OptionsTitle>, Without<MenuItem>)>,
+    mut items: Query<(&MenuItem, &Parent, &mut Text)>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() && !strings.changed() {
+        return;
+    }
+    let display = &settings.display;
+    let resolution = display.resolution.map_or_else(
+        || strings.get("options.resolution.native"),
+        |(width, height)| format!("{width}x{height}"),
+    );
+    let row = |key: &str, value: &dyn std::fmt::Display| strings.fill(key, &[("value", value)]);
+    let rows = ROWS.map(|option| match option {
+        OptionRow::DisplayMode => row(
+            "options.display",
+            &strings.get(display_mode_key(display.mode)),
+        ),
+        OptionRow::Resolution => row("options.resolution", &resolution),
+        OptionRow::Vsync => row("options.vsync", &strings.on_off(display.vsync)),
+        OptionRow::Quality => row("options.quality", &strings.get(quality_key(settings.quality))),
+        OptionRow::FootIk => row("options.foot_ik", &strings.on_off(settings.foot_ik)),
+        OptionRow::Rumble => row("options.rumble", &strings.on_off(settings.rumble)),
+        OptionRow::Language => row("options.language", &settings.language.name()),
+        OptionRow::Captions => row("options.captions", &strings.on_off(settings.captions)),
+        OptionRow::Palette => row("options.palette", &strings.get(palette_key(settings.palette))),
+        OptionRow::ReduceFlashing => row(
+            "options.reduce_flashing",
+            &strings.on_off(settings.reduce_flashing),
+        ),
+        OptionRow::Controls => row(
+            "options.controls",
+            &strings.get(controls_key(settings.controls)),
+        ),
+        OptionRow::BlockMode => row(
+            "options.block",
+            &strings.get(button_mode_key(bindings.player(Team::One).block)),
+        ),
+        OptionRow::CrouchMode => row(
+            "options.crouch",
+            &strings.get(button_mode_key(bindings.player(Team::One).crouch)),
+        ),
+    });
+    for mut title in titles.iter_mut() {
+        title.sections[0].value = strings.get("options.title");
+    }
+    for mut style in panels.iter_mut() {
+        style.display = if menu.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for (item, parent, mut text) in items.iter_mut() {
+        if panels.contains(parent.get()) {
+            text.sections[0].value = rows[item.0].clone();
+        }
+    }
+}
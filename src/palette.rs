@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::character::Team;
+
+// Colour sets for everything that tells fighters and boxes apart. The
+// colourblind sets lean on the Okabe-Ito palette and avoid pairs the
+// matching kind of colourblindness can't separate.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HudPalette {
+    #[default]
+    Standard,
+    // Red-green, weak green.
+    Deuteranopia,
+    // Red-green, weak red.
+    Protanopia,
+    // Blue-yellow.
+    Tritanopia,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PaletteColors {
+    // Rim light outlines and HUD accents, by team.
+    pub teams: [Color; 2],
+    pub health: Color,
+    pub low_health: Color,
+    pub meter: Color,
+    pub hitbox: Color,
+    pub hurtbox: Color,
+}
+
+impl PaletteColors {
+    pub fn team(&self, team: Team) -> Color {
+        match team {
+            Team::One => self.teams[0],
+            Team::Two => self.teams[1],
+        }
+    }
+}
+
+impl HudPalette {
+    pub fn next(self) -> Self {
+        match self {
+            HudPalette::Standard => HudPalette::Deuteranopia,
+            HudPalette::Deuteranopia => HudPalette::Protanopia,
+            HudPalette::Protanopia => HudPalette::Tritanopia,
+            HudPalette::Tritanopia => HudPalette::Standard,
+        }
+    }
+
+    pub fn colors(self) -> PaletteColors {
+        match self {
+            HudPalette::Standard => PaletteColors {
+                teams: [Color::rgb(0.3, 0.6, 1.0), Color::rgb(1.0, 0.35, 0.25)],
+                health: Color::rgb(0.2, 0.85, 0.3),
+                low_health: Color::rgb(1.0, 0.3, 0.2),
+                meter: Color::rgb(1.0, 0.8, 0.2),
+                hitbox: Color::rgb(1.0, 0.1, 0.1),
+                hurtbox: Color::rgb(0.1, 1.0, 0.2),
+            },
+            HudPalette::Deuteranopia => PaletteColors {
+                teams: [Color::rgb(0.0, 0.45, 0.7), Color::rgb(0.9, 0.6, 0.0)],
+                health: Color::rgb(0.35, 0.7, 0.9),
+                low_health: Color::rgb(0.8, 0.4, 0.0),
+                meter: Color::rgb(0.95, 0.9, 0.25),
+                hitbox: Color::rgb(0.9, 0.6, 0.0),
+                hurtbox: Color::rgb(0.35, 0.7, 0.9),
+            },
+            // Reds read as dark for protanopes, so nothing relies on them.
+            HudPalette::Protanopia => PaletteColors {
+                teams: [Color::rgb(0.0, 0.45, 0.7), Color::rgb(0.95, 0.9, 0.25)],
+                health: Color::rgb(0.35, 0.7, 0.9),
+                low_health: Color::rgb(0.9, 0.6, 0.0),
+                meter: Color::rgb(0.8, 0.6, 0.7),
+                hitbox: Color::rgb(0.95, 0.9, 0.25),
+                hurtbox: Color::rgb(0.35, 0.7, 0.9),
+            },
+            HudPalette::Tritanopia => PaletteColors {
+                teams: [Color::rgb(0.9, 0.2, 0.2), Color::rgb(0.0, 0.75, 0.8)],
+                health: Color::rgb(0.9, 0.9, 0.9),
+                low_health: Color::rgb(0.9, 0.2, 0.2),
+                meter: Color::rgb(0.8, 0.3, 0.6),
+                hitbox: Color::rgb(0.9, 0.2, 0.2),
+                hurtbox: Color::rgb(0.0, 0.75, 0.8),
+            },
+        }
+    }
+}
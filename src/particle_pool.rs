@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy_hanabi::prelude::*;
+
+use crate::settings::Settings;
+
+// Instances made of each burst up front; a busy fight reuses them rather than
+// spawning more, so the GPU buffers are allocated once.
+const SLOTS_PER_BURST: usize = 8;
+// Particles allowed alive at once across every pooled burst, at full
+// particle density. Lower graphics quality shrinks it to match.
+pub const PARTICLE_BUDGET: u32 = 400;
+
+// A one-shot effect registered with the pool.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BurstKind(usize);
+
+struct Burst {
+    particles: u32,
+    lifetime: f32,
+}
+
+struct Slot {
+    entity: Entity,
+    kind: BurstKind,
+    // Real seconds the burst was last fired; None while it sits free.
+    fired: Option<f32>,
+}
+
+// Pre-spawned instances of every one-shot effect, handed out as bursts are
+// fired. When there are no free instances, or firing would take the live
+// particles over budget, the oldest bursts are cut short to make room.
+#[derive(Resource, Default)]
+pub struct ParticlePool {
+    bursts: Vec<Burst>,
+    slots: Vec<Slot>,
+    queued: Vec<(BurstKind, Vec3)>,
+}
+
+impl ParticlePool {
+    // Spawns the instances for an effect. It must be built with a spawner
+    // that doesn't fire on its own, as the pool decides when it goes off.
+    pub fn register(
+        &mut self,
+        commands: &mut Commands,
+        effect: Handle<EffectAsset>,
+        particles: u32,
+        lifetime: f32,
+    ) -> BurstKind {
+        let kind = BurstKind(self.bursts.len());
+        self.bursts.push(Burst {
+            particles,
+            lifetime,
+        });
+        for _ in 0..SLOTS_PER_BURST {
+            let entity = commands
+                .spawn((
+                    Name::new("pooled_burst"),
+                    ParticleEffectBundle {
+                        effect: ParticleEffect::new(effect.clone()),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ))
+                .id();
+            self.slots.push(Slot {
+                entity,
+                kind,
+                fired: None,
+            });
+        }
+        kind
+    }
+
+    // Queues a burst to go off at `position` this frame.
+    pub fn fire(&mut self, kind: BurstKind, position: Vec3) {
+        self.queued.push((kind, position));
+    }
+
+    fn live_particles(&self) -> u32 {
+        self.slots
+            .iter()
+            .filter(|slot| slot.fired.is_some())
+            .map(|slot| self.bursts[slot.kind.0].particles)
+            .sum()
+    }
+
+    fn oldest(&self, kind: Option<BurstKind>) -> Option<usize> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| kind.map_or(true, |kind| slot.kind == kind))
+            .filter_map(|(index, slot)| slot.fired.map(|fired| (index, fired)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+}
+
+fn retire(slot: &mut Slot, visibilities: &mut Query<&mut Visibility>) {
+    slot.fired = None;
+    if let Ok(mut visibility) = visibilities.get_mut(slot.entity) {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+pub fn fire_pooled_bursts(
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut pool: ResMut<ParticlePool>,
+    mut visibilities: Query<&mut Visibility>,
+    mut effects: Query<(&mut Transform, &mut EffectSpawner)>,
+) {
+    let now = time.elapsed_seconds();
+    let budget = (PARTICLE_BUDGET as f32 * settings.quality.particle_density()) as u32;
+    let pool = &mut *pool;
+    for slot in pool.slots.iter_mut() {
+        let lifetime = pool.bursts[slot.kind.0].lifetime;
+        if slot.fired.is_some_and(|fired| now - fired > lifetime) {
+            retire(slot, &mut visibilities);
+        }
+    }
+
+    for (kind, position) in std::mem::take(&mut pool.queued) {
+        let free = pool
+            .slots
+            .iter()
+            .position(|slot| slot.kind == kind && slot.fired.is_none());
+        let Some(index) = free.or_else(|| pool.oldest(Some(kind))) else {
+            continue;
+        };
+        retire(&mut pool.slots[index], &mut visibilities);
+        let cost = pool.bursts[kind.0].particles;
+        while pool.live_particles() + cost > budget {
+            let Some(oldest) = pool.oldest(None) else {
+                break;
+            };
+            retire(&mut pool.slots[oldest], &mut visibilities);
+        }
+
+        let slot = &mut pool.slots[index];
+        let Ok((mut transform, mut spawner)) = effects.get_mut(slot.entity) else {
+            continue;
+        };
+        transform.translation = position;
+        spawner.reset();
+        if let Ok(mut visibility) = visibilities.get_mut(slot.entity) {
+            *visibility = Visibility::Inherited;
+        }
+        slot.fired = Some(now);
+    }
+}
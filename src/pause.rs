@@ -0,0 +1,360 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Character, Player, Team},
+    combat::LimbKind,
+    input::{player_keys, PlayerKeys},
+    locale::Strings,
+    menu::{menu_item, Menu, MenuAction, MenuEvent, MenuItem},
+    moves::{AttackLevel, HitLevel, MoveId},
+    options::OptionsMenu,
+    roster::CharacterDef,
+    throws::THROW_DAMAGE,
+};
+
+// Not Escape, which closes the window.
+pub const PAUSE_MENU_KEY: KeyCode = KeyCode::Tab;
+// Over the fight's own menus, under the options panel it can open.
+const PAUSE_MENU_LAYER: i32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PausePage {
+    #[default]
+    Main,
+    MoveList,
+}
+
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    pub open: bool,
+    pub page: PausePage,
+    // Whether opening the menu stopped the clock, so closing it only starts
+    // it again if it was running before.
+    stopped_time: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PauseRow {
+    Resume,
+    MoveList,
+    Options,
+}
+
+const ROWS: [PauseRow; 3] = [PauseRow::Resume, PauseRow::MoveList, PauseRow::Options];
+
+#[derive(Component)]
+pub struct PausePanel;
+
+#[derive(Component)]
+pub struct MoveListPanel;
+
+// Where the move list's lines go, rebuilt each time the page opens.
+#[derive(Component)]
+pub struct MoveListBody;
+
+#[derive(Component)]
+pub struct PauseTitle;
+
+fn panel_text(value: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+fn panel_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            left: Val::Percent(30.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+        z_index: ZIndex::Global(105),
+        ..default()
+    }
+}
+
+pub fn setup_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            panel_node(),
+            Menu::vertical(ROWS.len(), PAUSE_MENU_LAYER),
+            PausePanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((panel_text("", 32.0), PauseTitle));
+            for index in 0..ROWS.len() {
+                panel.spawn(menu_item(index, panel_text("", 26.0)));
+            }
+        });
+    commands
+        .spawn((
+            panel_node(),
+            Menu::vertical(1, PAUSE_MENU_LAYER),
+            MoveListPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((panel_text("", 32.0), PauseTitle));
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                MoveListBody,
+            ));
+            panel.spawn(menu_item(0, panel_text("", 26.0)));
+        });
+}
+
+// The fight is over, so the menu goes and the clock runs again.
+pub fn close_pause_menu(
+    mut commands: Commands,
+    mut pause: ResMut<PauseMenu>,
+    mut time: ResMut<Time<Virtual>>,
+    panels: Query<Entity, Or<(With<PausePanel>, With<MoveListPanel>)>>,
+) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if pause.stopped_time {
+        time.unpause();
+    }
+    *pause = PauseMenu::default();
+}
+
+// The pause key opens and closes the menu. Cancel backs out a page at a time.
+// While it's open the clock is stopped, so the fight holds where it was.
+pub fn pause_menu_keys(
+    keys: Res<Input<KeyCode>>,
+    mut pause: ResMut<PauseMenu>,
+    mut options: ResMut<OptionsMenu>,
+    mut time: ResMut<Time<Virtual>>,
+    pause_panels: Query<(), With<PausePanel>>,
+    move_list_panels: Query<(), With<MoveListPanel>>,
+    mut events: EventReader<MenuEvent>,
+) {
+    if keys.just_pressed(PAUSE_MENU_KEY) {
+        pause.open = !pause.open;
+        pause.page = PausePage::Main;
+    }
+    for event in events.iter() {
+        if !pause.open {
+            continue;
+        }
+        if pause_panels.contains(event.menu) {
+            match (ROWS[event.item], event.action) {
+                (_, MenuAction::Cancel) | (PauseRow::Resume, MenuAction::Confirm) => {
+                    pause.open = false;
+                }
+                (PauseRow::MoveList, MenuAction::Confirm) => pause.page = PausePage::MoveList,
+                (PauseRow::Options, MenuAction::Confirm) => options.open = true,
+                _ => {}
+            }
+        } else if move_list_panels.contains(event.menu)
+            && matches!(event.action, MenuAction::Confirm | MenuAction::Cancel)
+        {
+            pause.page = PausePage::Main;
+        }
+    }
+
+    if pause.open && !time.is_paused() {
+        time.pause();
+        pause.stopped_time = true;
+    } else if !pause.open && pause.stopped_time {
+        time.unpause();
+        pause.stopped_time = false;
+    }
+}
+
+fn move_name_key(move_id: MoveId) -> &'static str {
+    match move_id {
+        MoveId::Punch => "move.punch",
+        MoveId::Kick => "move.kick",
+        MoveId::Elbow => "move.elbow",
+        MoveId::ShoulderCharge => "move.shoulder_charge",
+    }
+}
+
+fn move_keys(keys: &PlayerKeys, move_id: MoveId) -> String {
+    match move_id {
+        MoveId::Punch | MoveId::Elbow => format!("{:?}", keys.punch),
+        MoveId::Kick => format!("{:?}", keys.kick),
+        MoveId::ShoulderCharge => format!("{:?}+{:?}", keys.punch, keys.kick),
+    }
+}
+
+fn level_key(level: AttackLevel) -> &'static str {
+    match level {
+        AttackLevel::Light => "move_list.level.light",
+        AttackLevel::Medium => "move_list.level.medium",
+        AttackLevel::Heavy => "move_list.level.heavy",
+    }
+}
+
+fn hit_level_key(hit_level: HitLevel) -> &'static str {
+    match hit_level {
+        HitLevel::High => "move_list.hit.high",
+        HitLevel::Mid => "move_list.hit.mid",
+        HitLevel::Low => "move_list.hit.low",
+    }
+}
+
+// A line for each move and one under it for anything special about it. The
+// moves are whichever the character's model has an attacking limb for, in
+// the order they're listed there.
+fn move_list_lines(def: &CharacterDef, keys: &PlayerKeys, strings: &Strings) -> Vec<(String, f32)> {
+    let mut moves: Vec<MoveId> = Vec::new();
+    for limb in def.colliders.iter() {
+        if let LimbKind::Attack(move_id) = limb.kind {
+            if !moves.contains(&move_id) {
+                moves.push(move_id);
+            }
+        }
+    }
+    let mut lines = Vec::new();
+    for move_id in moves {
+        let data = move_id.data();
+        lines.push((
+            strings.fill(
+                "move_list.move",
+                &[
+                    ("name", &strings.get(move_name_key(move_id))),
+                    ("input", &move_keys(keys, move_id)),
+                    ("damage", &data.damage),
+                    ("hit", &strings.get(hit_level_key(data.hit_level))),
+                    ("level", &strings.get(level_key(data.level))),
+                ],
+            ),
+            22.0,
+        ));
+        let mut notes: Vec<String> = [
+            (data.launcher, "move_list.launcher"),
+            (data.armor, "move_list.armor"),
+            (data.guard_break, "move_list.guard_break"),
+            (data.ground_bounce.is_some(), "move_list.ground_bounce"),
+        ]
+        .into_iter()
+        .filter(|(has, _)| *has)
+        .map(|(_, key)| strings.get(key))
+        .collect();
+        if let Some(point_blank) = data.point_blank {
+            let name = strings.get(move_name_key(point_blank.move_id));
+            notes.push(strings.fill("move_list.point_blank", &[("move", &name)]));
+        }
+        if !data.cancels_into.is_empty() {
+            let into: Vec<String> = data
+                .cancels_into
+                .iter()
+                .map(|into| strings.get(move_name_key(*into)))
+                .collect();
+            notes.push(strings.fill("move_list.cancels", &[("moves", &into.join(", "))]));
+        }
+        if !notes.is_empty() {
+            lines.push((format!("    {}", notes.join(", ")), 18.0));
+        }
+    }
+    // Every character can throw; it's not a limb's move.
+    let input = format!("{:?}", keys.throw);
+    lines.push((
+        strings.fill(
+            "move_list.throw",
+            &[("input", &input), ("damage", &THROW_DAMAGE)],
+        ),
+        22.0,
+    ));
+    lines.push((
+        format!(
+            "    {}",
+            strings.fill("move_list.throw_break", &[("input", &input)])
+        ),
+        18.0,
+    ));
+    lines
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_pause_menu(
+    mut commands: Commands,
+    pause: Res<PauseMenu>,
+    strings: Strings,
+    characters: Res<Assets<CharacterDef>>,
+    players: Query<(&Character, &Team), With<Player>>,
+    mut pause_panels: Query<&mut Style, (With<PausePanel>, Without<MoveListPanel>)>,
+    mut move_list_panels: Query<&mut Style, With<MoveListPanel>>,
+    bodies: Query<Entity, With<MoveListBody>>,
+    mut titles: Query<(&Parent, &mut Text), (With<PauseTitle>, Without<MenuItem>)>,
+    mut items: Query<(&MenuItem, &Parent, &mut Text)>,
+) {
+    if !pause.is_changed() && !strings.changed() {
+        return;
+    }
+    let shown = |page: PausePage| {
+        if pause.open && pause.page == page {
+            Display::Flex
+        } else {
+            Display::None
+        }
+    };
+    for mut style in pause_panels.iter_mut() {
+        style.display = shown(PausePage::Main);
+    }
+    for mut style in move_list_panels.iter_mut() {
+        style.display = shown(PausePage::MoveList);
+    }
+
+    // Player one's character, which is who's at the keys.
+    let def = players
+        .iter()
+        .find(|(_, team)| **team == Team::One)
+        .and_then(|(character, _)| characters.get(&character.0));
+    for (parent, mut text) in titles.iter_mut() {
+        text.sections[0].value = if pause_panels.contains(parent.get()) {
+            strings.get("pause.title")
+        } else {
+            let name = def.map_or(String::new(), |def| def.name.clone());
+            strings.fill("move_list.title", &[("name", &name)])
+        };
+    }
+    for (item, parent, mut text) in items.iter_mut() {
+        if pause_panels.contains(parent.get()) {
+            text.sections[0].value = strings.get(match ROWS[item.0] {
+                PauseRow::Resume => "pause.resume",
+                PauseRow::MoveList => "pause.move_list",
+                PauseRow::Options => "pause.options",
+            });
+        } else if move_list_panels.contains(parent.get()) {
+            text.sections[0].value = strings.get("move_list.back");
+        }
+    }
+
+    if !pause.open || pause.page != PausePage::MoveList {
+        return;
+    }
+    let lines = def.map_or_else(Vec::new, |def| {
+        move_list_lines(def, player_keys(Team::One), &strings)
+    });
+    for body in bodies.iter() {
+        commands
+            .entity(body)
+            .despawn_descendants()
+            .with_children(|body| {
+                for (line, font_size) in lines.iter() {
+                    body.spawn(panel_text(line, *font_size));
+                }
+            });
+    }
+}
@@ -0,0 +1,245 @@
+use std::path::PathBuf;
+
+use bevy::{
+    input::mouse::MouseWheel, prelude::*, render::renderer::RenderDevice, tasks::IoTaskPool,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    camera::Cameraman,
+    capture::{capture_path, offscreen_camera, offscreen_image, Readback},
+    character::Character,
+    depth_of_field::DepthOfField,
+};
+
+pub const PHOTO_MODE_KEY: KeyCode = KeyCode::F10;
+pub const HIDE_HUD_KEY: KeyCode = KeyCode::H;
+pub const DEPTH_OF_FIELD_KEY: KeyCode = KeyCode::F;
+pub const PHOTO_KEY: KeyCode = KeyCode::Return;
+// Photos are rendered offscreen at this many times the window's resolution.
+pub const PHOTO_SCALE: u32 = 2;
+// Frames a photo camera draws before it's read back, for its image to reach
+// the GPU and anything it's the first to draw to finish compiling.
+const PHOTO_FRAMES: u32 = 4;
+
+const MOVE_SPEED: f32 = 4.0;
+const TURN_SPEED: f32 = 1.2;
+const ROLL_SPEED: f32 = 0.8;
+const FOV_STEP: f32 = 0.05;
+const MIN_FOV: f32 = 0.2;
+const MAX_FOV: f32 = 1.6;
+
+// Everything photo mode changed, so leaving it puts the fight back exactly as
+// it was.
+#[derive(Resource, Default)]
+pub struct PhotoMode {
+    pub active: bool,
+    pub hud_hidden: bool,
+    saved_transform: Transform,
+    saved_fov: f32,
+    was_paused: bool,
+    pub depth_of_field: bool,
+}
+
+// An offscreen camera taking a photo, counting down the frames until it's
+// read back.
+#[derive(Component)]
+pub struct PhotoCamera {
+    frames: u32,
+    image: Handle<Image>,
+    path: PathBuf,
+}
+
+pub fn photo_mode_inactive(photo: Res<PhotoMode>) -> bool {
+    !photo.active
+}
+
+pub fn toggle_photo_mode(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut photo: ResMut<PhotoMode>,
+    mut time: ResMut<Time<Virtual>>,
+    mut cameras: Query<(Entity, &mut Transform, &mut Projection), With<Cameraman>>,
+    mut huds: Query<&mut Visibility, (With<Node>, Without<Parent>)>,
+) {
+    if !keys.just_pressed(PHOTO_MODE_KEY) {
+        return;
+    }
+    let Ok((camera, mut transform, mut projection)) = cameras.get_single_mut() else {
+        return;
+    };
+    let Projection::Perspective(perspective) = projection.as_mut() else {
+        return;
+    };
+    photo.active = !photo.active;
+    if photo.active {
+        photo.saved_transform = *transform;
+        photo.saved_fov = perspective.fov;
+        photo.was_paused = time.is_paused();
+        time.pause();
+    } else {
+        *transform = photo.saved_transform;
+        perspective.fov = photo.saved_fov;
+        if !photo.was_paused {
+            time.unpause();
+        }
+        photo.hud_hidden = false;
+        for mut visibility in huds.iter_mut() {
+            *visibility = Visibility::Inherited;
+        }
+        photo.depth_of_field = false;
+        commands.entity(camera).remove::<DepthOfField>();
+    }
+}
+
+// Free camera: WASD to move, Q/E down/up, arrows to look, Z/X to roll and the
+// mouse wheel for field of view. Uses real time since the game is paused.
+pub fn fly_photo_camera(
+    time: Res<Time<Real>>,
+    keys: Res<Input<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    photo: Res<PhotoMode>,
+    mut cameras: Query<(&mut Transform, &mut Projection), With<Cameraman>>,
+) {
+    if !photo.active {
+        wheel.clear();
+        return;
+    }
+    let Ok((mut transform, mut projection)) = cameras.get_single_mut() else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    let axis = |positive: KeyCode, negative: KeyCode| {
+        keys.pressed(positive) as i32 as f32 - keys.pressed(negative) as i32 as f32
+    };
+
+    let movement = transform.forward() * axis(KeyCode::W, KeyCode::S)
+        + transform.right() * axis(KeyCode::D, KeyCode::A)
+        + Vec3::Y * axis(KeyCode::E, KeyCode::Q);
+    transform.translation += movement * MOVE_SPEED * dt;
+
+    transform.rotate_y(axis(KeyCode::Left, KeyCode::Right) * TURN_SPEED * dt);
+    transform.rotate_local_x(axis(KeyCode::Up, KeyCode::Down) * TURN_SPEED * dt);
+    transform.rotate_local_z(axis(KeyCode::Z, KeyCode::X) * ROLL_SPEED * dt);
+
+    if let Projection::Perspective(perspective) = projection.as_mut() {
+        for event in wheel.iter() {
+            perspective.fov =
+                (perspective.fov - event.y.signum() * FOV_STEP).clamp(MIN_FOV, MAX_FOV);
+        }
+    }
+}
+
+// With depth of field on, whatever's halfway between the fighters stays in
+// focus wherever the camera goes.
+pub fn focus_photo_camera(
+    fighters: Query<&Transform, (With<Character>, Without<Cameraman>)>,
+    mut cameras: Query<(&Transform, &Projection, &mut DepthOfField), With<Cameraman>>,
+) {
+    let Ok((transform, projection, mut depth_of_field)) = cameras.get_single_mut() else {
+        return;
+    };
+    let positions: Vec<Vec3> = fighters.iter().map(|fighter| fighter.translation).collect();
+    if !positions.is_empty() {
+        let subject = positions.iter().sum::<Vec3>() / positions.len() as f32;
+        depth_of_field.focus_distance = transform.translation.distance(subject);
+    }
+    if let Projection::Perspective(perspective) = projection {
+        depth_of_field.near = perspective.near;
+    }
+}
+
+// H hides the HUD, F toggles depth of field and Return takes a photo.
+#[allow(clippy::too_many_arguments)]
+pub fn photo_mode_keys(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut photo: ResMut<PhotoMode>,
+    mut huds: Query<&mut Visibility, (With<Node>, Without<Parent>)>,
+    cameras: Query<Entity, With<Cameraman>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    if !photo.active {
+        return;
+    }
+    if keys.just_pressed(HIDE_HUD_KEY) {
+        photo.hud_hidden = !photo.hud_hidden;
+        for mut visibility in huds.iter_mut() {
+            *visibility = if photo.hud_hidden {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+        }
+    }
+    if keys.just_pressed(DEPTH_OF_FIELD_KEY) {
+        photo.depth_of_field = !photo.depth_of_field;
+        for camera in cameras.iter() {
+            if photo.depth_of_field {
+                commands.entity(camera).insert(DepthOfField::default());
+            } else {
+                commands.entity(camera).remove::<DepthOfField>();
+            }
+        }
+    }
+    if keys.just_pressed(PHOTO_KEY) {
+        let Ok(window) = windows.get_single() else {
+            return;
+        };
+        let Some(path) = capture_path("photo", "png") else {
+            return;
+        };
+        // As big as the GPU allows, keeping the window's shape.
+        let largest = render_device.limits().max_texture_dimension_2d;
+        let (width, height) = (window.physical_width(), window.physical_height());
+        let scale = (PHOTO_SCALE as f32)
+            .min(largest as f32 / width.max(height).max(1) as f32)
+            .max(1.0);
+        let size = |side: u32| ((side as f32 * scale) as u32).max(1);
+        let image = offscreen_image(&mut images, size(width), size(height));
+        commands.spawn((
+            offscreen_camera(image.clone()),
+            PhotoCamera {
+                frames: PHOTO_FRAMES,
+                image,
+                path,
+            },
+        ));
+    }
+}
+
+// Reads each photo back on its last frame and saves it off the main thread,
+// then clears the camera away.
+pub fn develop_photos(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut photos: Query<(Entity, &mut PhotoCamera)>,
+) {
+    for (entity, mut photo) in photos.iter_mut() {
+        photo.frames = photo.frames.saturating_sub(1);
+        match photo.frames {
+            1 => {
+                let path = photo.path.clone();
+                let readback = Readback::new(photo.image.clone(), move |pixels| {
+                    let path = path.clone();
+                    IoTaskPool::get()
+                        .spawn(async move {
+                            match pixels.save(&path) {
+                                Ok(()) => info!("Saved photo to {}", path.display()),
+                                Err(err) => warn!("Can't write {}: {err}", path.display()),
+                            }
+                        })
+                        .detach();
+                });
+                commands.entity(entity).insert(readback);
+            }
+            0 => {
+                images.remove(&photo.image);
+                commands.entity(entity).despawn();
+            }
+            _ => {}
+        }
+    }
+}
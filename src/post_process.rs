@@ -0,0 +1,298 @@
+use bevy::{
+    core_pipeline::{core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state},
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner},
+        render_resource::{
+            BindGroupEntries, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+            BindingType, BufferBindingType, CachedRenderPipelineId, ColorTargetState, ColorWrites,
+            FragmentState, MultisampleState, Operations, PipelineCache, PrimitiveState,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, TextureFormat,
+            TextureSampleType, TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::ViewTarget,
+        RenderApp,
+    },
+};
+
+use crate::{
+    combat::{HitLanded, HEAVY_HIT_DAMAGE},
+    round::RoundEnded,
+    settings::Settings,
+    GameSet,
+};
+
+pub const POST_PROCESS_SHADER: &str = "shaders/post_process.wgsl";
+
+const PULSE_DURATION: f32 = 0.3;
+const PULSE_ABERRATION: f32 = 0.012;
+const PULSE_SATURATION: f32 = 1.6;
+// With reduced flashing: no colour fringing, a mild saturation swell.
+const GENTLE_PULSE_SATURATION: f32 = 1.15;
+
+// Per-camera settings for the pass that runs after tonemapping. Field order
+// must match `PostProcessSettings` in the shader.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct PostProcessSettings {
+    pub vignette: f32,
+    pub vignette_radius: f32,
+    pub aberration: f32,
+    pub saturation: f32,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        PostProcessSettings {
+            vignette: 0.35,
+            vignette_radius: 0.5,
+            aberration: 0.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+// A short burst of chromatic aberration and extra saturation for the hits
+// that deserve it.
+#[derive(Resource)]
+pub struct ImpactPulse {
+    pub timer: Timer,
+}
+
+impl Default for ImpactPulse {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(PULSE_DURATION, TimerMode::Once);
+        timer.tick(timer.duration());
+        ImpactPulse { timer }
+    }
+}
+
+pub fn start_impact_pulse(
+    mut pulse: ResMut<ImpactPulse>,
+    mut hits: EventReader<HitLanded>,
+    mut ended: EventReader<RoundEnded>,
+) {
+    let heavy_hit = hits
+        .iter()
+        .any(|hit| !hit.blocked && hit.damage >= HEAVY_HIT_DAMAGE);
+    let knockout = ended.iter().any(|round| round.reason.beaten());
+    if heavy_hit || knockout {
+        pulse.timer.reset();
+    }
+}
+
+pub fn apply_impact_pulse(
+    time: Res<Time<Real>>,
+    game_settings: Res<Settings>,
+    mut pulse: ResMut<ImpactPulse>,
+    mut settings: Query<&mut PostProcessSettings>,
+) {
+    pulse.timer.tick(time.delta());
+    let strength = 1.0 - pulse.timer.percent();
+    let (aberration, saturation) = if game_settings.reduce_flashing {
+        (0.0, GENTLE_PULSE_SATURATION)
+    } else {
+        (PULSE_ABERRATION, PULSE_SATURATION)
+    };
+    for mut settings in settings.iter_mut() {
+        settings.aberration = aberration * strength;
+        settings.saturation = 1.0 + (saturation - 1.0) * strength;
+    }
+}
+
+pub struct PostProcessPlugin;
+
+impl Plugin for PostProcessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<PostProcessSettings>::default(),
+            UniformComponentPlugin::<PostProcessSettings>::default(),
+        ))
+        .init_resource::<ImpactPulse>()
+        .add_systems(
+            Update,
+            (start_impact_pulse, apply_impact_pulse)
+                .chain()
+                .in_set(GameSet::Presentation),
+        );
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(
+                core_3d::graph::NAME,
+                PostProcessNode::NAME,
+            )
+            .add_render_graph_edges(
+                core_3d::graph::NAME,
+                &[
+                    core_3d::graph::node::TONEMAPPING,
+                    PostProcessNode::NAME,
+                    core_3d::graph::node::END_MAIN_PASS_POST_PROCESSING,
+                ],
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<PostProcessPipeline>();
+    }
+}
+
+#[derive(Default)]
+pub struct PostProcessNode;
+
+impl PostProcessNode {
+    pub const NAME: &'static str = "post_process";
+}
+
+impl ViewNode for PostProcessNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<PostProcessSettings>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let post_process_pipeline = world.resource::<PostProcessPipeline>();
+        let pipeline_id = if view_target.is_hdr() {
+            post_process_pipeline.hdr_pipeline
+        } else {
+            post_process_pipeline.pipeline
+        };
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(pipeline_id)
+        else {
+            return Ok(());
+        };
+        let Some(settings_binding) = world
+            .resource::<ComponentUniforms<PostProcessSettings>>()
+            .uniforms()
+            .binding()
+        else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "post_process_bind_group",
+            &post_process_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &post_process_pipeline.sampler,
+                settings_binding,
+            )),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("post_process_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct PostProcessPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline: CachedRenderPipelineId,
+    hdr_pipeline: CachedRenderPipelineId,
+}
+
+impl FromWorld for PostProcessPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_process_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(PostProcessSettings::min_size()),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let shader = world.resource::<AssetServer>().load(POST_PROCESS_SHADER);
+
+        // HDR cameras keep a float target through tonemapping, so the pass
+        // needs a pipeline for each format.
+        let mut queue = |format: TextureFormat| {
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("post_process_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    push_constant_ranges: Vec::new(),
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: shader.clone(),
+                        shader_defs: Vec::new(),
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                })
+        };
+        let pipeline = queue(TextureFormat::bevy_default());
+        let hdr_pipeline = queue(ViewTarget::TEXTURE_FORMAT_HDR);
+
+        PostProcessPipeline {
+            layout,
+            sampler,
+            pipeline,
+            hdr_pipeline,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::{Player, Team},
+    combat::FightEnded,
+    round::{Round, RoundEnded},
+    data::{read_ron_file, write_ron_file},
+    GameMode,
+};
+
+pub const PROFILE_PATH: &str = "profile.ron";
+
+pub const ROUND_WIN_POINTS: u32 = 1000;
+pub const PERFECT_BONUS: u32 = 5000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Achievement {
+    FirstWin,
+    TenWins,
+    // Won a match without taking any damage.
+    Flawless,
+    ArcadeClear,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Unlock {
+    AlternateSkins,
+    NightStage,
+}
+
+impl Unlock {
+    pub fn required(self) -> Achievement {
+        match self {
+            Unlock::AlternateSkins => Achievement::FirstWin,
+            Unlock::NightStage => Achievement::TenWins,
+        }
+    }
+}
+
+// Lifetime record for whoever plays on this machine, persisted to
+// PROFILE_PATH after every fight.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub matches_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub arcade_completions: u32,
+    pub best_score: u32,
+    // Most opponents beaten in one gauntlet run.
+    pub best_gauntlet: u32,
+    pub achievements: BTreeSet<Achievement>,
+}
+
+// Points the player has built up this session, kept across matches.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct ArcadeScore {
+    pub points: u32,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        read_ron_file(PROFILE_PATH)
+    }
+
+    pub fn save(&self) {
+        write_ron_file(PROFILE_PATH, self);
+    }
+
+    pub fn is_unlocked(&self, unlock: Unlock) -> bool {
+        self.achievements.contains(&unlock.required())
+    }
+
+    pub fn record_arcade_clear(&mut self) {
+        self.arcade_completions += 1;
+        self.award(Achievement::ArcadeClear);
+    }
+
+    fn award(&mut self, achievement: Achievement) {
+        if self.achievements.insert(achievement) {
+            info!("Achievement unlocked: {achievement:?}");
+        }
+    }
+}
+
+// Training fights don't count towards the record.
+pub fn record_fight_result(
+    mode: Res<GameMode>,
+    mut profile: ResMut<Profile>,
+    score: Res<ArcadeScore>,
+    round: Res<Round>,
+    mut ended: EventReader<FightEnded>,
+    players: Query<&Team, With<Player>>,
+) {
+    if *mode != GameMode::Versus {
+        ended.clear();
+        return;
+    }
+    let Ok(team) = players.get_single() else {
+        return;
+    };
+    for fight in ended.iter() {
+        profile.matches_played += 1;
+        if fight.winner == Some(*team) {
+            profile.wins += 1;
+            profile.award(Achievement::FirstWin);
+            if profile.wins >= 10 {
+                profile.award(Achievement::TenWins);
+            }
+            if round.flawless(*team) {
+                profile.award(Achievement::Flawless);
+            }
+        } else {
+            profile.losses += 1;
+        }
+        profile.best_score = profile.best_score.max(score.points);
+        profile.save();
+    }
+}
+
+// Each round the player takes is worth points, with a bonus for a perfect.
+pub fn score_rounds(
+    mode: Res<GameMode>,
+    mut score: ResMut<ArcadeScore>,
+    mut ended: EventReader<RoundEnded>,
+    players: Query<&Team, With<Player>>,
+) {
+    if *mode != GameMode::Versus {
+        ended.clear();
+        return;
+    }
+    let Ok(team) = players.get_single() else {
+        return;
+    };
+    for round in ended.iter().filter(|round| round.winner == Some(*team)) {
+        score.points += ROUND_WIN_POINTS;
+        if round.perfect {
+            score.points += PERFECT_BONUS;
+        }
+    }
+}
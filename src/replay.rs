@@ -0,0 +1,381 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    character::{spawn_fighters, Character, Enemy, Player, Team},
+    combat::HitLanded,
+    hud::Hud,
+    input::ControlInput,
+    locale::Strings,
+    rng::GameRng,
+    roster::MatchSetup,
+    round::{start_match, MatchRules, RoundEnded, RoundStarted},
+    snapshot::{roll_back, Snapshots},
+    transition::{ScreenTransition, TransitionConfig, TransitionCovered},
+    versus::RematchPrompt,
+    AppState, SimulationTick, SIMULATION_HZ,
+};
+
+pub const WATCH_REPLAY_KEY: KeyCode = KeyCode::R;
+const TIMELINE_HEIGHT: f32 = 18.0;
+const MARKER_WIDTH: f32 = 6.0;
+
+// Something worth jumping to in a replay.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimelineKind {
+    RoundStart,
+    Hit,
+    KnockOut,
+}
+
+impl TimelineKind {
+    fn color(self) -> Color {
+        match self {
+            TimelineKind::RoundStart => Color::rgb(1.0, 0.85, 0.3),
+            TimelineKind::Hit => Color::rgba(1.0, 1.0, 1.0, 0.7),
+            TimelineKind::KnockOut => Color::rgb(0.9, 0.15, 0.15),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimelineEvent {
+    pub tick: u32,
+    pub kind: TimelineKind,
+}
+
+// The last fight, kept so it can be watched again: how it was set up, both
+// sides' input on every simulation tick, and the moments worth seeking to.
+// Played back from the same seed, the fighters make the same moves on the
+// same ticks; see `Snapshots` for how closely the fight then follows.
+#[derive(Resource, Default)]
+pub struct Replay {
+    seed: u64,
+    setup: MatchSetup,
+    rules: Option<MatchRules>,
+    pub frames: Vec<[ControlInput; 2]>,
+    pub events: Vec<TimelineEvent>,
+    // Simulation ticks since the fight being recorded or played began, and
+    // the simulation tick it began on.
+    pub tick: u32,
+    started_at: u32,
+    pub playing: bool,
+    // The tick playback is heading for after a marker was picked.
+    pub seek: Option<u32>,
+}
+
+// Results, scores and records only count for fights actually played.
+pub fn live_match(replay: Res<Replay>) -> bool {
+    !replay.playing
+}
+
+// Runs once the match has been seeded. A new fight starts a new recording;
+// a replay puts back the setup the player had before it borrowed the seed.
+pub fn begin_replay(
+    rng: Res<GameRng>,
+    rules: Res<MatchRules>,
+    sim_tick: Res<SimulationTick>,
+    mut setup: ResMut<MatchSetup>,
+    mut replay: ResMut<Replay>,
+) {
+    replay.tick = 0;
+    replay.started_at = sim_tick.0;
+    if replay.playing {
+        setup.seed = replay.setup.seed;
+        return;
+    }
+    replay.seed = rng.seed();
+    replay.setup = setup.clone();
+    replay.rules = Some(*rules);
+    replay.frames.clear();
+    replay.events.clear();
+}
+
+fn team_index(team: Team) -> usize {
+    match team {
+        Team::One => 0,
+        Team::Two => 1,
+    }
+}
+
+// Every simulation tick of a fight, after the AI and devices have had their
+// say and before anything acts on it: records what each side asked for, or
+// while replaying, swaps in what they asked for the first time around.
+pub fn sync_replay_inputs(
+    mut replay: ResMut<Replay>,
+    mut fighters: Query<(&Team, &mut ControlInput), Or<(With<Player>, With<Enemy>)>>,
+) {
+    let tick = replay.tick as usize;
+    if replay.playing {
+        if let Some(frame) = replay.frames.get(tick) {
+            for (team, mut input) in fighters.iter_mut() {
+                *input = frame[team_index(*team)];
+            }
+        }
+    } else {
+        let mut frame = [ControlInput::default(); 2];
+        for (team, input) in fighters.iter() {
+            frame[team_index(*team)] = *input;
+        }
+        replay.frames.push(frame);
+    }
+    replay.tick += 1;
+}
+
+// On the tick, once the round has moved on, so each marker is stamped with
+// the tick its event happened on.
+pub fn log_timeline_events(
+    mut replay: ResMut<Replay>,
+    mut hits: EventReader<HitLanded>,
+    mut round_started: EventReader<RoundStarted>,
+    mut round_ended: EventReader<RoundEnded>,
+) {
+    let tick = replay.tick;
+    let mut events: Vec<TimelineKind> = round_started
+        .iter()
+        .map(|_| TimelineKind::RoundStart)
+        .collect();
+    events.extend(hits.iter().map(|_| TimelineKind::Hit));
+    events.extend(
+        round_ended
+            .iter()
+            .filter(|round| round.reason.beaten())
+            .map(|_| TimelineKind::KnockOut),
+    );
+    if replay.playing {
+        return;
+    }
+    replay
+        .events
+        .extend(events.into_iter().map(|kind| TimelineEvent { tick, kind }));
+}
+
+pub fn show_replay_prompt(mut commands: Commands, strings: Strings, replay: Res<Replay>) {
+    if replay.frames.is_empty() {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            strings.get("replay.watch"),
+            TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(15.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        }),
+        RematchPrompt,
+    ));
+}
+
+// From the result screen, fades out, clears the fight away and fights it
+// again from the recording, with the same seed and rules.
+#[allow(clippy::too_many_arguments)]
+pub fn watch_replay(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut starting: Local<bool>,
+    mut transition: ResMut<ScreenTransition>,
+    mut covered: EventReader<TransitionCovered>,
+    mut replay: ResMut<Replay>,
+    mut setup: ResMut<MatchSetup>,
+    mut rules: ResMut<MatchRules>,
+    mut next_state: ResMut<NextState<AppState>>,
+    leftovers: Query<Entity, Or<(With<Character>, With<Hud>, With<RematchPrompt>)>>,
+) {
+    if !*starting && keys.just_pressed(WATCH_REPLAY_KEY) && !replay.frames.is_empty() {
+        *starting = true;
+        transition.cover(TransitionConfig::default());
+    }
+    if !*starting || covered.iter().next().is_none() {
+        return;
+    }
+    for entity in leftovers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *starting = false;
+    *setup = MatchSetup {
+        seed: Some(replay.seed),
+        ..replay.setup.clone()
+    };
+    if let Some(recorded) = replay.rules {
+        *rules = recorded;
+    }
+    replay.playing = true;
+    replay.seek = None;
+    next_state.set(AppState::Fight);
+}
+
+#[derive(Component)]
+pub struct ReplayPanel;
+
+#[derive(Component)]
+pub struct ReplayPlayhead;
+
+// A point on the scrubber that seeks to its tick when clicked.
+#[derive(Component)]
+pub struct TimelineMarker(pub u32);
+
+pub fn setup_replay_panel(mut commands: Commands, strings: Strings, replay: Res<Replay>) {
+    if !replay.playing {
+        return;
+    }
+    let length = replay.frames.len().max(1) as f32;
+    let at = |tick: u32| Val::Percent(tick as f32 / length * 100.0);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(48.0),
+                    left: Val::Percent(10.0),
+                    width: Val::Percent(80.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ReplayPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section(
+                strings.get("replay.title"),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(TIMELINE_HEIGHT),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                    ..default()
+                })
+                .with_children(|bar| {
+                    for event in &replay.events {
+                        bar.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: at(event.tick),
+                                    width: Val::Px(MARKER_WIDTH),
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                background_color: event.kind.color().into(),
+                                ..default()
+                            },
+                            TimelineMarker(event.tick),
+                        ));
+                    }
+                    bar.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: Val::Px(2.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::CYAN.into(),
+                            ..default()
+                        },
+                        ReplayPlayhead,
+                    ));
+                });
+        });
+}
+
+// The fight is over, so any seek still under way is dropped.
+pub fn close_replay_panel(
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    panels: Query<Entity, With<ReplayPanel>>,
+) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if replay.seek.take().is_some() {
+        commands.insert_resource(TimeUpdateStrategy::Automatic);
+    }
+}
+
+pub fn update_replay_panel(
+    mut replay: ResMut<Replay>,
+    markers: Query<(&Interaction, &TimelineMarker), Changed<Interaction>>,
+    mut playheads: Query<&mut Style, With<ReplayPlayhead>>,
+) {
+    for (interaction, marker) in markers.iter() {
+        if *interaction == Interaction::Pressed {
+            replay.seek = Some(marker.0);
+        }
+    }
+    let length = replay.frames.len().max(1) as f32;
+    for mut style in playheads.iter_mut() {
+        style.left = Val::Percent(replay.tick.min(length as u32) as f32 / length * 100.0);
+    }
+}
+
+// Playback rolls back to the latest snapshot at or before the tick picked,
+// unless it's already closer; with nothing to roll back to, the fight starts
+// over from its seed. From there it runs exactly one tick per frame until it
+// gets to the tick, so none are skipped on the way.
+pub fn seek_replay(world: &mut World) {
+    let Replay {
+        tick,
+        seek,
+        started_at,
+        ..
+    } = *world.resource::<Replay>();
+    let Some(target) = seek else {
+        return;
+    };
+    let nearest = world.resource::<Snapshots>().latest_at(started_at + target);
+    match nearest {
+        Some(at) if tick > target || at > started_at + tick => {
+            if let Some(at) = roll_back(world, at) {
+                world.resource_mut::<Replay>().tick = at - started_at;
+            }
+        }
+        None if tick > target => restart_replay(world),
+        _ => {}
+    }
+
+    if world.resource::<Replay>().tick == target {
+        world.resource_mut::<Replay>().seek = None;
+        world.insert_resource(TimeUpdateStrategy::Automatic);
+        return;
+    }
+    world.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / SIMULATION_HZ,
+    )));
+}
+
+fn restart_replay(world: &mut World) {
+    let fighters: Vec<Entity> = world
+        .query_filtered::<Entity, With<Character>>()
+        .iter(world)
+        .collect();
+    for fighter in fighters {
+        world.entity_mut(fighter).despawn_recursive();
+    }
+    world.resource_mut::<Snapshots>().clear();
+    let seed = world.resource::<Replay>().seed;
+    world.resource_mut::<MatchSetup>().seed = Some(seed);
+    world.run_system_once(spawn_fighters);
+    world.run_system_once(start_match);
+    world.run_system_once(begin_replay);
+}
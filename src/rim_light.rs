@@ -0,0 +1,41 @@
+use bevy::{
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+
+use crate::{character::Team, palette::PaletteColors};
+
+pub const RIM_LIGHT_SHADER: &str = "shaders/rim_light.wgsl";
+
+// Fresnel rim added on top of the fighter's normal PBR shading so they stand
+// out from the background.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct RimLight {
+    #[uniform(100)]
+    pub color: Color,
+    // Higher is a thinner rim.
+    #[uniform(100)]
+    pub power: f32,
+}
+
+impl MaterialExtension for RimLight {
+    fn fragment_shader() -> ShaderRef {
+        RIM_LIGHT_SHADER.into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        RIM_LIGHT_SHADER.into()
+    }
+}
+
+pub type FighterMaterial = ExtendedMaterial<StandardMaterial, RimLight>;
+
+impl Team {
+    pub fn rim_light(&self, colors: &PaletteColors) -> RimLight {
+        RimLight {
+            color: colors.team(*self),
+            power: 3.0,
+        }
+    }
+}
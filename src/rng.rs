@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Mixed into the seed for the cosmetic stream so it doesn't mirror the
+// simulation's draws.
+const COSMETIC_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// Every random number the game uses, from one seed. The simulation stream
+// decides anything that can change a fight (AI, the training dummy) and is
+// only drawn from on the fixed tick, so the same seed and inputs draw the
+// same numbers on the same ticks. Sounds and effects draw from their own
+// stream, so however often they run they never shift the simulation's
+// sequence.
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    seed: u64,
+    simulation: StdRng,
+    cosmetic: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::thread_rng().gen())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            simulation: StdRng::seed_from_u64(seed),
+            cosmetic: StdRng::seed_from_u64(seed ^ COSMETIC_SALT),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        *self = GameRng::new(seed);
+    }
+
+    pub fn simulation(&mut self) -> &mut StdRng {
+        &mut self.simulation
+    }
+
+    pub fn cosmetic(&mut self) -> &mut StdRng {
+        &mut self.cosmetic
+    }
+}
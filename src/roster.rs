@@ -0,0 +1,247 @@
+use bevy::{asset::LoadState, prelude::*, reflect::TypePath};
+use serde::Deserialize;
+
+use crate::{
+    ai::Difficulty,
+    animation::AnimationGraph,
+    cloth::ClothDef,
+    combat::LimbCollider,
+    foot_ik::LegBones,
+    stage::TimeOfDay,
+    tuning::{tuning_ready, Tuning, TuningHandle},
+    voice::VoiceSet,
+    AppState, GameMode,
+};
+
+pub const ROSTER_PATH: &str = "game.roster.ron";
+
+// Character ids in select-screen order. Each id names a folder under
+// `characters/` holding `<id>.character.ron` and the assets it refers to.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct RosterDef {
+    pub characters: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationPaths {
+    pub idle: String,
+    pub punch: String,
+    pub kick: String,
+    pub run_forwards: String,
+    pub walk_backwards: String,
+    // Characters without guard, crouch or jump clips hold their idle instead.
+    #[serde(default)]
+    pub block: Option<String>,
+    #[serde(default)]
+    pub crouch: Option<String>,
+    #[serde(default)]
+    pub jump: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SfxPaths {
+    pub punch: String,
+    pub kick: String,
+    pub pain: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PaletteDef {
+    pub name: String,
+    pub tint: (f32, f32, f32),
+}
+
+// Shown as bars on the select screen, out of `MAX_STAT`. Purely descriptive;
+// the simulation doesn't read them.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CharacterStats {
+    pub power: u32,
+    pub speed: u32,
+    pub defense: u32,
+}
+
+pub const MAX_STAT: u32 = 5;
+
+impl Default for CharacterStats {
+    fn default() -> Self {
+        CharacterStats {
+            power: 3,
+            speed: 3,
+            defense: 3,
+        }
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct CharacterDef {
+    pub name: String,
+    pub scene: String,
+    pub animations: AnimationPaths,
+    pub animation_graph: AnimationGraph,
+    pub sfx: SfxPaths,
+    pub colliders: Vec<LimbCollider>,
+    // Bone the attack clips carry forward. Its travel moves the whole fighter
+    // instead, so the mesh and its colliders stay where the fighter is.
+    #[serde(default)]
+    pub root_bone: Option<String>,
+    // Legs whose feet are planted on uneven floors.
+    #[serde(default)]
+    pub legs: Vec<LegBones>,
+    // Bones turned to follow the opponent. The spine is optional and takes a
+    // share of the turn so the head doesn't do it all.
+    #[serde(default)]
+    pub head_bone: Option<String>,
+    #[serde(default)]
+    pub spine_bone: Option<String>,
+    // Bones that, with everything below them, keep walking while an
+    // upper-body move plays on the rest of the fighter.
+    #[serde(default)]
+    pub lower_body: Vec<String>,
+    // Strips of costume swung by the cloth simulation.
+    #[serde(default)]
+    pub cloth: Vec<ClothDef>,
+    #[serde(default)]
+    pub voice: VoiceSet,
+    #[serde(default)]
+    pub palettes: Vec<PaletteDef>,
+    #[serde(default)]
+    pub stats: CharacterStats,
+    pub ai_profile: String,
+}
+
+#[derive(Resource)]
+pub struct Roster {
+    pub def: Handle<RosterDef>,
+    pub characters: Vec<Handle<CharacterDef>>,
+    // The roster file's id for each entry in `characters`.
+    pub ids: Vec<String>,
+}
+
+impl Roster {
+    pub fn id(&self, character: &Handle<CharacterDef>) -> Option<&str> {
+        let index = self.characters.iter().position(|handle| handle == character)?;
+        self.ids.get(index).map(String::as_str)
+    }
+
+    // Every loaded character alongside its id.
+    pub fn defs<'a>(
+        &'a self,
+        characters: &'a Assets<CharacterDef>,
+    ) -> impl Iterator<Item = (&'a str, &'a CharacterDef)> {
+        self.ids
+            .iter()
+            .zip(self.characters.iter())
+            .filter_map(|(id, handle)| Some((id.as_str(), characters.get(handle)?)))
+    }
+}
+
+// Who drives the second fighter.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum EnemyControl {
+    // The AI profile named in the opponent's character file.
+    #[default]
+    CharacterProfile,
+    // A specific AI profile, e.g. picked by the arcade ladder.
+    Profile(String),
+    // Nothing; the fighter only moves if something else writes its input.
+    Idle,
+}
+
+// Roster indices and palettes of the characters picked for the next fight.
+#[derive(Resource, Clone, Debug)]
+pub struct MatchSetup {
+    pub player: usize,
+    pub enemy: usize,
+    // Who waits to be tagged in on each side in tag team matches.
+    pub player_partner: usize,
+    pub enemy_partner: usize,
+    pub player_skin: usize,
+    pub enemy_skin: usize,
+    pub enemy_control: EnemyControl,
+    pub difficulty: Difficulty,
+    // Seeds the match's GameRng; `None` picks a fresh seed every match.
+    pub seed: Option<u64>,
+    // `None` picks one of the stage's at random.
+    pub time_of_day: Option<TimeOfDay>,
+}
+
+impl Default for MatchSetup {
+    fn default() -> Self {
+        MatchSetup {
+            player: 0,
+            enemy: 1,
+            player_partner: 1,
+            enemy_partner: 0,
+            player_skin: 0,
+            enemy_skin: 0,
+            enemy_control: EnemyControl::default(),
+            difficulty: Difficulty::default(),
+            seed: None,
+            time_of_day: None,
+        }
+    }
+}
+
+impl MatchSetup {
+    pub fn is_mirror(&self) -> bool {
+        self.player == self.enemy
+    }
+
+    // In a mirror match the second fighter can't wear the first one's
+    // colours, so it is bumped to the next palette the character has.
+    pub fn resolved_enemy_skin(&self, palette_count: usize) -> usize {
+        if self.is_mirror() && self.enemy_skin == self.player_skin && palette_count > 1 {
+            (self.player_skin + 1) % palette_count
+        } else {
+            self.enemy_skin
+        }
+    }
+}
+
+pub fn load_roster(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Roster {
+        def: asset_server.load(ROSTER_PATH),
+        characters: Vec::new(),
+        ids: Vec::new(),
+    });
+}
+
+pub fn wait_for_roster(
+    asset_server: Res<AssetServer>,
+    mut roster: ResMut<Roster>,
+    roster_defs: Res<Assets<RosterDef>>,
+    characters: Res<Assets<CharacterDef>>,
+    tuning: Res<TuningHandle>,
+    tunings: Res<Assets<Tuning>>,
+    mode: Res<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(def) = roster_defs.get(&roster.def) else {
+        if asset_server.get_load_state(roster.def.id()) == Some(LoadState::Failed) {
+            error!("failed to load {ROSTER_PATH}");
+        }
+        return;
+    };
+    if roster.characters.is_empty() {
+        roster.characters = def
+            .characters
+            .iter()
+            .map(|id| asset_server.load(format!("characters/{id}/{id}.character.ron")))
+            .collect();
+        roster.ids = def.characters.clone();
+    }
+
+    let mut ready = true;
+    for character in roster.characters.iter() {
+        if characters.contains(character) {
+            continue;
+        }
+        ready = false;
+        if asset_server.get_load_state(character.id()) == Some(LoadState::Failed) {
+            error!("failed to load character {:?}", character.path());
+        }
+    }
+    if ready && tuning_ready(&asset_server, &tuning, &tunings) {
+        next_state.set(mode.first_screen());
+    }
+}
@@ -0,0 +1,477 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    character::{CharacterState, Health, Knockback, Team, MAX_HEALTH},
+    combat::{FightEnded, HitLanded},
+    input::ControlInput,
+    rng::GameRng,
+    roster::MatchSetup,
+    throws::ThrowLanded,
+    AppState, GameMode, SIMULATION_HZ,
+};
+
+// How long "K.O." or "Time over" holds before the next round starts.
+const ROUND_OVER_TICKS: u32 = 180;
+// Knockback slower than this has died down; a fighter past a ring-out edge
+// then is just standing there.
+const RING_OUT_SPEED: f32 = 0.5;
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MatchRules {
+    pub rounds_to_win: u32,
+    // Seconds on the clock each round; `None` plays without a timer.
+    pub round_time: Option<f32>,
+    // Ticks of "Round N / Fight!" before the fighters can move.
+    pub intro_ticks: u32,
+    pub starting_health: f32,
+    // Scales every hit, blocked or not.
+    pub damage_multiplier: f32,
+    // Fighters get a one-off boost when they fall to low health.
+    pub comeback: bool,
+    // Knocking the opponent over one of the stage's edges wins the round,
+    // on stages that have them.
+    pub ring_out: bool,
+    // The stage's hazards go off during rounds.
+    pub hazards: bool,
+    // Share of the starting health each side gets, player one's first.
+    pub handicaps: [f32; 2],
+    // Everyone slowly heals during rounds, for casual play.
+    pub regeneration: bool,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        MatchRules {
+            rounds_to_win: 2,
+            round_time: Some(99.0),
+            intro_ticks: 150,
+            starting_health: MAX_HEALTH,
+            damage_multiplier: 1.0,
+            comeback: true,
+            ring_out: true,
+            hazards: true,
+            handicaps: [1.0, 1.0],
+            regeneration: false,
+        }
+    }
+}
+
+impl MatchRules {
+    pub fn handicap(&self, team: Team) -> f32 {
+        self.handicaps[team_index(team)]
+    }
+
+    pub fn set_handicap(&mut self, team: Team, handicap: f32) {
+        self.handicaps[team_index(team)] = handicap;
+    }
+
+    // Health a fighter on `team` starts each round with.
+    pub fn team_health(&self, team: Team) -> f32 {
+        self.starting_health * self.handicap(team)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RoundPhase {
+    #[default]
+    Intro,
+    Fighting,
+    Over,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RoundEndReason {
+    KnockOut,
+    TimeOver,
+    RingOut,
+}
+
+impl RoundEndReason {
+    // The loser was beaten outright rather than on the clock.
+    pub fn beaten(self) -> bool {
+        matches!(self, RoundEndReason::KnockOut | RoundEndReason::RingOut)
+    }
+}
+
+// A stretch at one end of a stage past which a fighter knocked there is out
+// of the ring: `Left(x)` is everything left of `x`, `Right(x)` right of it.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RingOutEdge {
+    Left(f32),
+    Right(f32),
+}
+
+impl RingOutEdge {
+    fn contains(self, x: f32) -> bool {
+        match self {
+            RingOutEdge::Left(edge) => x < edge,
+            RingOutEdge::Right(edge) => x > edge,
+        }
+    }
+}
+
+// The current stage's ring-out edges. Empty on stages without any.
+#[derive(Resource, Default, Debug)]
+pub struct RingOut {
+    pub edges: Vec<RingOutEdge>,
+}
+
+// Where the match is up to. Training has no rounds and sits in `Fighting`.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct Round {
+    pub number: u32,
+    pub phase: RoundPhase,
+    // Ticks spent in the current phase.
+    pub ticks: u32,
+    pub time_left: Option<f32>,
+    pub wins: [u32; 2],
+    // Set once a round end has settled the match.
+    pub decided: bool,
+    // Damage each side has taken this round and all match, player one's
+    // first. Perfects go by these rather than health left, which
+    // regeneration and tag-ins top back up.
+    pub damage_taken: [f32; 2],
+    pub match_damage_taken: [f32; 2],
+}
+
+impl Round {
+    pub fn wins(&self, team: Team) -> u32 {
+        self.wins[team_index(team)]
+    }
+
+    pub fn take_damage(&mut self, team: Team, damage: f32) {
+        self.damage_taken[team_index(team)] += damage;
+        self.match_damage_taken[team_index(team)] += damage;
+    }
+
+    // Won without taking a hit all match.
+    pub fn flawless(&self, team: Team) -> bool {
+        self.match_damage_taken[team_index(team)] <= 0.0
+    }
+
+    fn enter(&mut self, phase: RoundPhase) {
+        self.phase = phase;
+        self.ticks = 0;
+    }
+}
+
+fn team_index(team: Team) -> usize {
+    match team {
+        Team::One => 0,
+        Team::Two => 1,
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RoundStarted {
+    pub number: u32,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FightStarted;
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RoundEnded {
+    pub winner: Option<Team>,
+    pub reason: RoundEndReason,
+    // The winner didn't take any damage.
+    pub perfect: bool,
+    // The loser went down to chip damage while blocking.
+    pub chip: bool,
+    // This round settled the match.
+    pub decisive: bool,
+}
+
+pub fn round_in_progress(round: Res<Round>) -> bool {
+    round.phase == RoundPhase::Fighting
+}
+
+pub fn start_match(
+    mode: Res<GameMode>,
+    rules: Res<MatchRules>,
+    setup: Res<MatchSetup>,
+    mut rng: ResMut<GameRng>,
+    mut round: ResMut<Round>,
+    mut started: EventWriter<RoundStarted>,
+) {
+    // Logged so a bug seen in a match can be replayed from its seed.
+    let seed = setup.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    rng.reseed(seed);
+    info!("match seed {seed}");
+    *round = Round {
+        number: 1,
+        time_left: rules.round_time,
+        ..default()
+    };
+    if mode.plays_rounds() {
+        started.send(RoundStarted { number: 1 });
+    } else {
+        round.enter(RoundPhase::Fighting);
+    }
+}
+
+fn end_round(
+    round: &mut Round,
+    rules: &MatchRules,
+    winner: Option<Team>,
+    reason: RoundEndReason,
+    chip: bool,
+    ended: &mut EventWriter<RoundEnded>,
+) {
+    // A draw scores for both fighters.
+    for team in [Team::One, Team::Two] {
+        if winner.map_or(true, |winner| winner == team) {
+            round.wins[team_index(team)] += 1;
+        }
+    }
+    let perfect = winner.is_some_and(|winner| round.damage_taken[team_index(winner)] <= 0.0);
+    round.decided = round.wins.iter().any(|wins| *wins >= rules.rounds_to_win);
+    round.enter(RoundPhase::Over);
+    ended.send(RoundEnded {
+        winner,
+        reason,
+        perfect,
+        chip,
+        decisive: round.decided,
+    });
+}
+
+pub fn check_knockout(
+    rules: Res<MatchRules>,
+    mut round: ResMut<Round>,
+    fighters: Query<(Entity, &Health, &Team)>,
+    mut hits: EventReader<HitLanded>,
+    mut throws: EventReader<ThrowLanded>,
+    mut ended: EventWriter<RoundEnded>,
+) {
+    // Drained every tick so an old block can't mark a later KO as chip.
+    let hits: Vec<HitLanded> = hits.iter().copied().collect();
+    let throws: Vec<ThrowLanded> = throws.iter().copied().collect();
+    if round.phase != RoundPhase::Fighting {
+        return;
+    }
+    let damage = hits
+        .iter()
+        .map(|hit| (hit.defender, hit.damage))
+        .chain(throws.iter().map(|throw| (throw.victim, throw.damage)));
+    for (defender, damage) in damage {
+        if let Ok((_, _, team)) = fighters.get(defender) {
+            round.take_damage(*team, damage);
+        }
+    }
+    let blocked_hits: Vec<Entity> = hits
+        .iter()
+        .filter(|hit| hit.blocked)
+        .map(|hit| hit.defender)
+        .collect();
+    let standings: Vec<(Team, Health)> = fighters
+        .iter()
+        .map(|(_, health, team)| (*team, *health))
+        .collect();
+    // Only a knockout where every fallen fighter was blocking counts as chip.
+    let chip = fighters
+        .iter()
+        .filter(|(_, health, _)| health.current <= 0.0)
+        .all(|(entity, ..)| blocked_hits.contains(&entity));
+    let knocked_out: Vec<Team> = standings
+        .iter()
+        .filter(|(_, health)| health.current <= 0.0)
+        .map(|(team, _)| *team)
+        .collect();
+    let winner = match knocked_out.as_slice() {
+        [] => return,
+        [loser] => Some(loser.opponent()),
+        _ => None,
+    };
+    end_round(
+        &mut round,
+        &rules,
+        winner,
+        RoundEndReason::KnockOut,
+        chip,
+        &mut ended,
+    );
+}
+
+// A fighter knocked past one of the stage's edges loses the round on the
+// spot. Walking out there is safe; it takes a hit to send them over.
+pub fn check_ring_out(
+    rules: Res<MatchRules>,
+    ring_out: Res<RingOut>,
+    mut round: ResMut<Round>,
+    fighters: Query<(&Transform, &Knockback, &CharacterState, &Team)>,
+    mut ended: EventWriter<RoundEnded>,
+) {
+    if !rules.ring_out || round.phase != RoundPhase::Fighting {
+        return;
+    }
+    let Some(loser) = fighters
+        .iter()
+        .find(|(transform, knockback, state, ..)| {
+            let knocked = knockback.0.length() > RING_OUT_SPEED || state.juggle.is_some();
+            knocked
+                && ring_out
+                    .edges
+                    .iter()
+                    .any(|edge| edge.contains(transform.translation.x))
+        })
+        .map(|(.., team)| *team)
+    else {
+        return;
+    };
+    end_round(
+        &mut round,
+        &rules,
+        Some(loser.opponent()),
+        RoundEndReason::RingOut,
+        false,
+        &mut ended,
+    );
+}
+
+// On time over the fighter with more health left takes the round.
+fn time_over_winner(standings: &[(Team, Health)]) -> Option<Team> {
+    let remaining = |team: Team| {
+        standings
+            .iter()
+            .find(|(other, _)| *other == team)
+            .map_or(0.0, |(_, health)| health.current / health.max)
+    };
+    let (one, two) = (remaining(Team::One), remaining(Team::Two));
+    if one > two {
+        Some(Team::One)
+    } else if two > one {
+        Some(Team::Two)
+    } else {
+        None
+    }
+}
+
+// Drives the round clock on the simulation tick so replays and headless runs
+// see the same timings.
+#[allow(clippy::too_many_arguments)]
+pub fn advance_round(
+    rules: Res<MatchRules>,
+    mut round: ResMut<Round>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut fighters: Query<(
+        &Team,
+        &mut Transform,
+        &mut Health,
+        &mut CharacterState,
+        &mut Knockback,
+        &mut ControlInput,
+    )>,
+    mut round_started: EventWriter<RoundStarted>,
+    mut fight_started: EventWriter<FightStarted>,
+    mut round_ended: EventWriter<RoundEnded>,
+    mut fight_ended: EventWriter<FightEnded>,
+) {
+    round.ticks += 1;
+    match round.phase {
+        RoundPhase::Intro => {
+            if round.ticks >= rules.intro_ticks {
+                round.enter(RoundPhase::Fighting);
+                fight_started.send(FightStarted);
+            }
+        }
+        RoundPhase::Fighting => {
+            if let Some(time_left) = round.time_left.as_mut() {
+                *time_left = (*time_left - (1.0 / SIMULATION_HZ) as f32).max(0.0);
+            }
+            if round.time_left.is_some_and(|time_left| time_left <= 0.0) {
+                let standings: Vec<(Team, Health)> = fighters
+                    .iter()
+                    .map(|(team, _, health, ..)| (*team, *health))
+                    .collect();
+                end_round(
+                    &mut round,
+                    &rules,
+                    time_over_winner(&standings),
+                    RoundEndReason::TimeOver,
+                    false,
+                    &mut round_ended,
+                );
+            }
+        }
+        RoundPhase::Over if round.ticks >= ROUND_OVER_TICKS => {
+            if round.decided {
+                let won = |team: Team| round.wins(team) >= rules.rounds_to_win;
+                let winner = match (won(Team::One), won(Team::Two)) {
+                    (true, false) => Some(Team::One),
+                    (false, true) => Some(Team::Two),
+                    _ => None,
+                };
+                fight_ended.send(FightEnded { winner });
+                next_state.set(AppState::Finished);
+                return;
+            }
+            for (team, mut transform, mut health, mut state, mut knockback, mut input) in
+                fighters.iter_mut()
+            {
+                *transform = team.start_transform();
+                health.current = health.max;
+                *state = CharacterState::default();
+                *knockback = Knockback::default();
+                *input = ControlInput::default();
+            }
+            round.number += 1;
+            round.time_left = rules.round_time;
+            round.damage_taken = [0.0; 2];
+            round.enter(RoundPhase::Intro);
+            round_started.send(RoundStarted {
+                number: round.number,
+            });
+        }
+        RoundPhase::Over => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(current: f32, max: f32) -> Health {
+        Health { current, max }
+    }
+
+    #[test]
+    fn time_over_goes_to_more_health_left() {
+        let standings = [
+            (Team::One, health(40.0, 100.0)),
+            (Team::Two, health(60.0, 100.0)),
+        ];
+        assert_eq!(time_over_winner(&standings), Some(Team::Two));
+    }
+
+    #[test]
+    fn time_over_compares_shares_of_handicapped_health() {
+        // 30 of 50 is more than 50 of 100.
+        let standings = [
+            (Team::One, health(30.0, 50.0)),
+            (Team::Two, health(50.0, 100.0)),
+        ];
+        assert_eq!(time_over_winner(&standings), Some(Team::One));
+    }
+
+    #[test]
+    fn time_over_on_equal_health_is_a_draw() {
+        let standings = [
+            (Team::One, health(50.0, 100.0)),
+            (Team::Two, health(50.0, 100.0)),
+        ];
+        assert_eq!(time_over_winner(&standings), None);
+    }
+
+    #[test]
+    fn damage_from_an_earlier_round_spoils_a_flawless_match() {
+        let mut round = Round::default();
+        round.take_damage(Team::One, 5.0);
+        // As the next round starts.
+        round.damage_taken = [0.0; 2];
+        assert!(!round.flawless(Team::One));
+        assert!(round.flawless(Team::Two));
+    }
+}
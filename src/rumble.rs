@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    prelude::*,
+};
+
+use crate::{
+    ai::AiController,
+    character::{Health, Player, Team},
+    combat::{HitLanded, HEAVY_HIT_DAMAGE},
+    input::team_gamepad,
+    settings::Settings,
+};
+
+const LIGHT_RUMBLE: (f32, f32) = (0.25, 0.12);
+const HEAVY_RUMBLE: (f32, f32) = (0.8, 0.3);
+const KO_RUMBLE: (f32, f32) = (1.0, 0.8);
+
+// Shakes the pad playing for `team`, if one is plugged in for it.
+fn rumble(
+    requests: &mut EventWriter<GamepadRumbleRequest>,
+    gamepads: &Gamepads,
+    team: Team,
+    (strength, seconds): (f32, f32),
+) {
+    let Some(gamepad) = team_gamepad(gamepads, team) else {
+        return;
+    };
+    requests.send(GamepadRumbleRequest::Add {
+        gamepad,
+        duration: Duration::from_secs_f32(seconds),
+        intensity: GamepadRumbleIntensity {
+            strong_motor: strength,
+            weak_motor: strength,
+        },
+    });
+}
+
+// Each player's own pad shakes: lightly for landing a hit, hard for taking a
+// heavy one or being knocked out. Fighters the AI plays, like the attract
+// demo's, have no pad to shake.
+pub fn rumble_on_hits(
+    settings: Res<Settings>,
+    gamepads: Res<Gamepads>,
+    mut hits: EventReader<HitLanded>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+    players: Query<(&Health, &Team), (With<Player>, Without<AiController>)>,
+) {
+    if !settings.rumble {
+        hits.clear();
+        return;
+    }
+    for hit in hits.iter() {
+        if let Ok((_, team)) = players.get(hit.attacker) {
+            if !hit.blocked {
+                rumble(&mut requests, &gamepads, *team, LIGHT_RUMBLE);
+            }
+        }
+        if let Ok((health, team)) = players.get(hit.defender) {
+            if health.current <= 0.0 {
+                rumble(&mut requests, &gamepads, *team, KO_RUMBLE);
+            } else if hit.damage >= HEAVY_HIT_DAMAGE {
+                rumble(&mut requests, &gamepads, *team, HEAVY_RUMBLE);
+            }
+        }
+    }
+}
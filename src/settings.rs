@@ -0,0 +1,286 @@
+use bevy::{
+    core_pipeline::bloom::BloomSettings,
+    pbr::{DirectionalLightShadowMap, PointLightShadowMap},
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode, WindowResolution},
+    winit::WinitWindows,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{Cameraman, StageLight, Sun},
+    data::{read_ron_file, write_ron_file},
+    input::ControlScheme,
+    locale::Language,
+    palette::HudPalette,
+};
+
+pub const SETTINGS_PATH: &str = "settings.ron";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DisplayMode {
+    Windowed,
+    #[default]
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    pub fn window_mode(self) -> WindowMode {
+        // In the browser the canvas just fills its parent element.
+        if cfg!(target_arch = "wasm32") {
+            return WindowMode::Windowed;
+        }
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            // Sized so the chosen resolution becomes the exclusive video mode.
+            DisplayMode::Fullscreen => WindowMode::SizedFullscreen,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    // Physical pixels; `None` keeps whatever the window/monitor picks.
+    pub resolution: Option<(u32, u32)>,
+    pub vsync: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            mode: DisplayMode::Borderless,
+            resolution: None,
+            vsync: true,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    pub fn window(&self) -> Window {
+        let mut resolution = WindowResolution::default();
+        if let Some((width, height)) = self.resolution {
+            resolution.set_physical_resolution(width, height);
+        }
+        Window {
+            mode: self.mode.window_mode(),
+            present_mode: self.present_mode(),
+            resolution,
+            canvas: Some("#bevy".to_string()),
+            fit_canvas_to_parent: true,
+            prevent_default_event_handling: false,
+            ..default()
+        }
+    }
+
+    fn apply(&self, window: &mut Window) {
+        window.mode = self.mode.window_mode();
+        window.present_mode = self.present_mode();
+        if let Some((width, height)) = self.resolution {
+            window.resolution.set_physical_resolution(width, height);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GraphicsQuality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl GraphicsQuality {
+    pub fn next(self) -> Self {
+        match self {
+            GraphicsQuality::Low => GraphicsQuality::Medium,
+            GraphicsQuality::Medium => GraphicsQuality::High,
+            GraphicsQuality::High => GraphicsQuality::Low,
+        }
+    }
+
+    pub fn shadows(self) -> bool {
+        self != GraphicsQuality::Low
+    }
+
+    pub fn shadow_map_size(self) -> usize {
+        match self {
+            GraphicsQuality::Low => 512,
+            GraphicsQuality::Medium => 1024,
+            GraphicsQuality::High => 2048,
+        }
+    }
+
+    pub fn msaa(self) -> Msaa {
+        match self {
+            GraphicsQuality::Low => Msaa::Off,
+            GraphicsQuality::Medium => Msaa::Sample2,
+            GraphicsQuality::High => Msaa::Sample4,
+        }
+    }
+
+    pub fn bloom(self) -> bool {
+        self == GraphicsQuality::High
+    }
+
+    // Fraction of each effect's particles that actually get spawned.
+    pub fn particle_density(self) -> f32 {
+        match self {
+            GraphicsQuality::Low => 0.25,
+            GraphicsQuality::Medium => 0.6,
+            GraphicsQuality::High => 1.0,
+        }
+    }
+
+    pub fn max_point_lights(self) -> usize {
+        match self {
+            GraphicsQuality::Low => 1,
+            GraphicsQuality::Medium | GraphicsQuality::High => usize::MAX,
+        }
+    }
+}
+
+// Player-facing options, persisted to SETTINGS_PATH.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub display: DisplaySettings,
+    pub quality: GraphicsQuality,
+    // Bend the legs so feet rest on uneven floors.
+    pub foot_ik: bool,
+    pub rumble: bool,
+    pub language: Language,
+    // Caption non-speech sounds for players who can't hear them.
+    pub captions: bool,
+    pub palette: HudPalette,
+    // Softens flashes and bursts for photosensitive players.
+    pub reduce_flashing: bool,
+    pub controls: ControlScheme,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            display: DisplaySettings::default(),
+            quality: GraphicsQuality::default(),
+            foot_ik: true,
+            rumble: true,
+            language: Language::default(),
+            captions: false,
+            palette: HudPalette::default(),
+            reduce_flashing: false,
+            controls: ControlScheme::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        read_ron_file(SETTINGS_PATH)
+    }
+
+    pub fn save(&self) {
+        write_ron_file(SETTINGS_PATH, self);
+    }
+}
+
+pub fn apply_display_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut window in windows.iter_mut() {
+        settings.display.apply(&mut window);
+    }
+}
+
+pub fn apply_graphics_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut msaa: ResMut<Msaa>,
+    mut shadow_map: ResMut<PointLightShadowMap>,
+    mut sun_shadow_map: ResMut<DirectionalLightShadowMap>,
+    mut cameras: Query<(Entity, &mut Camera), With<Cameraman>>,
+    mut suns: Query<&mut DirectionalLight, With<Sun>>,
+    mut lights: Query<(&StageLight, &mut PointLight, &mut Visibility)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let quality = settings.quality;
+    *msaa = quality.msaa();
+    shadow_map.size = quality.shadow_map_size();
+    sun_shadow_map.size = quality.shadow_map_size() * 2;
+    for mut sun in suns.iter_mut() {
+        sun.shadows_enabled = quality.shadows();
+    }
+    for (entity, mut camera) in cameras.iter_mut() {
+        camera.hdr = quality.bloom();
+        if quality.bloom() {
+            commands.entity(entity).insert(BloomSettings::NATURAL);
+        } else {
+            commands.entity(entity).remove::<BloomSettings>();
+        }
+    }
+    for (light, mut point_light, mut visibility) in lights.iter_mut() {
+        point_light.shadows_enabled = quality.shadows();
+        *visibility = if light.priority < quality.max_point_lights() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn toggle_borderless(keys: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    if !(alt && keys.just_pressed(KeyCode::Return)) {
+        return;
+    }
+    settings.display.mode = match settings.display.mode {
+        DisplayMode::Windowed => DisplayMode::Borderless,
+        _ => DisplayMode::Windowed,
+    };
+    settings.save();
+}
+
+// Resolutions the primary window's monitor supports, largest first.
+pub fn available_resolutions(
+    winit_windows: &WinitWindows,
+    window: Entity,
+) -> Vec<(u32, u32)> {
+    let Some(monitor) = winit_windows
+        .get_window(window)
+        .and_then(|window| window.current_monitor())
+    else {
+        return Vec::new();
+    };
+    let mut resolutions: Vec<(u32, u32)> = monitor
+        .video_modes()
+        .map(|mode| (mode.size().width, mode.size().height))
+        .collect();
+    resolutions.sort_unstable_by(|a, b| b.cmp(a));
+    resolutions.dedup();
+    resolutions
+}
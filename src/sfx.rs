@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use bevy::{
+    asset::UntypedAssetId,
+    audio::{Decodable, PlaybackMode, Source, Volume, VolumeLevel},
+    prelude::*,
+    reflect::TypePath,
+    utils::HashMap,
+};
+
+// Copies of one sound allowed to play at once. Past this a fast combo only
+// muddies the mix, so the oldest copy is cut off for the new one.
+const MAX_VOICES_PER_SOUND: usize = 3;
+const WHOOSH_SAMPLE_RATE: u32 = 44100;
+const CALL_SAMPLE_RATE: u32 = 22050;
+
+// Asks for a one-shot sound effect. Sent instead of spawning audio directly
+// so every effect goes through the voice limit. Samples are the default;
+// synthesised sounds like the whooshes have their own `play_sfx`.
+#[derive(Event, Clone, Debug)]
+pub struct PlaySfx<S: Asset = AudioSource> {
+    pub source: Handle<S>,
+    pub volume: f32,
+    pub speed: f32,
+}
+
+impl<S: Asset> PlaySfx<S> {
+    pub fn new(source: Handle<S>, volume: f32) -> Self {
+        PlaySfx {
+            source,
+            volume,
+            speed: 1.0,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+// A sound effect started by `play_sfx`, despawned as soon as it falls silent.
+#[derive(Component)]
+pub struct SfxVoice {
+    source: UntypedAssetId,
+    started: f32,
+}
+
+// One of these runs for each kind of source, and only looks after its own
+// voices.
+pub fn play_sfx<S: Asset + Decodable>(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut requests: EventReader<PlaySfx<S>>,
+    voices: Query<(Entity, &SfxVoice, Option<&AudioSink>)>,
+) {
+    let mut playing: HashMap<UntypedAssetId, Vec<(Entity, f32)>> = HashMap::new();
+    let ours = voices
+        .iter()
+        .filter(|(_, voice, _)| voice.source.type_id() == std::any::TypeId::of::<S>());
+    for (entity, voice, sink) in ours {
+        if sink.is_some_and(|sink| sink.empty()) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        playing
+            .entry(voice.source)
+            .or_default()
+            .push((entity, voice.started));
+    }
+
+    let now = time.elapsed_seconds();
+    for request in requests.iter() {
+        let source = request.source.id().untyped();
+        let voices = playing.entry(source).or_default();
+        voices.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        while voices.len() >= MAX_VOICES_PER_SOUND {
+            let (oldest, _) = voices.remove(0);
+            commands.entity(oldest).despawn();
+        }
+        let voice = commands
+            .spawn((
+                AudioSourceBundle {
+                    source: request.source.clone(),
+                    settings: PlaybackSettings {
+                        mode: PlaybackMode::Once,
+                        volume: Volume::Relative(VolumeLevel::new(request.volume)),
+                        speed: request.speed,
+                        ..default()
+                    },
+                },
+                SfxVoice {
+                    source,
+                    started: now,
+                },
+            ))
+            .id();
+        voices.push((voice, now));
+    }
+}
+
+// A limb cutting through the air: noise swelling in and out, darker for
+// heavier swings. Synthesised, so swings make a sound whether or not they
+// land and every character has one without a sample on disk.
+#[derive(Asset, TypePath, Clone, Copy, Debug)]
+pub struct Whoosh {
+    pub seconds: f32,
+    // How much of the noise's high end gets through, from 0 to 1.
+    pub brightness: f32,
+}
+
+pub struct WhooshDecoder {
+    whoosh: Whoosh,
+    sample: u32,
+    noise: u32,
+    filtered: f32,
+}
+
+impl Iterator for WhooshDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let seconds = self.sample as f32 / WHOOSH_SAMPLE_RATE as f32;
+        if seconds >= self.whoosh.seconds {
+            return None;
+        }
+        self.sample += 1;
+        // Xorshift, which is plenty random for noise.
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        let white = self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        self.filtered += (white - self.filtered) * self.whoosh.brightness;
+        let swell = (seconds / self.whoosh.seconds * std::f32::consts::PI).sin();
+        Some(self.filtered * swell * 0.8)
+    }
+}
+
+impl Source for WhooshDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        WHOOSH_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.whoosh.seconds))
+    }
+}
+
+impl Decodable for Whoosh {
+    type DecoderItem = <WhooshDecoder as Iterator>::Item;
+    type Decoder = WhooshDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        WhooshDecoder {
+            whoosh: *self,
+            sample: 0,
+            noise: 0x9e37_79b9,
+            filtered: 0.0,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Whooshes {
+    pub light: Handle<Whoosh>,
+    pub heavy: Handle<Whoosh>,
+}
+
+pub fn setup_whooshes(mut commands: Commands, mut whooshes: ResMut<Assets<Whoosh>>) {
+    commands.insert_resource(Whooshes {
+        light: whooshes.add(Whoosh {
+            seconds: 0.18,
+            brightness: 0.35,
+        }),
+        heavy: whooshes.add(Whoosh {
+            seconds: 0.3,
+            brightness: 0.15,
+        }),
+    });
+}
+
+// Renders a run of tones, each a frequency in hertz (0 for a rest) held for
+// some seconds, into an in-memory WAV. Stands in for announcer calls until
+// there are recordings, and plays through PlaySfx like any sample.
+pub fn synthesize_call(notes: &[(f32, f32)]) -> AudioSource {
+    let mut samples = Vec::new();
+    for &(frequency, seconds) in notes {
+        let count = (seconds * CALL_SAMPLE_RATE as f32) as u32;
+        for sample in 0..count {
+            let time = sample as f32 / CALL_SAMPLE_RATE as f32;
+            // Each note dies away before the next, so they don't click.
+            let fade = 1.0 - sample as f32 / count as f32;
+            let value = (time * frequency * std::f32::consts::TAU).sin() * fade * 0.5;
+            samples.push((value * i16::MAX as f32) as i16);
+        }
+    }
+
+    // 16-bit mono PCM.
+    let data_size = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&CALL_SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(CALL_SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    AudioSource {
+        bytes: bytes.into(),
+    }
+}
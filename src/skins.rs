@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Character, Team},
+    combat::HitLanded,
+    rim_light::FighterMaterial,
+    roster::{CharacterDef, PaletteDef},
+    settings::Settings,
+};
+
+pub const HIT_FLASH_DURATION: f32 = 0.1;
+const HIT_FLASH_COLOR: Color = Color::WHITE;
+const COUNTER_FLASH_COLOR: Color = Color::RED;
+// With reduced flashing hits glow faintly and fade out slowly instead.
+const GENTLE_FLASH_DURATION: f32 = 0.4;
+const GENTLE_FLASH_STRENGTH: f32 = 0.2;
+
+impl PaletteDef {
+    pub fn color(&self) -> Color {
+        let (r, g, b) = self.tint;
+        Color::rgb(r, g, b)
+    }
+}
+
+// Index into the fighter's palettes. Zero is the untouched model.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Skin(pub usize);
+
+#[derive(Component)]
+pub struct SkinApplied;
+
+// The fighter's own material copies with the emissive they started with, so
+// effects can change them without touching the other fighter.
+#[derive(Component, Default)]
+pub struct FighterMaterials(pub Vec<(Handle<FighterMaterial>, Color)>);
+
+#[derive(Component)]
+pub struct HitFlash {
+    pub timer: Timer,
+    pub color: Color,
+}
+
+fn tinted(color: Color, tint: Color) -> Color {
+    Color::rgba(
+        color.r() * tint.r(),
+        color.g() * tint.g(),
+        color.b() * tint.b(),
+        color.a() * tint.a(),
+    )
+}
+
+// Scenes share their materials between instances, so each fighter gets its
+// own copies rather than editing the loaded ones in place. The copies swap
+// the glTF's standard materials for ones with the team's rim light.
+pub fn apply_skins(
+    mut commands: Commands,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<FighterMaterial>>,
+    characters: Res<Assets<CharacterDef>>,
+    settings: Res<Settings>,
+    fighters: Query<(Entity, &Character, &Skin, &Team), Without<SkinApplied>>,
+    children: Query<&Children>,
+    meshes: Query<&Handle<StandardMaterial>>,
+) {
+    for (fighter, character, skin, team) in &fighters {
+        let parts: Vec<Entity> = children
+            .iter_descendants(fighter)
+            .filter(|entity| meshes.contains(*entity))
+            .collect();
+        if parts.is_empty() {
+            continue;
+        }
+
+        let tint = characters
+            .get(&character.0)
+            .and_then(|def| def.palettes.get(skin.0))
+            .filter(|_| skin.0 != 0)
+            .map(PaletteDef::color);
+        let mut owned = FighterMaterials::default();
+        for part in parts {
+            let Ok(handle) = meshes.get(part) else {
+                continue;
+            };
+            let Some(mut base) = standard_materials.get(handle).cloned() else {
+                continue;
+            };
+            if let Some(tint) = tint {
+                base.base_color = tinted(base.base_color, tint);
+            }
+            let emissive = base.emissive;
+            let handle = materials.add(FighterMaterial {
+                base,
+                extension: team.rim_light(&settings.palette.colors()),
+            });
+            commands
+                .entity(part)
+                .remove::<Handle<StandardMaterial>>()
+                .insert(handle.clone());
+            owned.0.push((handle, emissive));
+        }
+        commands.entity(fighter).insert((SkinApplied, owned));
+    }
+}
+
+// Recolours the outlines when the HUD palette changes mid-fight.
+pub fn apply_outline_palette(
+    settings: Res<Settings>,
+    mut materials: ResMut<Assets<FighterMaterial>>,
+    fighters: Query<(&Team, &FighterMaterials)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let colors = settings.palette.colors();
+    for (team, owned) in fighters.iter() {
+        for (handle, _) in owned.0.iter() {
+            if let Some(material) = materials.get_mut(handle) {
+                material.extension = team.rim_light(&colors);
+            }
+        }
+    }
+}
+
+pub fn start_hit_flash(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut hits: EventReader<HitLanded>,
+) {
+    let (duration, strength) = if settings.reduce_flashing {
+        (GENTLE_FLASH_DURATION, GENTLE_FLASH_STRENGTH)
+    } else {
+        (HIT_FLASH_DURATION, 1.0)
+    };
+    for hit in hits.iter().filter(|hit| !hit.blocked) {
+        let color = if hit.counter {
+            COUNTER_FLASH_COLOR
+        } else {
+            HIT_FLASH_COLOR
+        };
+        commands.entity(hit.defender).insert(HitFlash {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            color: color * strength,
+        });
+    }
+}
+
+pub fn apply_hit_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<FighterMaterial>>,
+    mut fighters: Query<(Entity, &mut HitFlash, &FighterMaterials)>,
+) {
+    for (fighter, mut flash, owned) in fighters.iter_mut() {
+        let finished = flash.timer.tick(time.delta()).finished();
+        let strength = 1.0 - flash.timer.percent();
+        for (handle, emissive) in owned.0.iter() {
+            let Some(material) = materials.get_mut(handle) else {
+                continue;
+            };
+            material.base.emissive = if finished {
+                *emissive
+            } else {
+                *emissive + flash.color * strength
+            };
+        }
+        if finished {
+            commands.entity(fighter).remove::<HitFlash>();
+        }
+    }
+}
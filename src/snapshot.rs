@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{KinematicCharacterController, KinematicCharacterControllerOutput};
+
+use crate::{
+    ai::AiController,
+    character::{Character, CharacterState, Health, Knockback, Meter},
+    combat::{rebuild_limb_colliders, LimbVelocity},
+    comeback::Comeback,
+    input::{ControlInput, SimpleControls},
+    rng::GameRng,
+    round::Round,
+    skins::{Skin, SkinApplied},
+    tag_team::{restore_model, TagEntrance, TagTeam},
+    training::TrainingDummy,
+    tuning::ComboCounter,
+    SimulationTick,
+};
+
+// Simulation ticks between snapshots.
+pub const SNAPSHOT_INTERVAL: u32 = 30;
+// Snapshots kept before the oldest is dropped: five minutes of fight.
+const SNAPSHOT_CAPACITY: usize = 600;
+
+// One fighter's part of a snapshot: everything on them that can change how
+// the fight goes from here.
+#[derive(Clone)]
+struct FighterSnapshot {
+    entity: Entity,
+    transform: Transform,
+    state: CharacterState,
+    health: Health,
+    meter: Meter,
+    knockback: Knockback,
+    combo: ComboCounter,
+    comeback: Comeback,
+    input: ControlInput,
+    ai: Option<AiController>,
+    dummy: Option<TrainingDummy>,
+    simple: Option<SimpleControls>,
+    // Who's on screen, which a tag changes, and the model they're drawn with.
+    character: Character,
+    name: Name,
+    skin: Skin,
+    model: Option<Handle<Scene>>,
+    tag: Option<TagTeam>,
+    entrance: Option<TagEntrance>,
+    // The move still to be made and whether the last one ended on the floor.
+    controller: KinematicCharacterController,
+    controller_output: Option<KinematicCharacterControllerOutput>,
+}
+
+// The combat-relevant world as it was at the start of one simulation tick.
+#[derive(Clone)]
+struct Snapshot {
+    tick: u32,
+    round: Round,
+    rng: GameRng,
+    fighters: Vec<FighterSnapshot>,
+    // Attacking limbs' velocities, by the bone each is on.
+    limbs: Vec<(Entity, LimbVelocity)>,
+}
+
+// The fight so far, copied every few ticks, oldest first. It's what replays
+// seek with and what training rewinds to.
+//
+// Movement, physics, hits and knockouts all step on the simulation tick and
+// time themselves in ticks, and the limbs are posed on it too, so running the
+// same inputs on from a snapshot plays the fight out the same way. Rapier's
+// contacts can't be put back, so the limb colliders are made afresh instead.
+// Going back past a tag puts the model from then back too, which picks its
+// colliders up when its scene is ready, as it did when it was tagged in.
+#[derive(Resource, Default)]
+pub struct Snapshots {
+    ring: VecDeque<Snapshot>,
+}
+
+impl Snapshots {
+    pub fn clear(&mut self) {
+        self.ring.clear();
+    }
+
+    pub fn oldest(&self) -> Option<u32> {
+        self.ring.front().map(|snapshot| snapshot.tick)
+    }
+
+    // Tick of the latest snapshot taken at or before `tick`.
+    pub fn latest_at(&self, tick: u32) -> Option<u32> {
+        self.ring
+            .iter()
+            .rev()
+            .map(|snapshot| snapshot.tick)
+            .find(|at| *at <= tick)
+    }
+}
+
+type SnapshotFighter<'a> = (
+    Entity,
+    &'a Transform,
+    &'a CharacterState,
+    &'a Health,
+    &'a Meter,
+    &'a Knockback,
+    &'a ComboCounter,
+    &'a Comeback,
+    &'a ControlInput,
+    Option<&'a AiController>,
+    Option<&'a TrainingDummy>,
+    Option<&'a SimpleControls>,
+    SnapshotModel<'a>,
+    (
+        &'a KinematicCharacterController,
+        Option<&'a KinematicCharacterControllerOutput>,
+    ),
+);
+
+type SnapshotModel<'a> = (
+    &'a Character,
+    &'a Name,
+    &'a Skin,
+    Option<&'a Handle<Scene>>,
+    Option<&'a TagTeam>,
+    Option<&'a TagEntrance>,
+);
+
+// Runs first thing on the simulation tick, before anyone's input is read.
+// The first tick of a fight is always kept so there's somewhere to go back
+// to from the start.
+pub fn take_snapshot(
+    tick: Res<SimulationTick>,
+    round: Res<Round>,
+    rng: Res<GameRng>,
+    mut snapshots: ResMut<Snapshots>,
+    fighters: Query<SnapshotFighter, With<Character>>,
+    limbs: Query<(&Parent, &LimbVelocity)>,
+) {
+    if tick.0 % SNAPSHOT_INTERVAL != 0 && !snapshots.ring.is_empty() {
+        return;
+    }
+    if snapshots.ring.len() >= SNAPSHOT_CAPACITY {
+        snapshots.ring.pop_front();
+    }
+    let fighters = fighters
+        .iter()
+        .map(
+            |(
+                entity,
+                transform,
+                state,
+                health,
+                meter,
+                knockback,
+                combo,
+                comeback,
+                input,
+                ai,
+                dummy,
+                simple,
+                (character, name, skin, model, tag, entrance),
+                (controller, controller_output),
+            )| FighterSnapshot {
+                entity,
+                transform: *transform,
+                state: state.clone(),
+                health: *health,
+                meter: *meter,
+                knockback: *knockback,
+                combo: combo.clone(),
+                comeback: *comeback,
+                input: *input,
+                ai: ai.cloned(),
+                dummy: dummy.cloned(),
+                simple: simple.cloned(),
+                character: character.clone(),
+                name: name.clone(),
+                skin: *skin,
+                model: model.cloned(),
+                tag: tag.cloned(),
+                entrance: entrance.cloned(),
+                controller: controller.clone(),
+                controller_output: controller_output.cloned(),
+            },
+        )
+        .collect();
+    snapshots.ring.push_back(Snapshot {
+        tick: tick.0,
+        round: round.clone(),
+        rng: rng.clone(),
+        fighters,
+        limbs: limbs
+            .iter()
+            .map(|(bone, velocity)| (bone.get(), *velocity))
+            .collect(),
+    });
+}
+
+pub fn clear_snapshots(mut snapshots: ResMut<Snapshots>) {
+    snapshots.clear();
+}
+
+// Puts the fight back how it was at the latest snapshot at or before `tick`
+// and forgets everything after it, returning the tick it went back to. The
+// next simulation tick carries on from there, and takes that snapshot again.
+pub fn roll_back(world: &mut World, tick: u32) -> Option<u32> {
+    let mut snapshots = world.resource_mut::<Snapshots>();
+    let index = snapshots
+        .ring
+        .iter()
+        .rposition(|snapshot| snapshot.tick <= tick)?;
+    let snapshot = snapshots.ring.drain(index..).next()?;
+
+    world.insert_resource(SimulationTick(snapshot.tick));
+    world.insert_resource(snapshot.round);
+    world.insert_resource(snapshot.rng);
+    for fighter in snapshot.fighters {
+        let model_swapped = world.get::<Handle<Scene>>(fighter.entity) != fighter.model.as_ref();
+        let reskinned = world.get::<Skin>(fighter.entity) != Some(&fighter.skin);
+        let Some(mut entity) = world.get_entity_mut(fighter.entity) else {
+            continue;
+        };
+        entity.insert((
+            fighter.transform,
+            fighter.state,
+            fighter.health,
+            fighter.meter,
+            fighter.knockback,
+            fighter.combo,
+            fighter.comeback,
+            fighter.input,
+            fighter.character,
+            fighter.name,
+            fighter.skin,
+            fighter.controller,
+        ));
+        match fighter.controller_output {
+            Some(output) => entity.insert(output),
+            None => entity.remove::<KinematicCharacterControllerOutput>(),
+        };
+        if let Some(tag) = fighter.tag {
+            entity.insert(tag);
+        }
+        match fighter.entrance {
+            Some(entrance) => entity.insert(entrance),
+            None => entity.remove::<TagEntrance>(),
+        };
+        if let Some(ai) = fighter.ai {
+            entity.insert(ai);
+        }
+        if let Some(dummy) = fighter.dummy {
+            entity.insert(dummy);
+        }
+        if let Some(simple) = fighter.simple {
+            entity.insert(simple);
+        }
+        match fighter.model {
+            Some(model) if model_swapped => restore_model(world, fighter.entity, model),
+            _ if reskinned => {
+                world.entity_mut(fighter.entity).remove::<SkinApplied>();
+            }
+            _ => {}
+        }
+    }
+    rebuild_limb_colliders(world);
+    let mut limbs = world.query::<(&Parent, &mut LimbVelocity)>();
+    for (bone, mut velocity) in limbs.iter_mut(world) {
+        if let Some((_, saved)) = snapshot.limbs.iter().find(|(at, _)| *at == bone.get()) {
+            *velocity = *saved;
+        }
+    }
+    Some(snapshot.tick)
+}
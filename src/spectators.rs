@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::round::RoundEnded;
+
+const CHEER_DURATION: f32 = 3.0;
+const CHEER_BLEND: f32 = 0.3;
+// Each spectator's loop runs a little off the one before's, in speed and
+// where it starts, so the crowd doesn't move in step.
+const SPEED_SPREAD: f32 = 0.12;
+const START_SPREAD: f32 = 0.7;
+
+// Someone watching from the back of the stage: a model with an idle loop
+// and a loop to cheer with. Scenery only, with no colliders.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpectatorDef {
+    pub scene: String,
+    pub idle: String,
+    pub cheer: String,
+    pub position: (f32, f32, f32),
+    // Radians about the vertical, to face them at the fight.
+    #[serde(default)]
+    pub turn: f32,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+#[derive(Component)]
+pub struct Spectator {
+    idle: Handle<AnimationClip>,
+    cheer: Handle<AnimationClip>,
+    speed: f32,
+    start: f32,
+    // Time left on a cheer, if they're cheering.
+    cheering: Option<Timer>,
+}
+
+// Called with the stage, which only spawns once, so the spectators stay put
+// from one fight to the next.
+pub fn spawn_spectators(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    spectators: &[SpectatorDef],
+) {
+    for (index, def) in spectators.iter().enumerate() {
+        let spread = (index as f32 * 2.4).sin();
+        commands.spawn((
+            Name::new("spectator"),
+            SceneBundle {
+                scene: asset_server.load(def.scene.clone()),
+                transform: Transform::from_translation(Vec3::from(def.position))
+                    .with_rotation(Quat::from_rotation_y(def.turn))
+                    .with_scale(Vec3::ONE * def.scale),
+                ..default()
+            },
+            Spectator {
+                idle: asset_server.load(def.idle.clone()),
+                cheer: asset_server.load(def.cheer.clone()),
+                speed: 1.0 + SPEED_SPREAD * spread,
+                start: START_SPREAD * (1.0 + spread),
+                cheering: None,
+            },
+        ));
+    }
+}
+
+pub fn start_spectator_animations(
+    spectators: Query<&Spectator>,
+    parents: Query<&Parent>,
+    mut animation_players: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+) {
+    for (entity, mut animation_player) in animation_players.iter_mut() {
+        let Some(spectator) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| spectators.get(ancestor).ok())
+        else {
+            continue;
+        };
+        animation_player
+            .play(spectator.idle.clone_weak())
+            .repeat()
+            .set_speed(spectator.speed)
+            .seek_to(spectator.start);
+    }
+}
+
+// The spectators cheer when a fighter is beaten, then settle back down.
+pub fn cheer_spectators(
+    time: Res<Time>,
+    mut ended: EventReader<RoundEnded>,
+    mut spectators: Query<(Entity, &mut Spectator)>,
+    parents: Query<&Parent>,
+    mut animation_players: Query<(Entity, &mut AnimationPlayer)>,
+) {
+    let beaten = ended.iter().any(|round| round.reason.beaten());
+    let mut changed = Vec::new();
+    for (entity, mut spectator) in spectators.iter_mut() {
+        if beaten {
+            spectator.cheering = Some(Timer::from_seconds(CHEER_DURATION, TimerMode::Once));
+            changed.push(entity);
+        } else if let Some(timer) = spectator.cheering.as_mut() {
+            if timer.tick(time.delta()).finished() {
+                spectator.cheering = None;
+                changed.push(entity);
+            }
+        }
+    }
+    if changed.is_empty() {
+        return;
+    }
+    for (entity, mut animation_player) in animation_players.iter_mut() {
+        let Some((_, spectator)) = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| changed.contains(ancestor))
+            .and_then(|ancestor| spectators.get(ancestor).ok())
+        else {
+            continue;
+        };
+        let clip = match spectator.cheering {
+            Some(_) => &spectator.cheer,
+            None => &spectator.idle,
+        };
+        animation_player
+            .play_with_transition(clip.clone_weak(), Duration::from_secs_f32(CHEER_BLEND))
+            .repeat()
+            .set_speed(spectator.speed);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use bevy::{audio::PlaybackMode, prelude::*, reflect::TypePath};
+use rand::seq::IteratorRandom;
+use serde::Deserialize;
+
+use crate::{
+    camera::{StageLight, SunConfig},
+    character::{spawn_floor, FloorStep, Health, StageFloor},
+    crowd::spawn_crowd,
+    decals::FloorDecal,
+    environment::EnvironmentDef,
+    hazards::{spawn_hazards, HazardDef},
+    profile::{Profile, Unlock},
+    rng::GameRng,
+    roster::MatchSetup,
+    round::{RingOut, RingOutEdge, RoundEnded},
+    spectators::{spawn_spectators, SpectatorDef},
+};
+
+pub const STAGE_PATH: &str = "stages/dojo.stage.ron";
+
+pub const MUSIC_BPM: f32 = 120.0;
+const PULSE_AMOUNT: f32 = 0.08;
+// Below this share of health the stage lights shift towards red.
+pub const DANGER_HEALTH: f32 = 0.2;
+const DANGER_COLOR: Color = Color::rgb(1.0, 0.35, 0.3);
+const SPOTLIGHT_DURATION: f32 = 1.5;
+const SPOTLIGHT_DIM: f32 = 0.15;
+
+// Dims the stage so only the rim-lit fighters read, e.g. for a finishing
+// blow. Reset the timer to start one.
+#[derive(Resource)]
+pub struct SpotlightMoment {
+    pub timer: Timer,
+}
+
+impl Default for SpotlightMoment {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(SPOTLIGHT_DURATION, TimerMode::Once);
+        timer.tick(timer.duration());
+        SpotlightMoment { timer }
+    }
+}
+
+// Ambient effects a stage can ask for. Each becomes a `Weather` entity that
+// the weather systems dress.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WeatherEffect {
+    CherryBlossoms { rate: f32 },
+    Rain { rate: f32 },
+    Fog { layers: usize, color: (f32, f32, f32, f32) },
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TimeOfDay {
+    Day,
+    Sunset,
+    Night,
+}
+
+impl TimeOfDay {
+    pub const ALL: [TimeOfDay; 3] = [TimeOfDay::Day, TimeOfDay::Sunset, TimeOfDay::Night];
+
+    pub fn unlocked(self, profile: &Profile) -> bool {
+        self != TimeOfDay::Night || profile.is_unlocked(Unlock::NightStage)
+    }
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            TimeOfDay::Day => "stage.time.day",
+            TimeOfDay::Sunset => "stage.time.sunset",
+            TimeOfDay::Night => "stage.time.night",
+        }
+    }
+}
+
+// How a stage is lit at one time of day. The sun's angles are as in
+// `SunConfig`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StageLighting {
+    pub sky: (f32, f32, f32),
+    pub ambient: (f32, f32, f32),
+    pub ambient_brightness: f32,
+    pub sun_color: (f32, f32, f32),
+    pub illuminance: f32,
+    pub elevation: f32,
+    pub azimuth: f32,
+    // Surroundings for this time of day, in place of the stage's own.
+    #[serde(default)]
+    pub environment: Option<EnvironmentDef>,
+}
+
+// Two looping layers: a steady murmur and a cheer mixed in with excitement.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CrowdAudio {
+    pub ambience: String,
+    pub cheer: String,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct StageDef {
+    pub name: String,
+    pub scene: String,
+    pub scale: f32,
+    #[serde(default)]
+    pub weather: Vec<WeatherEffect>,
+    #[serde(default)]
+    pub crowd: Option<CrowdAudio>,
+    // Height of the model's floor once scaled, so fighters stand on it.
+    #[serde(default)]
+    pub ground: f32,
+    #[serde(default)]
+    pub floor: Vec<FloorStep>,
+    // Marks knockdowns leave on the floor, if it takes them.
+    #[serde(default)]
+    pub decals: Option<FloorDecal>,
+    // Edges a fighter can be knocked over to lose the round.
+    #[serde(default)]
+    pub ring_out: Vec<RingOutEdge>,
+    #[serde(default)]
+    pub hazards: Vec<HazardDef>,
+    #[serde(default)]
+    pub spectators: Vec<SpectatorDef>,
+    // The times of day the stage can be fought at. Stages without any keep
+    // the default lighting.
+    #[serde(default)]
+    pub lighting: HashMap<TimeOfDay, StageLighting>,
+    #[serde(default)]
+    pub environment: Option<EnvironmentDef>,
+}
+
+#[derive(Resource)]
+pub struct CurrentStage {
+    pub def: Handle<StageDef>,
+    pub spawned: bool,
+    // What the current fight is lit for, if the stage has times of day.
+    pub time_of_day: Option<TimeOfDay>,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Weather(pub WeatherEffect);
+
+pub fn load_stage(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CurrentStage {
+        def: asset_server.load(STAGE_PATH),
+        spawned: false,
+        time_of_day: None,
+    });
+}
+
+pub fn spawn_stage(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut stage: ResMut<CurrentStage>,
+    stages: Res<Assets<StageDef>>,
+    floors: Query<Entity, With<StageFloor>>,
+) {
+    if stage.spawned {
+        return;
+    }
+    let Some(def) = stages.get(&stage.def) else {
+        return;
+    };
+    stage.spawned = true;
+    spawn_floor(&mut commands, &floors, def.ground, &def.floor);
+    commands.insert_resource(RingOut {
+        edges: def.ring_out.clone(),
+    });
+
+    commands.spawn(SceneBundle {
+        scene: asset_server.load(def.scene.clone()),
+        transform: Transform::from_scale(Vec3::ONE * def.scale),
+        ..default()
+    });
+    for effect in def.weather.iter() {
+        commands.spawn((SpatialBundle::default(), Weather(*effect)));
+    }
+    if let Some(crowd) = def.crowd.as_ref() {
+        spawn_crowd(&mut commands, &asset_server, crowd);
+    }
+    spawn_hazards(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        def.ground,
+        &def.hazards,
+    );
+    spawn_spectators(&mut commands, &asset_server, &def.spectators);
+}
+
+// Lights the stage for the time of day picked in the match options, or one
+// of the stage's at random. Drawn from the cosmetic stream, so a replay
+// comes back at the same time of day.
+pub fn apply_time_of_day(
+    mut commands: Commands,
+    setup: Res<MatchSetup>,
+    profile: Res<Profile>,
+    stage: Option<ResMut<CurrentStage>>,
+    stages: Res<Assets<StageDef>>,
+    mut rng: ResMut<GameRng>,
+    mut sun: ResMut<SunConfig>,
+) {
+    let Some(mut stage) = stage else {
+        return;
+    };
+    let Some(def) = stages.get(&stage.def) else {
+        return;
+    };
+    let mut times: Vec<TimeOfDay> = def
+        .lighting
+        .keys()
+        .copied()
+        .filter(|time| time.unlocked(&profile))
+        .collect();
+    // Sorted so the random pick doesn't depend on the map's order.
+    times.sort_by_key(|time| TimeOfDay::ALL.iter().position(|all| all == time));
+    let time = setup
+        .time_of_day
+        .filter(|time| def.lighting.contains_key(time))
+        .or_else(|| times.into_iter().choose(rng.cosmetic()));
+    stage.time_of_day = time;
+    let Some(lighting) = time.and_then(|time| def.lighting.get(&time)) else {
+        return;
+    };
+    let (r, g, b) = lighting.sky;
+    commands.insert_resource(ClearColor(Color::rgb(r, g, b)));
+    let (r, g, b) = lighting.ambient;
+    commands.insert_resource(AmbientLight {
+        color: Color::rgb(r, g, b),
+        brightness: lighting.ambient_brightness,
+    });
+    let (r, g, b) = lighting.sun_color;
+    sun.color = Color::rgb(r, g, b);
+    sun.illuminance = lighting.illuminance;
+    sun.elevation = lighting.elevation;
+    sun.azimuth = lighting.azimuth;
+}
+
+pub fn any_user_input(keys: Res<Input<KeyCode>>, mouse: Res<Input<MouseButton>>) -> bool {
+    keys.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some()
+}
+
+pub fn setup_music(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.spawn(AudioBundle {
+        source: asset_server.load("music.ogg"),
+        settings: PlaybackSettings {
+            mode: PlaybackMode::Loop,
+            ..Default::default()
+        },
+        ..default()
+    });
+}
+
+pub fn start_spotlight_moment(
+    mut spotlight: ResMut<SpotlightMoment>,
+    mut ended: EventReader<RoundEnded>,
+) {
+    if ended.iter().any(|round| round.decisive) {
+        spotlight.timer.reset();
+    }
+}
+
+pub fn drive_stage_lights(
+    time: Res<Time>,
+    mut spotlight: ResMut<SpotlightMoment>,
+    fighters: Query<&Health>,
+    mut lights: Query<(&StageLight, &mut PointLight)>,
+) {
+    spotlight.timer.tick(time.delta());
+    let beat = time.elapsed_seconds() * MUSIC_BPM / 60.0;
+    let pulse = 1.0 + PULSE_AMOUNT * (beat * std::f32::consts::TAU).cos();
+    // Ease back in over the second half of the moment.
+    let dim = if spotlight.timer.finished() {
+        1.0
+    } else {
+        let fade = ((spotlight.timer.percent() - 0.5) * 2.0).max(0.0);
+        SPOTLIGHT_DIM + (1.0 - SPOTLIGHT_DIM) * fade
+    };
+    let danger = fighters
+        .iter()
+        .any(|health| health.current / health.max < DANGER_HEALTH);
+    for (stage_light, mut light) in lights.iter_mut() {
+        light.intensity = stage_light.intensity * pulse * dim;
+        light.color = if danger { DANGER_COLOR } else { Color::WHITE };
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::{
+    character::Character,
+    combat::{Hitbox, Hurtbox},
+};
+
+// Distance between neighbouring pairs, far enough apart that one pair's limbs
+// never reach the next pair's bodies.
+const LANE_SPACING: f32 = 3.0;
+// Real seconds between the stats lines written to the log.
+const REPORT_SECONDS: f32 = 5.0;
+
+// How many AI pairs the stress test fights at once. Each pair is a normal
+// fight on its own lane, so the costs of animation, physics, hit detection
+// and effects all grow with the count.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct StressTest {
+    pub pairs: usize,
+}
+
+impl Default for StressTest {
+    fn default() -> Self {
+        StressTest { pairs: 16 }
+    }
+}
+
+impl StressTest {
+    // Offset for a pair's lane. Pairs alternate in front of and behind the
+    // first so the camera stays centred on the crowd.
+    pub fn lane(pair: usize) -> Vec3 {
+        let step = (pair + 1) / 2;
+        let side = if pair % 2 == 0 { 1.0 } else { -1.0 };
+        Vec3::Z * side * step as f32 * LANE_SPACING
+    }
+}
+
+// Logs the frame time next to the number of fighters and collision points,
+// so a slowdown can be matched to how much was on screen.
+pub fn report_stress_stats(
+    time: Res<Time<Real>>,
+    diagnostics: Res<DiagnosticsStore>,
+    mut since_report: Local<f32>,
+    fighters: Query<(), With<Character>>,
+    limbs: Query<(), Or<(With<Hitbox>, With<Hurtbox>)>>,
+) {
+    *since_report += time.delta_seconds();
+    if *since_report < REPORT_SECONDS {
+        return;
+    }
+    *since_report = 0.0;
+    let frame_time = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .unwrap_or_default();
+    info!(
+        "stress: {} fighters, {} colliders, {frame_time:.2} ms/frame",
+        fighters.iter().count(),
+        limbs.iter().count(),
+    );
+}
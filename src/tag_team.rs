@@ -0,0 +1,304 @@
+use bevy::{prelude::*, scene::SceneInstanceReady};
+
+use crate::{
+    animation::{FighterAnimations, FighterSfx, LookAt, RootMotion},
+    asset_cache::{character_asset, AssetCache},
+    body_layers::LegLayer,
+    character::{AnimationState, Character, CharacterState, Health, Knockback, JUMP_VELOCITY},
+    combat::HitLanded,
+    foot_ik::FootIk,
+    input::ControlInput,
+    roster::{CharacterDef, Roster},
+    round::{Round, RoundPhase, RoundStarted},
+    skins::{HitFlash, Skin, SkinApplied},
+};
+
+// Share of each hit's damage the fighter can win back by resting on the
+// bench, and how much of it comes back a second there.
+const RED_HEALTH_SHARE: f32 = 0.5;
+const RED_HEALTH_REGEN: f32 = 4.0;
+// Seconds after a tag before the side can tag again.
+const TAG_COOLDOWN: f32 = 3.0;
+// Seconds the fighter coming in can't be hit, covering their leap in.
+const TAG_ENTRANCE: f32 = 0.6;
+
+// Everything about the fighter waiting off-screen that has to come back with
+// them when they're tagged in.
+#[derive(Clone, Debug)]
+pub struct Benched {
+    pub character: Handle<CharacterDef>,
+    pub name: String,
+    pub skin: usize,
+    pub health: Health,
+    pub red: f32,
+}
+
+// A side fighting as a pair. The fighter entity stays the same when they
+// swap; the character on it changes places with `partner`.
+#[derive(Component, Clone, Debug)]
+pub struct TagTeam {
+    pub partner: Benched,
+    // Damage the fighter on screen could win back by tagging out.
+    pub red: f32,
+    cooldown: f32,
+}
+
+impl TagTeam {
+    pub fn new(partner: Benched) -> Self {
+        TagTeam {
+            partner,
+            red: 0.0,
+            cooldown: 0.0,
+        }
+    }
+}
+
+// The fighter has just been tagged in and can't be hit until it ends.
+#[derive(Component, Clone, Debug)]
+pub struct TagEntrance {
+    left: f32,
+}
+
+// The fighter's model was swapped for another, whose meshes still need the
+// fighter's palette and outline once its scene is ready.
+#[derive(Component)]
+pub struct SwappedModel;
+
+// Everything on a fighter that belongs to its model rather than the fight,
+// found again on the new model's bones once its scene is ready.
+type ModelParts = (LookAt, LegLayer, FootIk, RootMotion, HitFlash);
+
+fn model(cache: &AssetCache, id: &str) -> (Handle<Scene>, FighterAnimations, FighterSfx) {
+    (
+        cache.get::<Scene>(&character_asset(id, "scene")),
+        FighterAnimations::cached(cache, id),
+        FighterSfx::cached(cache, id),
+    )
+}
+
+// Puts a fighter restored from a snapshot back in the model they had then,
+// when a tag has swapped it since. Their Character has to be restored first.
+pub fn restore_model(world: &mut World, fighter: Entity, scene: Handle<Scene>) {
+    let Some(character) = world.get::<Character>(fighter) else {
+        return;
+    };
+    let Some(id) = world.resource::<Roster>().id(&character.0) else {
+        return;
+    };
+    let (_, animations, sfx) = model(world.resource::<AssetCache>(), id);
+    world
+        .entity_mut(fighter)
+        .remove::<ModelParts>()
+        .insert((scene, animations, sfx, SwappedModel));
+}
+
+// Puts the partner on screen in the fighter's place, leaping in, and benches
+// the fighter that was there. The new model is swapped in behind the scenes
+// and picks up its bones and colliders when its scene is ready, like a fresh
+// spawn does.
+#[allow(clippy::too_many_arguments)]
+fn swap_in(
+    commands: &mut Commands,
+    cache: &AssetCache,
+    roster: &Roster,
+    fighter: Entity,
+    tag: &mut TagTeam,
+    character: &mut Character,
+    name: &mut Name,
+    skin: &mut Skin,
+    health: &mut Health,
+    state: &mut CharacterState,
+    knockback: &mut Knockback,
+) {
+    let outgoing = Benched {
+        character: character.0.clone(),
+        name: name.to_string(),
+        skin: skin.0,
+        health: *health,
+        red: if health.current > 0.0 { tag.red } else { 0.0 },
+    };
+    let incoming = std::mem::replace(&mut tag.partner, outgoing);
+    character.0 = incoming.character;
+    *name = Name::new(incoming.name);
+    skin.0 = incoming.skin;
+    *health = incoming.health;
+    tag.red = incoming.red;
+    tag.cooldown = TAG_COOLDOWN;
+    *state = CharacterState {
+        airborne: true,
+        vertical_velocity: JUMP_VELOCITY,
+        ..default()
+    };
+    state.update_player_state(AnimationState::Jumping);
+    *knockback = Knockback::default();
+
+    let mut entity = commands.entity(fighter);
+    entity
+        .remove::<ModelParts>()
+        .insert((TagEntrance { left: TAG_ENTRANCE }, SwappedModel));
+    if let Some(id) = roster.id(&character.0) {
+        entity.insert(model(cache, id));
+    }
+}
+
+type TagFighter<'a> = (
+    Entity,
+    &'a mut TagTeam,
+    &'a mut ControlInput,
+    &'a mut Character,
+    &'a mut Name,
+    &'a mut Skin,
+    &'a mut Health,
+    &'a mut CharacterState,
+    &'a mut Knockback,
+);
+
+// Runs on the simulation tick ahead of the fighters' own input. A tag only
+// goes through from the ground, outside a move, with a partner still
+// standing. Benched partners win back their red health meanwhile.
+pub fn tag_fighters(
+    mut commands: Commands,
+    time: Res<Time>,
+    cache: Res<AssetCache>,
+    roster: Res<Roster>,
+    mut fighters: Query<TagFighter>,
+    mut entrances: Query<(Entity, &mut TagEntrance)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut entrance) in entrances.iter_mut() {
+        entrance.left -= dt;
+        if entrance.left <= 0.0 {
+            commands.entity(entity).remove::<TagEntrance>();
+        }
+    }
+    for (
+        fighter,
+        mut tag,
+        mut input,
+        mut character,
+        mut name,
+        mut skin,
+        mut health,
+        mut state,
+        mut knockback,
+    ) in fighters.iter_mut()
+    {
+        tag.cooldown = (tag.cooldown - dt).max(0.0);
+        let partner = &mut tag.partner;
+        let regained = partner.red.min(RED_HEALTH_REGEN * dt);
+        if partner.health.current > 0.0 {
+            partner.health.current = (partner.health.current + regained).min(partner.health.max);
+            partner.red -= regained;
+        }
+
+        let wants_tag = std::mem::take(&mut input.tag);
+        let ready = tag.cooldown <= 0.0
+            && tag.partner.health.current > 0.0
+            && !state.airborne
+            && state.current_animation_timer.is_none();
+        if wants_tag && ready {
+            swap_in(
+                &mut commands,
+                &cache,
+                &roster,
+                fighter,
+                &mut tag,
+                &mut character,
+                &mut name,
+                &mut skin,
+                &mut health,
+                &mut state,
+                &mut knockback,
+            );
+        }
+    }
+}
+
+// A knocked out fighter with a partner still standing isn't the end of the
+// round: the partner comes straight in, whatever was going on.
+pub fn tag_in_on_knockout(
+    mut commands: Commands,
+    round: Res<Round>,
+    cache: Res<AssetCache>,
+    roster: Res<Roster>,
+    mut fighters: Query<TagFighter>,
+) {
+    if round.phase != RoundPhase::Fighting {
+        return;
+    }
+    for (
+        fighter,
+        mut tag,
+        _,
+        mut character,
+        mut name,
+        mut skin,
+        mut health,
+        mut state,
+        mut knockback,
+    ) in fighters.iter_mut()
+    {
+        if health.current <= 0.0 && tag.partner.health.current > 0.0 {
+            swap_in(
+                &mut commands,
+                &cache,
+                &roster,
+                fighter,
+                &mut tag,
+                &mut character,
+                &mut name,
+                &mut skin,
+                &mut health,
+                &mut state,
+                &mut knockback,
+            );
+        }
+    }
+}
+
+// Part of every hit taken stays recoverable, up to the health actually lost.
+pub fn bank_red_health(
+    mut hits: EventReader<HitLanded>,
+    mut fighters: Query<(&mut TagTeam, &Health)>,
+) {
+    for hit in hits.iter() {
+        let Ok((mut tag, health)) = fighters.get_mut(hit.defender) else {
+            continue;
+        };
+        let lost = (health.max - health.current).max(0.0);
+        tag.red = (tag.red + hit.damage * RED_HEALTH_SHARE).min(lost);
+        if health.current <= 0.0 {
+            tag.red = 0.0;
+        }
+    }
+}
+
+// Each round starts both partners fresh; the fighter on screen is reset with
+// everyone else by the round itself.
+pub fn reset_partners(mut started: EventReader<RoundStarted>, mut fighters: Query<&mut TagTeam>) {
+    if started.iter().next().is_none() {
+        return;
+    }
+    for mut tag in fighters.iter_mut() {
+        tag.red = 0.0;
+        tag.cooldown = 0.0;
+        tag.partner.red = 0.0;
+        tag.partner.health.current = tag.partner.health.max;
+    }
+}
+
+// The new model's meshes need the fighter's palette and outline, which only
+// happens for a fighter that hasn't had them applied.
+pub fn reskin_tagged_fighters(
+    mut commands: Commands,
+    mut ready: EventReader<SceneInstanceReady>,
+    fighters: Query<(), With<SwappedModel>>,
+) {
+    for SceneInstanceReady { parent: fighter } in ready.iter() {
+        if fighters.contains(*fighter) {
+            commands
+                .entity(*fighter)
+                .remove::<(SkinApplied, SwappedModel)>();
+        }
+    }
+}
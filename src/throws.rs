@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{AnimationState, CharacterState, Health, Knockback, KnockedDown, Opponent},
+    input::ControlInput,
+    round::MatchRules,
+};
+
+// Origins closer than this are in throw range.
+pub const THROW_RANGE: f32 = 1.0;
+// Seconds the thrown fighter has to press throw and break out of it. The
+// throw lands when it runs out.
+const TECH_WINDOW: f32 = 0.3;
+// The thrower is stuck a little longer than that, whichever way it goes.
+const THROW_RECOVERY: f32 = 0.3;
+pub const THROW_DAMAGE: f32 = 12.0;
+const THROW_KNOCKBACK: f32 = 3.0;
+// Seconds on the floor after being thrown.
+const THROW_KNOCKDOWN: f32 = 0.8;
+// A broken throw pushes both fighters apart and holds them in their guard.
+const TECH_PUSH: f32 = 3.0;
+const TECH_RECOIL: f32 = 0.3;
+// Height off the fighters' origins that throw effects go at.
+const THROW_HEIGHT: f32 = 1.0;
+
+// Held by a fighter being thrown, until they break out or `window` runs out.
+#[derive(Clone, Debug)]
+pub struct Grab {
+    pub thrower: Entity,
+    pub window: Timer,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ThrowLanded {
+    pub thrower: Entity,
+    pub victim: Entity,
+    pub damage: f32,
+    pub position: Vec3,
+}
+
+// The thrown fighter broke out in time.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ThrowTeched {
+    pub fighters: [Entity; 2],
+    pub position: Vec3,
+}
+
+// Free to act and on the ground.
+fn can_throw(state: &CharacterState) -> bool {
+    !state.airborne && state.current_animation_timer.is_none() && state.grabbed.is_none()
+}
+
+// Throws catch fighters standing, walking, blocking or in the middle of an
+// attack, but not anyone already reeling from a hit or off their feet.
+fn throwable(state: &CharacterState) -> bool {
+    let stunned = state.current_animation_timer.is_some() && state.move_phase.is_none();
+    !state.airborne && !state.downed && !stunned && state.grabbed.is_none()
+}
+
+// Runs before process_input so throw presses don't also start attacks, and
+// only one of two fighters throwing on the same tick gets the throw.
+pub fn start_throws(
+    mut fighters: Query<(
+        Entity,
+        &mut CharacterState,
+        &mut ControlInput,
+        &Transform,
+        &Opponent,
+    )>,
+) {
+    let attempts: Vec<(Entity, Entity)> = fighters
+        .iter_mut()
+        .filter_map(|(thrower, _, mut input, _, opponent)| {
+            std::mem::take(&mut input.throw).then_some((thrower, opponent.0))
+        })
+        .collect();
+    for (thrower, victim) in attempts {
+        let Ok([thrower_fighter, victim_fighter]) = fighters.get_many_mut([thrower, victim]) else {
+            continue;
+        };
+        let (_, mut thrower_state, _, thrower_transform, _) = thrower_fighter;
+        let (_, mut victim_state, _, victim_transform, _) = victim_fighter;
+        let distance = (thrower_transform.translation - victim_transform.translation).length();
+        if distance > THROW_RANGE || !can_throw(&thrower_state) || !throwable(&victim_state) {
+            continue;
+        }
+        thrower_state.stun(TECH_WINDOW + THROW_RECOVERY, AnimationState::Idle);
+        victim_state.stun(TECH_WINDOW, AnimationState::Idle);
+        victim_state.grabbed = Some(Grab {
+            thrower,
+            window: Timer::from_seconds(TECH_WINDOW, TimerMode::Once),
+        });
+    }
+}
+
+// A thrown fighter pressing throw inside the window breaks out; otherwise
+// the throw lands when it closes and puts them on the floor.
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_throws(
+    time: Res<Time>,
+    rules: Res<MatchRules>,
+    mut fighters: Query<(
+        Entity,
+        &mut CharacterState,
+        &mut ControlInput,
+        &mut Knockback,
+        &mut Health,
+        &Transform,
+    )>,
+    mut landed: EventWriter<ThrowLanded>,
+    mut teched: EventWriter<ThrowTeched>,
+    mut knocked_down: EventWriter<KnockedDown>,
+) {
+    let grabs: Vec<(Entity, Entity)> = fighters
+        .iter()
+        .filter_map(|(victim, state, ..)| Some((victim, state.grabbed.as_ref()?.thrower)))
+        .collect();
+    for (victim, thrower) in grabs {
+        let Ok([victim_fighter, thrower_fighter]) = fighters.get_many_mut([victim, thrower]) else {
+            // The thrower has gone, as when a tag swaps them out.
+            if let Ok((_, mut state, ..)) = fighters.get_mut(victim) {
+                state.grabbed = None;
+            }
+            continue;
+        };
+        let (_, mut state, mut input, mut knockback, mut health, transform) = victim_fighter;
+        let (_, mut thrower_state, _, mut thrower_knockback, _, thrower_transform) =
+            thrower_fighter;
+        let Some(grab) = state.grabbed.as_mut() else {
+            continue;
+        };
+        let closed = grab.window.tick(time.delta()).finished();
+        let tech = std::mem::take(&mut input.throw);
+        let position =
+            (transform.translation + thrower_transform.translation) / 2.0 + Vec3::Y * THROW_HEIGHT;
+        let away = (transform.translation.x - thrower_transform.translation.x).signum();
+        if tech && !closed {
+            state.grabbed = None;
+            state.stun(TECH_RECOIL, AnimationState::Blocking);
+            thrower_state.stun(TECH_RECOIL, AnimationState::Blocking);
+            knockback.0 += Vec3::X * away * TECH_PUSH;
+            thrower_knockback.0 -= Vec3::X * away * TECH_PUSH;
+            teched.send(ThrowTeched {
+                fighters: [victim, thrower],
+                position,
+            });
+        } else if closed {
+            state.grabbed = None;
+            let damage = THROW_DAMAGE * rules.damage_multiplier;
+            health.current = (health.current - damage).max(0.0);
+            knockback.0 += Vec3::X * away * THROW_KNOCKBACK;
+            state.stun(THROW_KNOCKDOWN, AnimationState::Idle);
+            state.downed = true;
+            knocked_down.send(KnockedDown {
+                fighter: victim,
+                position: transform.translation,
+                hits: 0,
+            });
+            landed.send(ThrowLanded {
+                thrower,
+                victim,
+                damage,
+                position,
+            });
+        }
+    }
+}
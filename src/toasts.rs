@@ -0,0 +1,206 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    character::Team, combat::HitLanded, comeback::ComebackTriggered, dizzy::Dizzied, hud::Hud,
+    locale::Strings, round::RoundStarted, settings::Settings, tuning::ComboCounter,
+};
+
+// Real seconds a toast stays up, the last of them spent fading out.
+const TOAST_SECONDS: f32 = 1.8;
+const TOAST_FADE: f32 = 0.4;
+const MAX_TOASTS: usize = 3;
+// Combos get a toast every this many hits.
+const COMBO_TOAST_STEP: usize = 5;
+// Seconds after being hit or blocking in which a hit back is a reversal.
+const REVERSAL_WINDOW: f32 = 0.4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ToastKind {
+    FirstAttack,
+    Counter,
+    Reversal,
+    Combo(usize),
+    Comeback,
+    Dizzy,
+}
+
+impl ToastKind {
+    pub fn text(&self, strings: &Strings) -> String {
+        match self {
+            ToastKind::FirstAttack => strings.get("toast.first_attack"),
+            ToastKind::Counter => strings.get("toast.counter"),
+            ToastKind::Reversal => strings.get("toast.reversal"),
+            ToastKind::Combo(hits) => strings.fill("toast.combo", &[("hits", hits)]),
+            ToastKind::Comeback => strings.get("toast.comeback"),
+            ToastKind::Dizzy => strings.get("toast.dizzy"),
+        }
+    }
+}
+
+// Puts a short callout up on one fighter's side of the screen. Anything can
+// send these; the combat ones come from `toast_combat_events`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShowToast {
+    pub team: Team,
+    pub kind: ToastKind,
+}
+
+// Each side's stack of toasts under its health bar, newest last.
+#[derive(Component)]
+pub struct ToastStack(pub Team);
+
+#[derive(Component)]
+pub struct Toast {
+    pub timer: Timer,
+}
+
+pub fn setup_toasts(mut commands: Commands) {
+    for team in [Team::One, Team::Two] {
+        let (left, right, align) = match team {
+            Team::One => (Val::Px(16.0), Val::Auto, AlignItems::FlexStart),
+            Team::Two => (Val::Auto, Val::Px(16.0), AlignItems::FlexEnd),
+        };
+        commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(72.0),
+                    left,
+                    right,
+                    flex_direction: FlexDirection::Column,
+                    align_items: align,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                z_index: ZIndex::Global(40),
+                ..default()
+            },
+            ToastStack(team),
+            Hud,
+        ));
+    }
+}
+
+// The first clean hit of a round, counter hits, hits straight back out of
+// being hit or blocking, long combos, comebacks and dizzying the opponent.
+#[allow(clippy::too_many_arguments)]
+pub fn toast_combat_events(
+    time: Res<Time>,
+    mut first_landed: Local<bool>,
+    mut last_hurt: Local<HashMap<Entity, f32>>,
+    mut round_started: EventReader<RoundStarted>,
+    mut hits: EventReader<HitLanded>,
+    mut comebacks: EventReader<ComebackTriggered>,
+    mut dizzied: EventReader<Dizzied>,
+    teams: Query<&Team>,
+    combos: Query<&ComboCounter>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if round_started.iter().next().is_some() {
+        *first_landed = false;
+        last_hurt.clear();
+    }
+    let now = time.elapsed_seconds();
+    for hit in hits.iter() {
+        let hurt_at = last_hurt.insert(hit.defender, now);
+        if hit.blocked {
+            continue;
+        }
+        let Ok(team) = teams.get(hit.attacker) else {
+            continue;
+        };
+        let mut send = |kind| {
+            toasts.send(ShowToast { team: *team, kind });
+        };
+        if !*first_landed {
+            *first_landed = true;
+            send(ToastKind::FirstAttack);
+        }
+        if hit.counter {
+            send(ToastKind::Counter);
+        } else if last_hurt
+            .get(&hit.attacker)
+            .is_some_and(|at| now - at <= REVERSAL_WINDOW)
+            && !hurt_at.is_some_and(|at| now - at <= REVERSAL_WINDOW)
+        {
+            send(ToastKind::Reversal);
+        }
+        let hits = combos.get(hit.defender).map_or(0, |combo| combo.hits());
+        if hits >= COMBO_TOAST_STEP && hits % COMBO_TOAST_STEP == 0 {
+            send(ToastKind::Combo(hits));
+        }
+    }
+    for comeback in comebacks.iter() {
+        toasts.send(ShowToast {
+            team: comeback.team,
+            kind: ToastKind::Comeback,
+        });
+    }
+    for dizzy in dizzied.iter() {
+        if let Ok(team) = teams.get(dizzy.fighter) {
+            toasts.send(ShowToast {
+                team: team.opponent(),
+                kind: ToastKind::Dizzy,
+            });
+        }
+    }
+}
+
+pub fn show_toasts(
+    mut commands: Commands,
+    strings: Strings,
+    settings: Res<Settings>,
+    mut events: EventReader<ShowToast>,
+    stacks: Query<(Entity, &ToastStack)>,
+) {
+    let colors = settings.palette.colors();
+    for toast in events.iter() {
+        let Some((stack, _)) = stacks.iter().find(|(_, stack)| stack.0 == toast.team) else {
+            continue;
+        };
+        let entity = commands
+            .spawn((
+                TextBundle::from_section(
+                    toast.kind.text(&strings),
+                    TextStyle {
+                        font_size: 28.0,
+                        color: colors.team(toast.team),
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+                Toast {
+                    timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+                },
+            ))
+            .id();
+        commands.entity(stack).add_child(entity);
+    }
+}
+
+// Real time, like the banners, so slow motion doesn't hold toasts up. The
+// oldest go early once a stack is full.
+pub fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    stacks: Query<&Children, With<ToastStack>>,
+    mut toasts: Query<(&mut Toast, &mut Text, &mut BackgroundColor)>,
+) {
+    for children in stacks.iter() {
+        let overflow = children.len().saturating_sub(MAX_TOASTS);
+        for (index, child) in children.iter().enumerate() {
+            let Ok((mut toast, mut text, mut background)) = toasts.get_mut(*child) else {
+                continue;
+            };
+            if toast.timer.tick(time.delta()).finished() || index < overflow {
+                commands.entity(*child).despawn_recursive();
+                continue;
+            }
+            let alpha = (toast.timer.remaining_secs() / TOAST_FADE).min(1.0);
+            for section in text.sections.iter_mut() {
+                section.style.color.set_a(alpha);
+            }
+            background.0.set_a(0.5 * alpha);
+        }
+    }
+}
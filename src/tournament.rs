@@ -0,0 +1,320 @@
+use bevy::prelude::*;
+
+use crate::{character::Team, combat::FightEnded, locale::Strings, AppState};
+
+pub const MAX_ENTRANTS: usize = 8;
+const MAX_NAME_LENGTH: usize = 12;
+const WINNER_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+const PENDING_COLOR: Color = Color::rgb(0.6, 0.6, 0.65);
+
+// One pairing in the bracket. `None` on a side is a bye in the first round,
+// and a winner still to be decided after it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct BracketMatch {
+    // Entrant indices; the first side plays as P1.
+    pub sides: [Option<usize>; 2],
+    pub winner: Option<usize>,
+}
+
+// A single-elimination tournament between local players. Names are typed in
+// first; the bracket is drawn once entry closes, first round first.
+#[derive(Resource, Default, Debug)]
+pub struct Tournament {
+    pub entrants: Vec<String>,
+    pub typing: String,
+    pub rounds: Vec<Vec<BracketMatch>>,
+}
+
+impl Tournament {
+    pub fn started(&self) -> bool {
+        !self.rounds.is_empty()
+    }
+
+    // Entrants are seeded in the order they were entered, the first meeting
+    // the last. Empty slots become byes, which go straight through.
+    pub fn draw_bracket(&mut self) {
+        let entrants = self.entrants.len();
+        let size = entrants.next_power_of_two().max(2);
+        let mut round: Vec<BracketMatch> = (0..size / 2)
+            .map(|seed| {
+                let opponent = size - 1 - seed;
+                BracketMatch {
+                    sides: [Some(seed), (opponent < entrants).then_some(opponent)],
+                    winner: None,
+                }
+            })
+            .collect();
+        self.rounds.clear();
+        loop {
+            let next = round.len() / 2;
+            self.rounds.push(round);
+            if next == 0 {
+                break;
+            }
+            round = vec![BracketMatch::default(); next];
+        }
+        for index in 0..self.rounds[0].len() {
+            if let [Some(entrant), None] = self.rounds[0][index].sides {
+                self.advance(0, index, entrant);
+            }
+        }
+    }
+
+    fn advance(&mut self, round: usize, index: usize, winner: usize) {
+        self.rounds[round][index].winner = Some(winner);
+        if let Some(next) = self.rounds.get_mut(round + 1) {
+            next[index / 2].sides[index % 2] = Some(winner);
+        }
+    }
+
+    // Round and position of the next match to play: the first whose sides
+    // are both known and that hasn't been won.
+    pub fn next_match(&self) -> Option<(usize, usize)> {
+        self.rounds.iter().enumerate().find_map(|(round, matches)| {
+            matches
+                .iter()
+                .position(|pairing| {
+                    pairing.winner.is_none() && pairing.sides.iter().all(Option::is_some)
+                })
+                .map(|index| (round, index))
+        })
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        self.rounds.last()?.first()?.winner
+    }
+
+    pub fn name(&self, entrant: Option<usize>) -> &str {
+        entrant
+            .and_then(|entrant| self.entrants.get(entrant))
+            .map_or("", String::as_str)
+    }
+}
+
+#[derive(Component)]
+pub struct BracketScreen;
+
+// Drawn over the loading cover, which stays down until the fight starts.
+pub fn setup_bracket_screen(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(101),
+            ..default()
+        },
+        BracketScreen,
+    ));
+}
+
+pub fn close_bracket_screen(mut commands: Commands, screens: Query<Entity, With<BracketScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Typing adds to the name being entered and Enter adds it to the list; with
+// nothing typed, Enter draws the bracket once there are two names. After
+// that, Enter sends the next pair to character select, or once there's a
+// champion, clears the way for a new tournament.
+pub fn tournament_keys(
+    keys: Res<Input<KeyCode>>,
+    mut typed: EventReader<ReceivedCharacter>,
+    mut tournament: ResMut<Tournament>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if tournament.started() {
+        typed.clear();
+        if !keys.just_pressed(KeyCode::Return) {
+            return;
+        }
+        if tournament.champion().is_some() {
+            *tournament = Tournament::default();
+        } else {
+            next_state.set(AppState::CharacterSelect);
+        }
+        return;
+    }
+
+    for typed in typed.iter() {
+        let full = tournament.typing.chars().count() >= MAX_NAME_LENGTH;
+        if !typed.char.is_control() && !full {
+            tournament.typing.push(typed.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) && tournament.typing.pop().is_none() {
+        tournament.entrants.pop();
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        let name = tournament.typing.trim().to_owned();
+        if !name.is_empty() {
+            if tournament.entrants.len() < MAX_ENTRANTS {
+                tournament.entrants.push(name);
+            }
+            tournament.typing.clear();
+        } else if tournament.entrants.len() >= 2 {
+            tournament.draw_bracket();
+        }
+    }
+}
+
+fn line(value: String, font_size: f32, color: Color) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size,
+            color,
+            ..default()
+        },
+    )
+}
+
+fn draw_entry(screen: &mut ChildBuilder, tournament: &Tournament, strings: &Strings) {
+    let count = tournament.entrants.len();
+    screen.spawn(line(
+        strings.fill(
+            "tournament.entry",
+            &[("count", &count), ("max", &MAX_ENTRANTS)],
+        ),
+        32.0,
+        Color::WHITE,
+    ));
+    for (index, name) in tournament.entrants.iter().enumerate() {
+        screen.spawn(line(format!("{}. {name}", index + 1), 24.0, Color::WHITE));
+    }
+    if count < MAX_ENTRANTS {
+        let prompt = format!("{}. {}_", count + 1, tournament.typing);
+        screen.spawn(line(prompt, 24.0, WINNER_COLOR));
+    }
+    screen.spawn(line(
+        strings.get("tournament.entry_hint"),
+        20.0,
+        PENDING_COLOR,
+    ));
+}
+
+fn draw_bracket(screen: &mut ChildBuilder, tournament: &Tournament, strings: &Strings) {
+    screen
+        .spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(48.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|columns| {
+            for matches in &tournament.rounds {
+                columns
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        for pairing in matches {
+                            for side in pairing.sides {
+                                let color = match (side, pairing.winner) {
+                                    (Some(_), Some(winner)) if side == Some(winner) => WINNER_COLOR,
+                                    (Some(_), _) => Color::WHITE,
+                                    (None, _) => PENDING_COLOR,
+                                };
+                                let name = match side {
+                                    Some(_) => tournament.name(side).to_owned(),
+                                    None if pairing.winner.is_some() => {
+                                        strings.get("tournament.bye")
+                                    }
+                                    None => "---".to_owned(),
+                                };
+                                column.spawn(line(name, 22.0, color));
+                            }
+                        }
+                    });
+            }
+        });
+
+    if let Some(champion) = tournament.champion() {
+        let name = tournament.name(Some(champion));
+        screen.spawn(line(
+            strings.fill("tournament.champion", &[("name", &name)]),
+            56.0,
+            WINNER_COLOR,
+        ));
+        screen.spawn(line(strings.get("tournament.restart"), 20.0, PENDING_COLOR));
+    } else if let Some((round, index)) = tournament.next_match() {
+        let [one, two] = tournament.rounds[round][index].sides;
+        screen.spawn(line(
+            strings.fill(
+                "tournament.next",
+                &[
+                    ("one", &tournament.name(one)),
+                    ("two", &tournament.name(two)),
+                ],
+            ),
+            28.0,
+            Color::WHITE,
+        ));
+        screen.spawn(line(
+            strings.get("tournament.next_hint"),
+            20.0,
+            PENDING_COLOR,
+        ));
+    }
+}
+
+pub fn update_bracket_screen(
+    mut commands: Commands,
+    tournament: Res<Tournament>,
+    strings: Strings,
+    screens: Query<Entity, Added<BracketScreen>>,
+    all_screens: Query<Entity, With<BracketScreen>>,
+) {
+    if screens.is_empty() && !tournament.is_changed() && !strings.changed() {
+        return;
+    }
+    for screen in all_screens.iter() {
+        commands
+            .entity(screen)
+            .despawn_descendants()
+            .with_children(|screen| {
+                if tournament.started() {
+                    draw_bracket(screen, &tournament, &strings);
+                } else {
+                    draw_entry(screen, &tournament, &strings);
+                }
+            });
+    }
+}
+
+// The fighter on P1's side is the first name of the pairing being played.
+// A drawn match is played again.
+pub fn record_tournament_result(
+    mut tournament: ResMut<Tournament>,
+    mut ended: EventReader<FightEnded>,
+) {
+    for fight in ended.iter() {
+        let Some((round, index)) = tournament.next_match() else {
+            continue;
+        };
+        let [one, two] = tournament.rounds[round][index].sides;
+        let winner = match fight.winner {
+            Some(Team::One) => one,
+            Some(Team::Two) => two,
+            None => None,
+        };
+        if let Some(winner) = winner {
+            tournament.advance(round, index, winner);
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    character::{CharacterState, Health, Knockback, Meter, Opponent, Team, STAGE_HALF_WIDTH},
+    combat::HitLanded,
+    input::ControlInput,
+    input_display::InputHistory,
+    locale::Strings,
+    menu::clickable,
+    moves::MoveId,
+    rng::GameRng,
+    snapshot::{roll_back, Snapshots},
+    tuning::ComboCounter,
+    SimulationTick, SIMULATION_HZ,
+};
+
+pub const STANCE_KEY: KeyCode = KeyCode::F1;
+pub const BLOCK_MODE_KEY: KeyCode = KeyCode::F2;
+pub const COUNTER_KEY: KeyCode = KeyCode::F3;
+pub const RECORD_KEY: KeyCode = KeyCode::F4;
+pub const REPLAY_KEY: KeyCode = KeyCode::F5;
+pub const REWIND_KEY: KeyCode = KeyCode::Key4;
+pub const REWIND_BUTTON: GamepadButtonType = GamepadButtonType::Select;
+pub const LEFT_CORNER_KEY: KeyCode = KeyCode::Key1;
+pub const MIDSCREEN_KEY: KeyCode = KeyCode::Key2;
+pub const RIGHT_CORNER_KEY: KeyCode = KeyCode::Key3;
+
+pub const MAX_RECORDING_TICKS: usize = 600;
+// How long "block after first hit" keeps the guard up after the last hit.
+pub const BLOCK_AFTER_HIT_TICKS: u32 = 60;
+// How far back the rewind key takes the fight.
+const REWIND_TICKS: u32 = 5 * SIMULATION_HZ as u32;
+// Distance between the fighters after a reset to a corner.
+const CORNER_GAP: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DummyStance {
+    #[default]
+    Stand,
+    Crouch,
+    Jump,
+}
+
+impl DummyStance {
+    fn name_key(self) -> &'static str {
+        match self {
+            DummyStance::Stand => "training.stance.stand",
+            DummyStance::Crouch => "training.stance.crouch",
+            DummyStance::Jump => "training.stance.jump",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DummyBlock {
+    #[default]
+    Never,
+    All,
+    AfterFirstHit,
+    Random,
+}
+
+impl DummyBlock {
+    fn name_key(self) -> &'static str {
+        match self {
+            DummyBlock::Never => "training.block.never",
+            DummyBlock::All => "training.block.all",
+            DummyBlock::AfterFirstHit => "training.block.after_first_hit",
+            DummyBlock::Random => "training.block.random",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RecorderState {
+    #[default]
+    Idle,
+    Recording,
+    Replaying(usize),
+}
+
+// The player's inputs captured while recording, replayed on the dummy.
+#[derive(Default)]
+pub struct Recorder {
+    pub state: RecorderState,
+    pub frames: Vec<ControlInput>,
+}
+
+// Puts both fighters back on their feet at full health and meter. Fighters
+// never change sides, so a corner is always held by whoever starts on that
+// side: the player in the left one, the dummy in the right.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResetPositions {
+    Midscreen,
+    LeftCorner,
+    RightCorner,
+}
+
+impl ResetPositions {
+    fn transform(self, team: Team) -> Transform {
+        let start = team.start_transform();
+        let x = match (self, team) {
+            (ResetPositions::Midscreen, _) => return start,
+            (ResetPositions::LeftCorner, Team::One) => -STAGE_HALF_WIDTH,
+            (ResetPositions::LeftCorner, Team::Two) => -STAGE_HALF_WIDTH + CORNER_GAP,
+            (ResetPositions::RightCorner, Team::One) => STAGE_HALF_WIDTH - CORNER_GAP,
+            (ResetPositions::RightCorner, Team::Two) => STAGE_HALF_WIDTH,
+        };
+        start.with_translation(Vec3::new(x, 0.0, start.translation.z))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct DummySettings {
+    pub stance: DummyStance,
+    pub block: DummyBlock,
+    pub auto_counter: bool,
+    pub recorder: Recorder,
+}
+
+#[derive(Component, Default, Clone)]
+pub struct TrainingDummy {
+    pub block_until: u32,
+    pub random_block: bool,
+    pub counter_pending: bool,
+    pub opponent_was_attacking: bool,
+}
+
+#[derive(Component)]
+pub struct DummyPanel;
+
+#[derive(Component)]
+pub struct DummyPanelTitle;
+
+// One line of the dummy panel, and what clicking it does: the same as its
+// key.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DummyControl {
+    Stance,
+    Block,
+    AutoCounter,
+    Record,
+    Replay,
+    Rewind,
+    Reset(ResetPositions),
+}
+
+impl DummyControl {
+    const ALL: [DummyControl; 9] = [
+        DummyControl::Stance,
+        DummyControl::Block,
+        DummyControl::AutoCounter,
+        DummyControl::Record,
+        DummyControl::Replay,
+        DummyControl::Rewind,
+        DummyControl::Reset(ResetPositions::LeftCorner),
+        DummyControl::Reset(ResetPositions::Midscreen),
+        DummyControl::Reset(ResetPositions::RightCorner),
+    ];
+
+    fn key(self) -> KeyCode {
+        match self {
+            DummyControl::Stance => STANCE_KEY,
+            DummyControl::Block => BLOCK_MODE_KEY,
+            DummyControl::AutoCounter => COUNTER_KEY,
+            DummyControl::Record => RECORD_KEY,
+            DummyControl::Replay => REPLAY_KEY,
+            DummyControl::Rewind => REWIND_KEY,
+            DummyControl::Reset(ResetPositions::LeftCorner) => LEFT_CORNER_KEY,
+            DummyControl::Reset(ResetPositions::Midscreen) => MIDSCREEN_KEY,
+            DummyControl::Reset(ResetPositions::RightCorner) => RIGHT_CORNER_KEY,
+        }
+    }
+}
+
+// Sent by the panel's rewind key, button or line for `rewind_training` to
+// act on.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RewindTraining;
+
+fn mirrored(input: ControlInput) -> ControlInput {
+    ControlInput {
+        left: input.right,
+        right: input.left,
+        ..input
+    }
+}
+
+// Runs straight after the hits are worked out on the same tick, so the
+// dummy never misses one however the ticks fall across frames.
+pub fn brace_dummy(
+    tick: Res<SimulationTick>,
+    settings: Res<DummySettings>,
+    mut hits: EventReader<HitLanded>,
+    mut dummies: Query<&mut TrainingDummy>,
+) {
+    for hit in hits.iter() {
+        if let Ok(mut dummy) = dummies.get_mut(hit.defender) {
+            dummy.block_until = tick.0 + BLOCK_AFTER_HIT_TICKS;
+            dummy.counter_pending = settings.auto_counter;
+        }
+    }
+}
+
+pub fn drive_dummy(
+    tick: Res<SimulationTick>,
+    mut settings: ResMut<DummySettings>,
+    mut rng: ResMut<GameRng>,
+    mut dummies: Query<(&mut TrainingDummy, &mut ControlInput, &Opponent)>,
+    opponents: Query<(&CharacterState, &ControlInput), Without<TrainingDummy>>,
+) {
+    for (mut dummy, mut input, opponent) in dummies.iter_mut() {
+        let Ok((opponent_state, opponent_input)) = opponents.get(opponent.0) else {
+            continue;
+        };
+
+        match settings.recorder.state {
+            RecorderState::Recording => {
+                settings.recorder.frames.push(mirrored(*opponent_input));
+                if settings.recorder.frames.len() >= MAX_RECORDING_TICKS {
+                    settings.recorder.state = RecorderState::Idle;
+                }
+            }
+            RecorderState::Replaying(index) => {
+                if let Some(frame) = settings.recorder.frames.get(index).copied() {
+                    *input = frame;
+                    settings.recorder.state = RecorderState::Replaying(index + 1);
+                    continue;
+                }
+                settings.recorder.state = RecorderState::Idle;
+            }
+            RecorderState::Idle => {}
+        }
+
+        let opponent_attacking = MoveId::from_state(opponent_state.player_state).is_some();
+        if opponent_attacking && !dummy.opponent_was_attacking {
+            dummy.random_block = rng.simulation().gen_bool(0.5);
+        }
+        dummy.opponent_was_attacking = opponent_attacking;
+
+        *input = ControlInput::default();
+        match settings.stance {
+            DummyStance::Stand => {}
+            DummyStance::Crouch => input.crouch = true,
+            DummyStance::Jump => input.jump = true,
+        }
+        input.block = match settings.block {
+            DummyBlock::Never => false,
+            DummyBlock::All => true,
+            DummyBlock::AfterFirstHit => tick.0 < dummy.block_until,
+            DummyBlock::Random => dummy.random_block && opponent_attacking,
+        };
+        if dummy.counter_pending && !opponent_attacking {
+            input.block = false;
+            input.punch = true;
+            dummy.counter_pending = false;
+        }
+    }
+}
+
+// Each control goes off from its key or a click on its line of the panel,
+// and rewinding from the gamepad's select button too.
+pub fn dummy_settings_keys(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut settings: ResMut<DummySettings>,
+    mut resets: EventWriter<ResetPositions>,
+    mut rewinds: EventWriter<RewindTraining>,
+    clicked: Query<(&Interaction, &DummyControl), Changed<Interaction>>,
+) {
+    let mut controls: Vec<DummyControl> = DummyControl::ALL
+        .into_iter()
+        .filter(|control| keys.just_pressed(control.key()))
+        .collect();
+    controls.extend(
+        clicked
+            .iter()
+            .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+            .map(|(_, control)| *control),
+    );
+    if gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, REWIND_BUTTON)))
+    {
+        controls.push(DummyControl::Rewind);
+    }
+
+    for control in controls {
+        match control {
+            DummyControl::Stance => {
+                settings.stance = match settings.stance {
+                    DummyStance::Stand => DummyStance::Crouch,
+                    DummyStance::Crouch => DummyStance::Jump,
+                    DummyStance::Jump => DummyStance::Stand,
+                };
+            }
+            DummyControl::Block => {
+                settings.block = match settings.block {
+                    DummyBlock::Never => DummyBlock::All,
+                    DummyBlock::All => DummyBlock::AfterFirstHit,
+                    DummyBlock::AfterFirstHit => DummyBlock::Random,
+                    DummyBlock::Random => DummyBlock::Never,
+                };
+            }
+            DummyControl::AutoCounter => settings.auto_counter = !settings.auto_counter,
+            DummyControl::Record => {
+                let recorder = &mut settings.recorder;
+                if recorder.state == RecorderState::Recording {
+                    recorder.state = RecorderState::Idle;
+                } else {
+                    recorder.frames.clear();
+                    recorder.state = RecorderState::Recording;
+                }
+            }
+            DummyControl::Replay => {
+                if !settings.recorder.frames.is_empty() {
+                    settings.recorder.state = RecorderState::Replaying(0);
+                }
+            }
+            DummyControl::Rewind => rewinds.send(RewindTraining),
+            DummyControl::Reset(reset) => resets.send(reset),
+        }
+    }
+}
+
+pub fn reset_positions(
+    mut resets: EventReader<ResetPositions>,
+    mut fighters: Query<(
+        &Team,
+        &mut Transform,
+        &mut Health,
+        &mut Meter,
+        &mut CharacterState,
+        &mut Knockback,
+        &mut ComboCounter,
+        &mut ControlInput,
+    )>,
+) {
+    let Some(reset) = resets.iter().last().copied() else {
+        return;
+    };
+    for (
+        team,
+        mut transform,
+        mut health,
+        mut meter,
+        mut state,
+        mut knockback,
+        mut combo,
+        mut input,
+    ) in fighters.iter_mut()
+    {
+        *transform = reset.transform(*team);
+        health.current = health.max;
+        meter.current = meter.max;
+        *state = CharacterState::default();
+        *knockback = Knockback::default();
+        *combo = ComboCounter::default();
+        *input = ControlInput::default();
+    }
+}
+
+// Puts both fighters back where they were five seconds ago, or as far back
+// as the snapshots go, to try the same situation again. Pressing it again
+// goes back further. A recording being played on the dummy goes back with
+// them so it lines up again, one being made loses the rewound frames, and
+// the input display starts over.
+pub fn rewind_training(world: &mut World) {
+    let requested = world
+        .resource_mut::<Events<RewindTraining>>()
+        .drain()
+        .count();
+    if requested == 0 {
+        return;
+    }
+    let Some(oldest) = world.resource::<Snapshots>().oldest() else {
+        return;
+    };
+    let now = world.resource::<SimulationTick>().0;
+    let Some(to) = roll_back(world, now.saturating_sub(REWIND_TICKS).max(oldest)) else {
+        return;
+    };
+
+    let rewound = (now - to) as usize;
+    let recorder = &mut world.resource_mut::<DummySettings>().into_inner().recorder;
+    match recorder.state {
+        RecorderState::Replaying(index) => {
+            recorder.state = RecorderState::Replaying(index.saturating_sub(rewound));
+        }
+        RecorderState::Recording => {
+            let kept = recorder.frames.len().saturating_sub(rewound);
+            recorder.frames.truncate(kept);
+        }
+        RecorderState::Idle => {}
+    }
+    world.resource_mut::<InputHistory>().entries.clear();
+}
+
+fn panel_text(value: &str) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size: 18.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+pub fn setup_dummy_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            DummyPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((panel_text(""), DummyPanelTitle));
+            for control in DummyControl::ALL {
+                panel.spawn((clickable(panel_text("")), control));
+            }
+        });
+}
+
+pub fn update_dummy_panel(
+    settings: Res<DummySettings>,
+    strings: Strings,
+    mut titles: Query<&mut Text, (With<DummyPanelTitle>, Without<DummyControl>)>,
+    mut rows: Query<(&DummyControl, &mut Text)>,
+) {
+    if !settings.is_changed() && !strings.changed() {
+        return;
+    }
+    for mut title in titles.iter_mut() {
+        title.sections[0].value = strings.get("training.title");
+    }
+    let recorder = match settings.recorder.state {
+        RecorderState::Idle => strings.fill(
+            "training.recorder.frames",
+            &[("count", &settings.recorder.frames.len())],
+        ),
+        RecorderState::Recording => strings.get("training.recorder.recording"),
+        RecorderState::Replaying(_) => strings.get("training.recorder.replaying"),
+    };
+    for (control, mut text) in rows.iter_mut() {
+        text.sections[0].value = match control {
+            DummyControl::Stance => strings.fill(
+                "training.stance",
+                &[("value", &strings.get(settings.stance.name_key()))],
+            ),
+            DummyControl::Block => strings.fill(
+                "training.block",
+                &[("value", &strings.get(settings.block.name_key()))],
+            ),
+            DummyControl::AutoCounter => {
+                let value = if settings.auto_counter {
+                    "common.on"
+                } else {
+                    "common.off"
+                };
+                strings.fill("training.auto_counter", &[("value", &strings.get(value))])
+            }
+            DummyControl::Record => strings.fill("training.record", &[("value", &recorder)]),
+            DummyControl::Replay => strings.get("training.replay"),
+            DummyControl::Rewind => strings.get("training.rewind"),
+            DummyControl::Reset(ResetPositions::LeftCorner) => {
+                strings.get("training.reset.left_corner")
+            }
+            DummyControl::Reset(ResetPositions::Midscreen) => {
+                strings.get("training.reset.midscreen")
+            }
+            DummyControl::Reset(ResetPositions::RightCorner) => {
+                strings.get("training.reset.right_corner")
+            }
+        };
+    }
+}
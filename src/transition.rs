@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::round::RoundStarted;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TransitionStyle {
+    #[default]
+    Fade,
+    // Slides a solid panel across from the left.
+    Wipe,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    Linear,
+    #[default]
+    EaseInOut,
+    EaseOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransitionConfig {
+    pub style: TransitionStyle,
+    pub easing: Easing,
+    // Seconds for each half: covering, then revealing.
+    pub duration: f32,
+    pub color: Color,
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        TransitionConfig {
+            style: TransitionStyle::Fade,
+            easing: Easing::EaseInOut,
+            duration: 0.4,
+            color: Color::BLACK,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TransitionPhase {
+    #[default]
+    Idle,
+    Covering,
+    // Fully covered until someone calls `reveal`.
+    Covered,
+    Revealing,
+}
+
+// Fullscreen cover used between screens and rounds. Whoever starts a cover
+// listens for `TransitionCovered` to swap what's behind it; after `swap` the
+// reveal follows on its own.
+#[derive(Resource, Default)]
+pub struct ScreenTransition {
+    pub phase: TransitionPhase,
+    pub config: TransitionConfig,
+    timer: Timer,
+    auto_reveal: bool,
+}
+
+impl ScreenTransition {
+    // Starts fully covered, e.g. to hide loading.
+    pub fn covered() -> Self {
+        ScreenTransition {
+            phase: TransitionPhase::Covered,
+            ..default()
+        }
+    }
+
+    pub fn cover(&mut self, config: TransitionConfig) {
+        self.start(TransitionPhase::Covering, config);
+        self.auto_reveal = false;
+    }
+
+    pub fn swap(&mut self, config: TransitionConfig) {
+        self.start(TransitionPhase::Covering, config);
+        self.auto_reveal = true;
+    }
+
+    pub fn reveal(&mut self, config: TransitionConfig) {
+        self.start(TransitionPhase::Revealing, config);
+    }
+
+    pub fn is_busy(&self) -> bool {
+        matches!(self.phase, TransitionPhase::Covering | TransitionPhase::Revealing)
+    }
+
+    fn start(&mut self, phase: TransitionPhase, config: TransitionConfig) {
+        self.phase = phase;
+        self.config = config;
+        self.timer = Timer::from_seconds(config.duration, TimerMode::Once);
+    }
+
+    // 0 is fully clear, 1 fully covered.
+    fn coverage(&self) -> f32 {
+        let t = self.config.easing.apply(self.timer.percent());
+        match self.phase {
+            TransitionPhase::Idle => 0.0,
+            TransitionPhase::Covering => t,
+            TransitionPhase::Covered => 1.0,
+            TransitionPhase::Revealing => 1.0 - t,
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TransitionCovered;
+
+#[derive(Component)]
+pub struct TransitionOverlay;
+
+pub fn setup_transition_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        TransitionOverlay,
+    ));
+}
+
+// Fades in from black once the fight is ready, hiding the loading frames.
+pub fn reveal_fight(mut transition: ResMut<ScreenTransition>) {
+    transition.reveal(TransitionConfig {
+        duration: 0.8,
+        ..default()
+    });
+}
+
+// Fighters snap back to their marks between rounds; cut to black and fade
+// back in over it.
+pub fn reveal_next_round(
+    mut started: EventReader<RoundStarted>,
+    mut transition: ResMut<ScreenTransition>,
+) {
+    if started.iter().any(|round| round.number > 1) {
+        transition.reveal(TransitionConfig {
+            duration: 0.5,
+            ..default()
+        });
+    }
+}
+
+// Real time, so slow motion and pausing don't stretch transitions.
+pub fn drive_transition(
+    time: Res<Time<Real>>,
+    mut transition: ResMut<ScreenTransition>,
+    mut covered: EventWriter<TransitionCovered>,
+    mut overlays: Query<(&mut Style, &mut BackgroundColor), With<TransitionOverlay>>,
+) {
+    if transition.is_busy() && transition.timer.tick(time.delta()).finished() {
+        if transition.phase == TransitionPhase::Covering {
+            covered.send(TransitionCovered);
+            if transition.auto_reveal {
+                let config = transition.config;
+                transition.reveal(config);
+            } else {
+                transition.phase = TransitionPhase::Covered;
+            }
+        } else {
+            transition.phase = TransitionPhase::Idle;
+        }
+    }
+
+    let coverage = transition.coverage();
+    let config = transition.config;
+    for (mut style, mut background) in overlays.iter_mut() {
+        match config.style {
+            TransitionStyle::Fade => {
+                style.width = Val::Percent(100.0);
+                background.0 = config.color.with_a(config.color.a() * coverage);
+            }
+            TransitionStyle::Wipe => {
+                style.width = Val::Percent(100.0 * coverage);
+                background.0 = config.color;
+            }
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::{prelude::*, reflect::TypePath};
+use serde::Deserialize;
+
+use crate::{
+    character::{CharacterState, Player},
+    combat::HitLanded,
+    input::ControlInput,
+    locale::Strings,
+    moves::{MoveId, MovePhase},
+    training::TrainingDummy,
+};
+
+pub const TRIALS_PATH: &str = "game.trials.ron";
+pub const NEXT_TRIAL_KEY: KeyCode = KeyCode::PageDown;
+pub const DEMO_KEY: KeyCode = KeyCode::Home;
+
+// How close the demonstration walks in before it starts attacking.
+const DEMO_RANGE: f32 = 1.5;
+
+// A sequence of moves that has to connect as one true combo on the dummy.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrialDef {
+    pub name: String,
+    pub steps: Vec<MoveId>,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct TrialList {
+    pub trials: Vec<TrialDef>,
+}
+
+#[derive(Resource)]
+pub struct TrialsHandle(pub Handle<TrialList>);
+
+// The player's own fighter plays the trial back, one step at a time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrialDemo {
+    step: usize,
+    // The last step's move has been seen past its startup, so the next
+    // startup is a fresh move rather than the same one.
+    armed: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct ComboTrial {
+    pub index: usize,
+    // Steps of the current trial landed in a row.
+    pub landed: usize,
+    pub complete: bool,
+    pub demo: Option<TrialDemo>,
+    // The landed steps came from the demonstration, so they don't count.
+    pub demonstrated: bool,
+    // Game seconds until the dummy can act again after the last hit.
+    stunned_until: f32,
+    juggled: bool,
+}
+
+impl ComboTrial {
+    fn reset(&mut self) {
+        self.landed = 0;
+        self.complete = false;
+        self.demonstrated = self.demo.is_some();
+    }
+}
+
+#[derive(Component)]
+pub struct TrialPanel;
+
+pub fn load_trials(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(TrialsHandle(asset_server.load(TRIALS_PATH)));
+}
+
+fn current_trial<'a>(
+    trial: &ComboTrial,
+    handle: &TrialsHandle,
+    lists: &'a Assets<TrialList>,
+) -> Option<&'a TrialDef> {
+    lists.get(&handle.0)?.trials.get(trial.index)
+}
+
+pub fn trial_keys(
+    keys: Res<Input<KeyCode>>,
+    handle: Res<TrialsHandle>,
+    lists: Res<Assets<TrialList>>,
+    mut trial: ResMut<ComboTrial>,
+) {
+    let count = lists.get(&handle.0).map_or(0, |list| list.trials.len());
+    if count == 0 {
+        return;
+    }
+    if keys.just_pressed(NEXT_TRIAL_KEY) {
+        trial.index = (trial.index + 1) % count;
+        trial.demo = None;
+        trial.reset();
+    }
+    if keys.just_pressed(DEMO_KEY) {
+        trial.demo = Some(TrialDemo::default());
+        trial.reset();
+    }
+}
+
+// A hit only extends the trial if the dummy was still reeling from the last
+// one: inside its hitstun, or still in the air from a juggle.
+pub fn track_trial(
+    time: Res<Time>,
+    handle: Res<TrialsHandle>,
+    lists: Res<Assets<TrialList>>,
+    mut trial: ResMut<ComboTrial>,
+    mut hits: EventReader<HitLanded>,
+    dummies: Query<&CharacterState, With<TrainingDummy>>,
+) {
+    let Some(def) = current_trial(&trial, &handle, &lists) else {
+        return;
+    };
+    let now = time.elapsed_seconds();
+    for hit in hits.iter() {
+        let Ok(dummy) = dummies.get(hit.defender) else {
+            continue;
+        };
+        if trial.complete || hit.blocked {
+            continue;
+        }
+        let combo = trial.landed > 0 && (now <= trial.stunned_until || trial.juggled);
+        let landed = if combo { trial.landed } else { 0 };
+        trial.landed = if def.steps.get(landed) == Some(&hit.move_id) {
+            landed + 1
+        } else {
+            usize::from(def.steps.first() == Some(&hit.move_id))
+        };
+        trial.stunned_until = now + hit.move_id.data().level.hitstun();
+        trial.juggled = dummy.juggle.is_some();
+        if trial.landed == def.steps.len() {
+            trial.complete = !trial.demonstrated;
+        }
+    }
+    // Once the dummy recovers the combo is over, and after a demonstration
+    // the slate is wiped for the player's own attempt.
+    let recovered = dummies
+        .iter()
+        .all(|dummy| dummy.juggle.is_none() && !dummy.airborne);
+    if trial.demo.is_some() || now <= trial.stunned_until || !recovered {
+        return;
+    }
+    if !trial.complete && trial.landed > 0 {
+        trial.landed = 0;
+        trial.juggled = false;
+    }
+    if trial.demonstrated {
+        trial.demonstrated = false;
+    }
+}
+
+// Drives the player's fighter through the trial: walk into range, then latch
+// each step's attack until it comes out.
+pub fn drive_trial_demo(
+    handle: Res<TrialsHandle>,
+    lists: Res<Assets<TrialList>>,
+    mut trial: ResMut<ComboTrial>,
+    mut players: Query<(&mut ControlInput, &CharacterState, &Transform), With<Player>>,
+    dummies: Query<&Transform, With<TrainingDummy>>,
+) {
+    let Some(mut demo) = trial.demo else {
+        return;
+    };
+    let Some(def) = current_trial(&trial, &handle, &lists) else {
+        return;
+    };
+    let (Ok((mut input, state, transform)), Ok(dummy)) =
+        (players.get_single_mut(), dummies.get_single())
+    else {
+        return;
+    };
+    *input = ControlInput::default();
+    let offset = dummy.translation.x - transform.translation.x;
+    if demo.step == 0 && offset.abs() > DEMO_RANGE {
+        input.right = offset > 0.0;
+        input.left = offset < 0.0;
+        return;
+    }
+
+    let attacking = MoveId::from_state(state.player_state);
+    let in_startup = state.move_phase == Some(MovePhase::Startup);
+    if !in_startup {
+        demo.armed = true;
+    }
+    if demo.armed && in_startup && attacking == def.steps.get(demo.step).copied() {
+        demo.step += 1;
+        demo.armed = false;
+    }
+    match def.steps.get(demo.step) {
+        Some(MoveId::Punch | MoveId::Elbow) => input.punch = true,
+        Some(MoveId::Kick) => input.kick = true,
+        Some(MoveId::ShoulderCharge) => {
+            input.punch = true;
+            input.kick = true;
+        }
+        // Every step is out; finish once the last one has played through.
+        None => {
+            if state.current_animation_timer.is_none() {
+                trial.demo = None;
+            }
+            return;
+        }
+    }
+    trial.demo = Some(demo);
+}
+
+pub fn setup_trial_panel(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            right: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.4)),
+        TrialPanel,
+    ));
+}
+
+pub fn update_trial_panel(
+    trial: Res<ComboTrial>,
+    handle: Res<TrialsHandle>,
+    lists: Res<Assets<TrialList>>,
+    strings: Strings,
+    mut panels: Query<&mut Text, With<TrialPanel>>,
+) {
+    if !trial.is_changed() && !lists.is_changed() && !strings.changed() {
+        return;
+    }
+    let Some(list) = lists.get(&handle.0) else {
+        return;
+    };
+    let Some(def) = list.trials.get(trial.index) else {
+        return;
+    };
+    let mut lines = vec![strings.fill(
+        "trial.title",
+        &[
+            ("number", &(trial.index + 1)),
+            ("count", &list.trials.len()),
+            ("name", &def.name),
+        ],
+    )];
+    for (index, move_id) in def.steps.iter().enumerate() {
+        let mark = if index < trial.landed { "[x]" } else { "[ ]" };
+        let name = strings.get(match move_id {
+            MoveId::Punch => "trial.move.punch",
+            MoveId::Kick => "trial.move.kick",
+            MoveId::Elbow => "trial.move.elbow",
+            MoveId::ShoulderCharge => "trial.move.shoulder_charge",
+        });
+        lines.push(format!("{mark} {name}"));
+    }
+    if trial.complete {
+        lines.push(strings.get("trial.complete"));
+    } else if trial.demo.is_some() {
+        lines.push(strings.get("trial.demo"));
+    }
+    lines.push(strings.get("trial.hint"));
+    for mut text in panels.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
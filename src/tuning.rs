@@ -0,0 +1,195 @@
+use bevy::{asset::LoadState, prelude::*, reflect::TypePath};
+use serde::Deserialize;
+
+use crate::{moves::MoveId, SIMULATION_HZ};
+
+pub const TUNING_PATH: &str = "game.tuning.ron";
+
+// How much each hit in a combo is worth. Hits keep their full damage until
+// `full_damage_hits` have landed, then lose `per_hit` each down to `minimum`.
+// A move already used in the combo is scaled by `repeat` again per use.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ComboScaling {
+    // Seconds after a hit in which the next still counts as the same combo.
+    pub window: f32,
+    pub full_damage_hits: u32,
+    pub per_hit: f32,
+    pub minimum: f32,
+    pub repeat: f32,
+}
+
+impl Default for ComboScaling {
+    fn default() -> Self {
+        ComboScaling {
+            window: 0.6,
+            full_damage_hits: 2,
+            per_hit: 0.1,
+            minimum: 0.3,
+            repeat: 0.85,
+        }
+    }
+}
+
+impl ComboScaling {
+    // Multiplier for the next hit given the moves already in the combo.
+    pub fn scale(&self, combo: &[MoveId], move_id: MoveId) -> f32 {
+        let scaled_hits = (combo.len() as u32 + 1).saturating_sub(self.full_damage_hits);
+        let repeats = combo.iter().filter(|used| **used == move_id).count();
+        let scale = (1.0 - self.per_hit * scaled_hits as f32) * self.repeat.powi(repeats as i32);
+        scale.max(self.minimum)
+    }
+
+    // Whether a hit on `tick` comes too long after the one on `last_hit` to
+    // carry on its combo.
+    pub fn expired(&self, last_hit: u32, tick: u32) -> bool {
+        tick.saturating_sub(last_hit) as f32 > self.window * SIMULATION_HZ as f32
+    }
+}
+
+// Air combos. Launchers throw the defender up at `launch`; each further hit
+// lifts them by `lift`, shrinking by `lift_decay` per hit, while gravity
+// grows by `gravity_per_hit` so juggles can't go on forever.
+#[derive(Deserialize, Debug, Clone)]
+pub struct JuggleTuning {
+    pub launch: f32,
+    pub lift: f32,
+    pub lift_decay: f32,
+    pub gravity_per_hit: f32,
+    // Upward speed of the single bounce when a juggled fighter lands.
+    pub bounce: f32,
+    // Seconds spent on the floor after the bounce.
+    pub knockdown: f32,
+}
+
+impl Default for JuggleTuning {
+    fn default() -> Self {
+        JuggleTuning {
+            launch: 9.0,
+            lift: 5.0,
+            lift_decay: 0.8,
+            gravity_per_hit: 0.15,
+            bounce: 3.0,
+            knockdown: 0.8,
+        }
+    }
+}
+
+// How hard a limb is moving when it connects. Hits at `reference_speed` (in
+// metres a second) do what the move says; faster and slower ones scale their
+// damage and knockback with the speed, between `minimum` and `maximum`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImpactTuning {
+    pub reference_speed: f32,
+    pub minimum: f32,
+    pub maximum: f32,
+}
+
+impl Default for ImpactTuning {
+    fn default() -> Self {
+        ImpactTuning {
+            reference_speed: 6.0,
+            minimum: 0.7,
+            maximum: 1.3,
+        }
+    }
+}
+
+impl ImpactTuning {
+    pub fn scale(&self, speed: f32) -> f32 {
+        (speed / self.reference_speed).clamp(self.minimum, self.maximum)
+    }
+}
+
+// Numbers for the combat rules that designers tweak without a rebuild.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct Tuning {
+    #[serde(default)]
+    pub combo: ComboScaling,
+    #[serde(default)]
+    pub juggle: JuggleTuning,
+    #[serde(default)]
+    pub impact: ImpactTuning,
+}
+
+#[derive(Resource)]
+pub struct TuningHandle(pub Handle<Tuning>);
+
+pub fn load_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(asset_server.load(TUNING_PATH)));
+}
+
+// Whether the fight can start yet. A tuning file that fails to load falls
+// back to the defaults rather than holding up the match.
+pub fn tuning_ready(
+    asset_server: &AssetServer,
+    handle: &TuningHandle,
+    tunings: &Assets<Tuning>,
+) -> bool {
+    if tunings.contains(&handle.0) {
+        return true;
+    }
+    if asset_server.get_load_state(handle.0.id()) == Some(LoadState::Failed) {
+        error!("failed to load {TUNING_PATH}, using default tuning");
+        return true;
+    }
+    false
+}
+
+// The defender's running combo: which moves have hit them and the simulation
+// tick the last one landed on.
+#[derive(Component, Default, Clone, Debug)]
+pub struct ComboCounter {
+    pub moves: Vec<MoveId>,
+    pub last_hit: u32,
+}
+
+impl ComboCounter {
+    pub fn hits(&self) -> usize {
+        self.moves.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combo_keeps_full_damage_for_the_first_hits() {
+        let scaling = ComboScaling::default();
+        assert_eq!(scaling.scale(&[], MoveId::Punch), 1.0);
+        assert_eq!(scaling.scale(&[MoveId::Punch], MoveId::Kick), 1.0);
+    }
+
+    #[test]
+    fn combo_scales_later_hits_and_repeated_moves() {
+        let scaling = ComboScaling::default();
+        let scale = scaling.scale(&[MoveId::Punch, MoveId::Kick], MoveId::Kick);
+        assert!((scale - 0.9 * 0.85).abs() < 1e-5);
+    }
+
+    #[test]
+    fn combo_scaling_bottoms_out_at_the_minimum() {
+        let scaling = ComboScaling::default();
+        let combo = [MoveId::Punch; 12];
+        assert_eq!(scaling.scale(&combo, MoveId::Punch), scaling.minimum);
+    }
+
+    #[test]
+    fn combo_expires_after_its_window() {
+        let scaling = ComboScaling::default();
+        let window = (scaling.window * SIMULATION_HZ as f32) as u32;
+        assert!(!scaling.expired(100, 100 + window));
+        assert!(scaling.expired(100, 101 + window));
+        // A rollback can put the tick before the last hit.
+        assert!(!scaling.expired(100, 50));
+    }
+
+    #[test]
+    fn impact_scales_with_speed_within_limits() {
+        let impact = ImpactTuning::default();
+        assert_eq!(impact.scale(impact.reference_speed), 1.0);
+        assert!((impact.scale(impact.reference_speed * 1.2) - 1.2).abs() < 1e-5);
+        assert_eq!(impact.scale(0.0), impact.minimum);
+        assert_eq!(impact.scale(100.0), impact.maximum);
+    }
+}
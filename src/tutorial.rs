@@ -0,0 +1,249 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{AnimationState, CharacterState, Player},
+    combat::HitLanded,
+    input::ControlInput,
+    locale::Strings,
+    menu::{play_blip, MenuSounds},
+    moves::MoveId,
+    training::TrainingDummy,
+};
+
+// Seconds a movement has to be held to count.
+const HOLD_SECONDS: f32 = 1.0;
+// Real seconds "well done" stays up before the next objective.
+const STEP_PAUSE: f32 = 1.5;
+// The opponent's attack rhythm while the player learns to block.
+const OPPONENT_RANGE: f32 = 1.6;
+const OPPONENT_ATTACK_INTERVAL: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+    WalkForward,
+    WalkBack,
+    Crouch,
+    Jump,
+    LandPunch,
+    LandKick,
+    BlockAttacks,
+}
+
+impl Objective {
+    // What has to happen, and how many times.
+    fn target(&self) -> f32 {
+        match self {
+            Objective::WalkForward | Objective::WalkBack | Objective::Crouch => HOLD_SECONDS,
+            Objective::Jump | Objective::LandPunch | Objective::LandKick => 1.0,
+            Objective::BlockAttacks => 3.0,
+        }
+    }
+
+    fn prompt_key(&self) -> &'static str {
+        match self {
+            Objective::WalkForward => "tutorial.walk_forward",
+            Objective::WalkBack => "tutorial.walk_back",
+            Objective::Crouch => "tutorial.crouch",
+            Objective::Jump => "tutorial.jump",
+            Objective::LandPunch => "tutorial.land_punch",
+            Objective::LandKick => "tutorial.land_kick",
+            Objective::BlockAttacks => "tutorial.block",
+        }
+    }
+
+    // Held movements count up in seconds; everything else in events.
+    fn timed(&self) -> bool {
+        matches!(
+            self,
+            Objective::WalkForward | Objective::WalkBack | Objective::Crouch
+        )
+    }
+}
+
+pub const OBJECTIVES: [Objective; 7] = [
+    Objective::WalkForward,
+    Objective::WalkBack,
+    Objective::Crouch,
+    Objective::Jump,
+    Objective::LandPunch,
+    Objective::LandKick,
+    Objective::BlockAttacks,
+];
+
+// Sent once when the current objective is met.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ObjectiveComplete {
+    pub objective: Objective,
+}
+
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    // Index into OBJECTIVES; past the end once the tutorial is finished.
+    pub step: usize,
+    pub progress: f32,
+    // Running between an objective being met and the next one showing.
+    pub pause: Option<Timer>,
+}
+
+impl Tutorial {
+    pub fn objective(&self) -> Option<Objective> {
+        OBJECTIVES.get(self.step).copied()
+    }
+}
+
+#[derive(Component)]
+pub struct TutorialPrompt;
+
+pub fn setup_tutorial(mut commands: Commands, mut tutorial: ResMut<Tutorial>) {
+    *tutorial = Tutorial::default();
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 30.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(15.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        }),
+        TutorialPrompt,
+    ));
+}
+
+// Counts progress towards the objective from the player's state and the
+// combat events, and reports it the moment it's met.
+pub fn track_tutorial(
+    time: Res<Time>,
+    mut tutorial: ResMut<Tutorial>,
+    mut hits: EventReader<HitLanded>,
+    mut completed: EventWriter<ObjectiveComplete>,
+    players: Query<(Entity, &CharacterState), With<Player>>,
+) {
+    let hits: Vec<HitLanded> = hits.iter().copied().collect();
+    let Some(objective) = tutorial.objective() else {
+        return;
+    };
+    if tutorial.pause.is_some() {
+        return;
+    }
+    let Ok((player, state)) = players.get_single() else {
+        return;
+    };
+    let landed = |move_id| {
+        hits.iter()
+            .filter(|hit| hit.attacker == player && hit.move_id == move_id && !hit.blocked)
+            .count() as f32
+    };
+    let held = |held_state| {
+        if state.player_state == held_state {
+            time.delta_seconds()
+        } else {
+            0.0
+        }
+    };
+    let progress = match objective {
+        Objective::WalkForward => held(AnimationState::Running),
+        Objective::WalkBack => held(AnimationState::RunningBackwards),
+        Objective::Crouch => held(AnimationState::Crouching),
+        Objective::Jump => f32::from(u8::from(state.airborne)),
+        // Up close the punch comes out as an elbow, which counts too.
+        Objective::LandPunch => landed(MoveId::Punch) + landed(MoveId::Elbow),
+        Objective::LandKick => landed(MoveId::Kick),
+        Objective::BlockAttacks => hits
+            .iter()
+            .filter(|hit| hit.defender == player && hit.blocked)
+            .count() as f32,
+    };
+    if progress == 0.0 {
+        return;
+    }
+    tutorial.progress += progress;
+    if tutorial.progress >= objective.target() {
+        tutorial.pause = Some(Timer::from_seconds(STEP_PAUSE, TimerMode::Once));
+        completed.send(ObjectiveComplete { objective });
+    }
+}
+
+// Holds "well done" up for a moment, then moves on to the next objective.
+pub fn advance_tutorial(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    sounds: Res<MenuSounds>,
+    mut tutorial: ResMut<Tutorial>,
+    mut completed: EventReader<ObjectiveComplete>,
+) {
+    if completed.iter().next().is_some() {
+        play_blip(&mut commands, &sounds.confirm);
+    }
+    let Some(pause) = tutorial.pause.as_mut() else {
+        return;
+    };
+    if pause.tick(time.delta()).finished() {
+        tutorial.pause = None;
+        tutorial.step += 1;
+        tutorial.progress = 0.0;
+    }
+}
+
+// The opponent stands still until the blocking lesson, then walks in and
+// attacks on a steady beat so the player can practise reading it.
+pub fn drive_tutorial_opponent(
+    time: Res<Time>,
+    tutorial: Res<Tutorial>,
+    mut cooldown: Local<f32>,
+    mut opponents: Query<(&mut ControlInput, &Transform), With<TrainingDummy>>,
+    players: Query<&Transform, With<Player>>,
+) {
+    let (Ok((mut input, transform)), Ok(player)) =
+        (opponents.get_single_mut(), players.get_single())
+    else {
+        return;
+    };
+    *input = ControlInput::default();
+    if tutorial.objective() != Some(Objective::BlockAttacks) || tutorial.pause.is_some() {
+        *cooldown = OPPONENT_ATTACK_INTERVAL;
+        return;
+    }
+    *cooldown -= time.delta_seconds();
+    let offset = player.translation.x - transform.translation.x;
+    if offset.abs() > OPPONENT_RANGE {
+        input.right = offset > 0.0;
+        input.left = offset < 0.0;
+    } else if *cooldown <= 0.0 {
+        input.punch = true;
+        *cooldown = OPPONENT_ATTACK_INTERVAL;
+    }
+}
+
+pub fn update_tutorial_prompt(
+    tutorial: Res<Tutorial>,
+    strings: Strings,
+    mut prompts: Query<&mut Text, With<TutorialPrompt>>,
+) {
+    if !tutorial.is_changed() && !strings.changed() {
+        return;
+    }
+    let prompt = match tutorial.objective() {
+        None => strings.get("tutorial.finished"),
+        Some(_) if tutorial.pause.is_some() => strings.get("tutorial.well_done"),
+        Some(objective) if objective.timed() => strings.get(objective.prompt_key()),
+        Some(objective) => strings.fill(
+            "tutorial.counted",
+            &[
+                ("objective", &strings.get(objective.prompt_key())),
+                ("count", &(tutorial.progress as u32)),
+                ("target", &(objective.target() as u32)),
+            ],
+        ),
+    };
+    for mut text in prompts.iter_mut() {
+        text.sections[0].value = prompt.clone();
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Character, Team},
+    combat::FightEnded,
+    hud::Hud,
+    locale::Strings,
+    replay::Replay,
+    roster::MatchSetup,
+    transition::{ScreenTransition, TransitionConfig, TransitionCovered},
+    AppState, GameMode,
+};
+
+pub const REMATCH_KEY: KeyCode = KeyCode::Return;
+// The same pairing fought this many times running gets the rival intro.
+pub const RIVAL_REMATCHES: u32 = 3;
+
+// How the sides have been doing in local versus this session: each side's
+// run of match wins, and how many times running the same two characters
+// have been picked against each other.
+#[derive(Resource, Default, Debug)]
+pub struct Rivalry {
+    pub streaks: [u32; 2],
+    pub rematches: u32,
+    last_matchup: Option<(usize, usize)>,
+}
+
+impl Rivalry {
+    pub fn streak(&self, team: Team) -> u32 {
+        self.streaks[team as usize]
+    }
+
+    pub fn is_rivalry(&self) -> bool {
+        self.rematches >= RIVAL_REMATCHES
+    }
+}
+
+#[derive(Component)]
+pub struct RematchPrompt;
+
+pub fn count_rematches(setup: Res<MatchSetup>, mut rivalry: ResMut<Rivalry>) {
+    let matchup = (setup.player, setup.enemy);
+    rivalry.rematches = if rivalry.last_matchup == Some(matchup) {
+        rivalry.rematches + 1
+    } else {
+        0
+    };
+    rivalry.last_matchup = Some(matchup);
+}
+
+// The winner's run goes on and the loser's ends. A draw ends both.
+pub fn record_streaks(mut rivalry: ResMut<Rivalry>, mut ended: EventReader<FightEnded>) {
+    for fight in ended.iter() {
+        for team in [Team::One, Team::Two] {
+            let streak = &mut rivalry.streaks[team as usize];
+            *streak = if fight.winner == Some(team) {
+                *streak + 1
+            } else {
+                0
+            };
+        }
+    }
+}
+
+// Tournament matches hand back to the bracket, gauntlet fights to the next
+// challenger.
+pub fn show_rematch_prompt(mut commands: Commands, mode: Res<GameMode>, strings: Strings) {
+    let prompt = match *mode {
+        GameMode::Tournament => "tournament.continue",
+        GameMode::Gauntlet => "gauntlet.continue",
+        _ => "versus.rematch",
+    };
+    commands.spawn((
+        TextBundle::from_section(
+            strings.get(prompt),
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(20.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        }),
+        RematchPrompt,
+    ));
+}
+
+// Once a versus match is over, confirming fades out and tears the fight down
+// behind the cover, then goes back to the select screen with the last picks
+// still highlighted for a quick rematch. A tournament goes back to its bracket
+// and a gauntlet to its next challenger. Leaving after a replay ends it.
+#[allow(clippy::too_many_arguments)]
+pub fn leave_finished_match(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut leaving: Local<bool>,
+    mut transition: ResMut<ScreenTransition>,
+    mut covered: EventReader<TransitionCovered>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut replay: ResMut<Replay>,
+    leftovers: Query<Entity, Or<(With<Character>, With<Hud>, With<RematchPrompt>)>>,
+) {
+    let confirmed = keys.just_pressed(REMATCH_KEY)
+        || gamepads.iter().any(|gamepad| {
+            buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South))
+        });
+    if !*leaving && confirmed {
+        *leaving = true;
+        transition.cover(TransitionConfig::default());
+    }
+    if !*leaving || covered.iter().next().is_none() {
+        return;
+    }
+    for entity in leftovers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *leaving = false;
+    replay.playing = false;
+    next_state.set(match *mode {
+        GameMode::Tournament => AppState::Bracket,
+        GameMode::Gauntlet => AppState::Challenger,
+        _ => AppState::CharacterSelect,
+    });
+}
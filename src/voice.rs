@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    asset_cache::{character_asset, AssetCache},
+    character::{Character, Health, Team},
+    combat::HitLanded,
+    rng::GameRng,
+    roster::{CharacterDef, Roster},
+    round::{RoundEnded, RoundStarted},
+    sfx::PlaySfx,
+    tuning::ComboCounter,
+};
+
+const VOICE_VOLUME: f32 = 0.8;
+// Seconds before the same fighter speaks again, and before anyone does.
+const VOICE_COOLDOWN: f32 = 6.0;
+const VOICE_GAP: f32 = 1.5;
+// Hits into a combo before the attacker crows about it.
+const COMBO_BARK_HITS: usize = 3;
+// Share of health left when a fighter first admits they're in trouble.
+const LOW_HEALTH_SHARE: f32 = 0.25;
+
+// Something a fighter has a line for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoiceTrigger {
+    RoundStart,
+    Combo,
+    LowHealth,
+    Victory,
+}
+
+// One thing a character can say: the locale key of its subtitle, and the
+// recording of it if there is one.
+#[derive(Deserialize, Debug, Clone)]
+pub struct VoiceLine {
+    pub text: String,
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+// A character's lines for each trigger. One is picked at random each time.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct VoiceSet {
+    #[serde(default)]
+    pub round_start: Vec<VoiceLine>,
+    #[serde(default)]
+    pub combo: Vec<VoiceLine>,
+    #[serde(default)]
+    pub low_health: Vec<VoiceLine>,
+    #[serde(default)]
+    pub victory: Vec<VoiceLine>,
+}
+
+impl VoiceSet {
+    pub fn lines(&self, trigger: VoiceTrigger) -> &[VoiceLine] {
+        match trigger {
+            VoiceTrigger::RoundStart => &self.round_start,
+            VoiceTrigger::Combo => &self.combo,
+            VoiceTrigger::LowHealth => &self.low_health,
+            VoiceTrigger::Victory => &self.victory,
+        }
+    }
+
+    // Paths of every line that has a recording.
+    pub fn recordings(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.round_start,
+            &self.combo,
+            &self.low_health,
+            &self.victory,
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|line| line.sound.as_deref())
+    }
+}
+
+// Name a voice recording is cached under for a character.
+pub fn voice_asset(id: &str, path: &str) -> String {
+    character_asset(id, &format!("voice.{path}"))
+}
+
+// A line a fighter has just said, for the subtitles.
+#[derive(Event, Clone, Debug)]
+pub struct Barked {
+    pub speaker: String,
+    pub text: String,
+}
+
+// When the fighter may next speak, and whether they've already said they're
+// hurt this round.
+#[derive(Component, Default)]
+pub struct Voice {
+    next_line: f32,
+    low_health_said: bool,
+}
+
+// Collects this frame's reasons to speak, then lets through the ones the
+// cooldowns allow. A match-winning line is never held back.
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_barks(
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    cache: Res<AssetCache>,
+    roster: Res<Roster>,
+    characters: Res<Assets<CharacterDef>>,
+    mut quiet_until: Local<f32>,
+    mut round_started: EventReader<RoundStarted>,
+    mut hits: EventReader<HitLanded>,
+    mut round_ended: EventReader<RoundEnded>,
+    mut sounds: EventWriter<PlaySfx>,
+    mut barked: EventWriter<Barked>,
+    mut fighters: Query<(Entity, &Name, &Character, &Team, &Health, &mut Voice)>,
+    combos: Query<&ComboCounter>,
+) {
+    let now = time.elapsed_seconds();
+    let mut wanted = Vec::new();
+    if round_started.iter().next().is_some() {
+        let mut speakers = Vec::new();
+        for (entity, .., mut voice) in fighters.iter_mut() {
+            voice.low_health_said = false;
+            speakers.push(entity);
+        }
+        if !speakers.is_empty() {
+            let speaker = speakers[rng.cosmetic().gen_range(0..speakers.len())];
+            wanted.push((speaker, VoiceTrigger::RoundStart));
+        }
+    }
+    for hit in hits.iter().filter(|hit| !hit.blocked) {
+        if combos
+            .get(hit.defender)
+            .is_ok_and(|combo| combo.hits() == COMBO_BARK_HITS)
+        {
+            wanted.push((hit.attacker, VoiceTrigger::Combo));
+        }
+    }
+    for (entity, _, _, _, health, mut voice) in fighters.iter_mut() {
+        let hurt = health.current > 0.0 && health.current <= health.max * LOW_HEALTH_SHARE;
+        if hurt && !voice.low_health_said {
+            voice.low_health_said = true;
+            wanted.push((entity, VoiceTrigger::LowHealth));
+        }
+    }
+    for winner in round_ended
+        .iter()
+        .filter(|round| round.decisive)
+        .filter_map(|round| round.winner)
+    {
+        for (entity, _, _, team, ..) in fighters.iter() {
+            if *team == winner {
+                wanted.push((entity, VoiceTrigger::Victory));
+            }
+        }
+    }
+
+    for (fighter, trigger) in wanted {
+        let urgent = trigger == VoiceTrigger::Victory;
+        let Ok((_, name, character, _, _, mut voice)) = fighters.get_mut(fighter) else {
+            continue;
+        };
+        if !urgent && (now < *quiet_until || now < voice.next_line) {
+            continue;
+        }
+        let Some(def) = characters.get(&character.0) else {
+            continue;
+        };
+        let lines = def.voice.lines(trigger);
+        if lines.is_empty() {
+            continue;
+        }
+        let line = &lines[rng.cosmetic().gen_range(0..lines.len())];
+        if let (Some(sound), Some(id)) = (&line.sound, roster.id(&character.0)) {
+            sounds.send(PlaySfx::new(
+                cache.get(&voice_asset(id, sound)),
+                VOICE_VOLUME,
+            ));
+        }
+        barked.send(Barked {
+            speaker: name.to_string(),
+            text: line.text.clone(),
+        });
+        voice.next_line = now + VOICE_COOLDOWN;
+        *quiet_until = now + VOICE_GAP;
+    }
+}
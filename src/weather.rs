@@ -0,0 +1,203 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+
+use crate::stage::{Weather, WeatherEffect};
+
+const FOG_SIZE: Vec2 = Vec2::new(40.0, 4.0);
+const FOG_DRIFT: f32 = 2.0;
+
+#[derive(Component)]
+pub struct FogLayer {
+    pub base: Vec3,
+    pub alpha: f32,
+    pub speed: f32,
+    pub phase: f32,
+}
+
+pub fn spawn_fog(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    weather: Query<(Entity, &Weather), Added<Weather>>,
+) {
+    for (entity, weather) in &weather {
+        let WeatherEffect::Fog { layers, color } = weather.0 else {
+            continue;
+        };
+        let (r, g, b, a) = color;
+        let mesh = meshes.add(Mesh::from(shape::Quad::new(FOG_SIZE)));
+        commands.entity(entity).with_children(|parent| {
+            for layer in 0..layers {
+                let depth = layer as f32;
+                let base = Vec3::new(0.0, 0.8 + depth * 0.4, -3.0 - depth * 2.0);
+                parent.spawn((
+                    PbrBundle {
+                        mesh: mesh.clone(),
+                        // Each layer animates its own alpha.
+                        material: materials.add(StandardMaterial {
+                            base_color: Color::rgba(r, g, b, a),
+                            alpha_mode: AlphaMode::Blend,
+                            unlit: true,
+                            ..default()
+                        }),
+                        transform: Transform::from_translation(base),
+                        ..default()
+                    },
+                    NotShadowCaster,
+                    FogLayer {
+                        base,
+                        alpha: a,
+                        speed: 0.1 + depth * 0.05,
+                        phase: depth * 1.7,
+                    },
+                ));
+            }
+        });
+    }
+}
+
+pub fn drift_fog(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut layers: Query<(&FogLayer, &Handle<StandardMaterial>, &mut Transform)>,
+) {
+    let t = time.elapsed_seconds();
+    for (layer, material, mut transform) in layers.iter_mut() {
+        let wave = (t * layer.speed + layer.phase).sin();
+        transform.translation = layer.base + Vec3::X * wave * FOG_DRIFT;
+        let shimmer = 0.75 + 0.25 * (t * layer.speed * 2.0 + layer.phase).cos();
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_a(layer.alpha * shimmer);
+        }
+    }
+}
+
+#[cfg(feature = "particles")]
+pub use particles::*;
+
+#[cfg(feature = "particles")]
+mod particles {
+    use bevy::prelude::*;
+    use bevy_hanabi::prelude::*;
+
+    use crate::{
+        settings::Settings,
+        stage::{Weather, WeatherEffect},
+    };
+
+    // Box above the stage the falling effects spawn in.
+    const WEATHER_EXTENT: Vec3 = Vec3::new(16.0, 4.0, 8.0);
+    const WEATHER_HEIGHT: f32 = 7.0;
+
+    struct Particles {
+        name: &'static str,
+        rate: f32,
+        extent: Vec3,
+        velocity: Vec3,
+        lifetime: f32,
+        size: f32,
+        color: Vec4,
+    }
+
+    impl Particles {
+        fn effect(&self) -> EffectAsset {
+            let writer = ExprWriter::new();
+            let position = (writer.rand(VectorType::VEC3F) - writer.lit(Vec3::splat(0.5)))
+                * writer.lit(self.extent);
+            let init_position = SetAttributeModifier::new(Attribute::POSITION, position.expr());
+            let init_velocity =
+                SetAttributeModifier::new(Attribute::VELOCITY, writer.lit(self.velocity).expr());
+            let init_lifetime =
+                SetAttributeModifier::new(Attribute::LIFETIME, writer.lit(self.lifetime).expr());
+
+            let mut gradient = Gradient::new();
+            gradient.add_key(0.0, self.color);
+            gradient.add_key(0.9, self.color);
+            gradient.add_key(1.0, self.color.truncate().extend(0.0));
+
+            let capacity = (self.rate * self.lifetime * 1.5).ceil().max(32.0) as u32;
+            EffectAsset::new(capacity, Spawner::rate(self.rate.into()), writer.finish())
+                .with_name(self.name)
+                .init(init_position)
+                .init(init_velocity)
+                .init(init_lifetime)
+                .render(ColorOverLifetimeModifier { gradient })
+                .render(SetSizeModifier {
+                    size: Vec2::splat(self.size).into(),
+                    screen_space_size: false,
+                })
+        }
+    }
+
+    fn spawn_particles(
+        parent: &mut ChildBuilder,
+        effects: &mut Assets<EffectAsset>,
+        particles: Particles,
+        height: f32,
+    ) {
+        parent.spawn(ParticleEffectBundle {
+            effect: ParticleEffect::new(effects.add(particles.effect())),
+            transform: Transform::from_xyz(0.0, height, 0.0),
+            ..default()
+        });
+    }
+
+    // Rain splashes are a second emitter scattered over the floor rather
+    // than real collisions, which this version of hanabi can't spawn from.
+    pub fn spawn_weather_particles(
+        mut commands: Commands,
+        mut effects: ResMut<Assets<EffectAsset>>,
+        settings: Res<Settings>,
+        weather: Query<(Entity, &Weather), Added<Weather>>,
+    ) {
+        let density = settings.quality.particle_density();
+        for (entity, weather) in &weather {
+            commands.entity(entity).with_children(|parent| match weather.0 {
+                WeatherEffect::CherryBlossoms { rate } => spawn_particles(
+                    parent,
+                    &mut effects,
+                    Particles {
+                        name: "cherry_blossoms",
+                        rate: rate * density,
+                        extent: WEATHER_EXTENT,
+                        velocity: Vec3::new(0.4, -0.7, 0.0),
+                        lifetime: 12.0,
+                        size: 0.06,
+                        color: Vec4::new(1.0, 0.7, 0.8, 1.0),
+                    },
+                    WEATHER_HEIGHT,
+                ),
+                WeatherEffect::Rain { rate } => {
+                    spawn_particles(
+                        parent,
+                        &mut effects,
+                        Particles {
+                            name: "rain",
+                            rate: rate * density,
+                            extent: WEATHER_EXTENT,
+                            velocity: Vec3::new(0.0, -14.0, 0.0),
+                            lifetime: 0.6,
+                            size: 0.02,
+                            color: Vec4::new(0.7, 0.75, 0.9, 0.6),
+                        },
+                        WEATHER_HEIGHT,
+                    );
+                    spawn_particles(
+                        parent,
+                        &mut effects,
+                        Particles {
+                            name: "rain_splashes",
+                            rate: rate * density * 0.5,
+                            extent: WEATHER_EXTENT * Vec3::new(1.0, 0.0, 1.0),
+                            velocity: Vec3::new(0.0, 0.6, 0.0),
+                            lifetime: 0.2,
+                            size: 0.04,
+                            color: Vec4::new(0.8, 0.85, 1.0, 0.5),
+                        },
+                        0.02,
+                    );
+                }
+                WeatherEffect::Fog { .. } => {}
+            });
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use ninja_vs_pirates::{
+    character::{CharacterState, Health, Team, MAX_HEALTH},
+    combat::HitLanded,
+    headless::{headless_app, run_ticks, wait_for_fight, ScriptedInput, ScriptedInputs},
+    input::ControlInput,
+    moves::MoveId,
+    round::{MatchRules, Round, RoundPhase},
+    snapshot::{roll_back, Snapshots},
+    tuning::ImpactTuning,
+    SimulationTick,
+};
+
+// Origins closer than this get the elbow instead of the punch.
+const PUNCH_GAP: f32 = 1.0;
+// Close enough to throw.
+const THROW_GAP: f32 = 0.8;
+
+fn start(rules: MatchRules, gap: f32) -> App {
+    let mut app = headless_app(ScriptedInputs::default());
+    app.insert_resource(rules);
+    wait_for_fight(&mut app);
+    // Let the colliders go on before anyone moves.
+    run_ticks(&mut app, 2);
+    let mut fighters = app.world.query::<(&Team, &mut Transform)>();
+    for (team, mut transform) in fighters.iter_mut(&mut app.world) {
+        *transform = team.start_transform();
+        transform.translation.x = match team {
+            Team::One => -gap / 2.0,
+            Team::Two => gap / 2.0,
+        };
+    }
+    app
+}
+
+fn rules() -> MatchRules {
+    MatchRules {
+        intro_ticks: 0,
+        ..default()
+    }
+}
+
+// Presses `input` for one tick, `delay` ticks from now.
+fn press(app: &mut App, delay: u32, team: Team, input: ControlInput) {
+    let tick = app.world.resource::<SimulationTick>().0 + delay;
+    let mut script = app.world.resource_mut::<ScriptedInputs>();
+    script.0.push(ScriptedInput { tick, team, input });
+    script.0.push(ScriptedInput {
+        tick: tick + 1,
+        team,
+        input: ControlInput::default(),
+    });
+}
+
+fn health(app: &mut App, team: Team) -> f32 {
+    let mut fighters = app.world.query::<(&Team, &Health)>();
+    fighters
+        .iter(&app.world)
+        .find(|(other, _)| **other == team)
+        .map(|(_, health)| health.current)
+        .unwrap()
+}
+
+fn state(app: &mut App, team: Team) -> CharacterState {
+    let mut fighters = app.world.query::<(&Team, &CharacterState)>();
+    fighters
+        .iter(&app.world)
+        .find(|(other, _)| **other == team)
+        .map(|(_, state)| state.clone())
+        .unwrap()
+}
+
+// The hits that land over the next `ticks` ticks, with the tick each landed
+// on and its damage.
+fn landed_hits(app: &mut App, ticks: u32) -> Vec<(u32, f32)> {
+    let mut landed = Vec::new();
+    for _ in 0..ticks {
+        app.update();
+        let tick = app.world.resource::<SimulationTick>().0;
+        let hits = app.world.resource::<Events<HitLanded>>();
+        landed.extend(
+            hits.iter_current_update_events()
+                .map(|hit| (tick, hit.damage)),
+        );
+    }
+    landed
+}
+
+const PUNCH: ControlInput = ControlInput {
+    left: false,
+    right: false,
+    punch: true,
+    kick: false,
+    block: false,
+    crouch: false,
+    jump: false,
+    tag: false,
+    throw: false,
+};
+
+const THROW: ControlInput = ControlInput {
+    punch: false,
+    throw: true,
+    ..PUNCH
+};
+
+#[test]
+fn punch_in_range_deals_its_damage() {
+    let mut app = start(rules(), PUNCH_GAP);
+    press(&mut app, 1, Team::One, PUNCH);
+    run_ticks(&mut app, 40);
+    // The limb snaps out in a tick, as fast as the impact scaling goes.
+    let damage = MoveId::Punch.data().damage * ImpactTuning::default().maximum;
+    assert!((health(&mut app, Team::Two) - (MAX_HEALTH - damage)).abs() < 1e-3);
+    assert_eq!(health(&mut app, Team::One), MAX_HEALTH);
+}
+
+#[test]
+fn punch_out_of_range_whiffs() {
+    let mut app = start(rules(), 3.0);
+    press(&mut app, 1, Team::One, PUNCH);
+    run_ticks(&mut app, 40);
+    assert_eq!(health(&mut app, Team::Two), MAX_HEALTH);
+}
+
+#[test]
+fn knockout_ends_the_round() {
+    let mut app = start(
+        MatchRules {
+            starting_health: 5.0,
+            ..rules()
+        },
+        PUNCH_GAP,
+    );
+    press(&mut app, 1, Team::One, PUNCH);
+    run_ticks(&mut app, 40);
+    let round = app.world.resource::<Round>();
+    assert_eq!(round.phase, RoundPhase::Over);
+    assert_eq!(round.wins(Team::One), 1);
+    assert_eq!(round.wins(Team::Two), 0);
+}
+
+#[test]
+fn time_over_draw_moves_on_to_the_next_round() {
+    let mut app = start(
+        MatchRules {
+            round_time: Some(0.5),
+            ..rules()
+        },
+        3.0,
+    );
+    run_ticks(&mut app, 40);
+    let round = app.world.resource::<Round>();
+    assert_eq!(round.phase, RoundPhase::Over);
+    // A draw scores for both.
+    assert_eq!(round.wins, [1, 1]);
+    assert!(!round.decided);
+    for _ in 0..600 {
+        run_ticks(&mut app, 1);
+        if app.world.resource::<Round>().number == 2 {
+            return;
+        }
+    }
+    panic!("the second round never started");
+}
+
+#[test]
+fn throw_lands_when_not_broken() {
+    let mut app = start(rules(), THROW_GAP);
+    press(&mut app, 1, Team::One, THROW);
+    run_ticks(&mut app, 30);
+    assert!(health(&mut app, Team::Two) < MAX_HEALTH);
+    assert!(state(&mut app, Team::Two).downed);
+}
+
+#[test]
+fn throw_broken_inside_the_window_does_nothing() {
+    let mut app = start(rules(), THROW_GAP);
+    press(&mut app, 1, Team::One, THROW);
+    press(&mut app, 6, Team::Two, THROW);
+    run_ticks(&mut app, 30);
+    assert_eq!(health(&mut app, Team::Two), MAX_HEALTH);
+    assert!(!state(&mut app, Team::Two).downed);
+    assert!(state(&mut app, Team::Two).grabbed.is_none());
+}
+
+#[test]
+fn seeking_back_to_a_hit_lands_it_again() {
+    let mut app = start(rules(), PUNCH_GAP);
+    // Snapshot the fighters where they were put, not where they spawned.
+    app.world.resource_mut::<Snapshots>().clear();
+    press(&mut app, 5, Team::One, PUNCH);
+    let mut hit = None;
+    for _ in 0..40 {
+        hit = landed_hits(&mut app, 1).pop();
+        if hit.is_some() {
+            break;
+        }
+    }
+    let (tick, damage) = hit.expect("the punch never landed");
+    // Straight back, with the fist still in the other fighter's face.
+    let at = app.world.resource::<Snapshots>().latest_at(tick).unwrap();
+    assert_eq!(roll_back(&mut app.world, at), Some(at));
+    assert_eq!(landed_hits(&mut app, tick - at + 10), vec![(tick, damage)]);
+}